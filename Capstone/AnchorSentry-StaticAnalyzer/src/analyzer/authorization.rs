@@ -0,0 +1,360 @@
+use quote::ToTokens;
+use serde::Serialize;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprMethodCall};
+use crate::analyzer::constraints::{expr_to_string, mentions_ident, AccountConstraints, Bump};
+use crate::analyzer::normalize::program_handlers;
+use crate::report::knowledge_base::Location;
+use crate::state::{NormalizedAccountField, NormalizedAccountStruct, NormalizedProgram, SourceFile};
+
+/// Who can call each handler of a program and what it lets them touch
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProgramAuthorization {
+    pub program: String,
+    pub instructions: Vec<InstructionAuthorization>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstructionAuthorization {
+    pub instruction: String,
+    // Accounts struct of `Context<X>`
+    pub context: String,
+    // false when no Accounts struct named `context` was found, signers and accounts are then unknown
+    pub context_found: bool,
+    // `seed: u64`
+    pub args: Vec<String>,
+    // methods of the Accounts impl the handler calls, `ctx.accounts.deposit(amount)`
+    pub calls: Vec<String>,
+    pub signers: Vec<SignerAuthority>,
+    pub accounts: Vec<AccountAccess>,
+    pub location: Location,
+}
+
+/// A signer and the stored keys or derivations it is checked against
+#[derive(Debug, Clone, Serialize)]
+pub struct SignerAuthority {
+    pub account: String,
+    // `escrow.maker (has_one)`, empty when any signer is accepted
+    pub checked_against: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountAccess {
+    pub name: String,
+    // `Account<Escrow>`
+    pub ty: String,
+    pub signer: bool,
+    pub writable: bool,
+    // seeds of a PDA, `b"escrow"`, `maker.key().as_ref()`
+    pub seeds: Option<Vec<String>>,
+    // `bump` or `bump = escrow.bump`
+    pub bump: Option<String>,
+    // every constraint gating the account, `has_one = maker`, `token::authority = escrow`
+    pub constraints: Vec<String>,
+}
+
+/// `ctx.accounts.deposit(amount)` -> `deposit`
+#[derive(Default)]
+struct CallCollector {
+    calls: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for CallCollector {
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        if let Expr::Field(f) = &*expr.receiver
+            && matches!(&f.member, syn::Member::Named(m) if m == "accounts") {
+            let method = expr.method.to_string();
+            if !self.calls.contains(&method) {
+                self.calls.push(method);
+            }
+        }
+        visit::visit_expr_method_call(self, expr);
+    }
+}
+
+fn compact(e: &Expr) -> String {
+    expr_to_string(e).replace(' ', "")
+}
+
+fn is_signer(field: &NormalizedAccountField) -> bool {
+    field.base_type == "Signer" || field.constraints.signer.is_some()
+}
+
+fn is_writable(c: &AccountConstraints) -> bool {
+    c.is_mut() || c.is_init() || c.zero || c.close.is_some() || c.realloc.is_some()
+}
+
+/// `Account<'info, Escrow>` -> `Account<Escrow>`
+fn type_label(field: &NormalizedAccountField) -> String {
+    let generics: Vec<&str> = field.generic_args.iter().filter(|g| !g.starts_with('\'')).map(String::as_str).collect();
+    if generics.is_empty() { field.base_type.clone() } else { format!("{}<{}>", field.base_type, generics.join(", ")) }
+}
+
+/// Constraints as written, `has_one = maker`, `constraint = a == b`; `mut` and `signer` are shown as access
+fn constraint_list(c: &AccountConstraints) -> Vec<String> {
+    let mut out = Vec::new();
+    let flags = [("init", c.init), ("init_if_needed", c.init_if_needed), ("zero", c.zero), ("executable", c.executable)];
+    out.extend(flags.iter().filter(|(_, set)| *set).map(|(name, _)| name.to_string()));
+    out.extend(c.has_one.iter().filter_map(|h| h.expr.as_ref()).map(|e| format!("has_one = {}", compact(e))));
+    out.extend(c.constraint.iter().filter_map(|h| h.expr.as_ref()).map(|e| format!("constraint = {}", compact(e))));
+    if let Some(e) = c.address.as_ref().and_then(|a| a.expr.as_ref()) {
+        out.push(format!("address = {}", compact(e)));
+    }
+    if let Some(e) = c.owner.as_ref().and_then(|a| a.expr.as_ref()) {
+        out.push(format!("owner = {}", compact(e)));
+    }
+    for n in c.token.iter().chain(&c.mint).chain(&c.associated_token).chain(&c.other) {
+        let key = if n.namespace.is_empty() { n.key.clone() } else { format!("{}::{}", n.namespace, n.key) };
+        match &n.value {
+            Some(v) => out.push(format!("{} = {}", key, compact(v))),
+            None => out.push(key),
+        }
+    }
+    if let Some(e) = &c.close {
+        out.push(format!("close = {}", compact(e)));
+    }
+    out
+}
+
+/// `escrow.maker` style references to the stored fields of other accounts
+fn stored_refs(text: &str, s: &NormalizedAccountStruct, signer: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for other in s.fields.iter().filter(|f| f.name != signer) {
+        let prefix = format!("{}.", other.name);
+        for (i, _) in text.match_indices(&prefix) {
+            if text[..i].chars().last().is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+                continue;
+            }
+            let member: String = text[i + prefix.len()..].chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+            let r = format!("{}.{}", other.name, member);
+            if !member.is_empty() && member != "key" && !out.contains(&r) {
+                out.push(r);
+            }
+        }
+    }
+    out
+}
+
+/// How the Accounts struct and the code it runs tie `signer` to stored state
+fn signer_checks(s: &NormalizedAccountStruct, signer: &str, bodies: &[(String, String)]) -> Vec<String> {
+    let signer_key = format!("{}.key()", signer);
+    let mut out = Vec::new();
+    for field in &s.fields {
+        let c = &field.constraints;
+        if c.has_one_targets().iter().any(|t| t == signer) {
+            out.push(format!("{}.{} (has_one)", field.name, signer));
+        }
+        for e in c.constraint.iter().filter_map(|h| h.expr.as_ref()) {
+            let text = compact(e);
+            if text.contains(&signer_key) {
+                out.extend(stored_refs(&text, s, signer).into_iter().map(|r| format!("{} (constraint on {})", r, field.name)));
+            }
+        }
+        if field.name == signer
+            && let Some(e) = c.address.as_ref().and_then(|a| a.expr.as_ref()) {
+            out.push(format!("{} (address)", compact(e)));
+        }
+        if field.name != signer && c.seeds.iter().flatten().any(|e| mentions_ident(&compact(e), signer)) {
+            out.push(format!("{} seeds", field.name));
+        }
+        for key in ["authority", "owner"] {
+            let owner = c.namespaced("token", key).or_else(|| c.namespaced("associated_token", key));
+            if owner.is_some_and(|e| compact(e) == signer) {
+                out.push(format!("{} {}", field.name, key));
+            }
+        }
+    }
+    // require_keys_eq!(self.escrow.maker, self.maker.key()) in a called method
+    let self_key = format!("self.{}", signer_key);
+    for (method, body) in bodies {
+        for stmt in body.split(';').filter(|st| st.contains("==") || st.contains("!=") || st.contains("eq!(")) {
+            if !(stmt.contains(&self_key) || stmt.contains(&format!("accounts.{}", signer_key))) {
+                continue;
+            }
+            let stmt = stmt.replace("self.", "").replace("ctx.accounts.", "");
+            out.extend(stored_refs(&stmt, s, signer).into_iter().map(|r| format!("{} (checked in {})", r, method)));
+        }
+    }
+    let mut unique: Vec<String> = Vec::new();
+    for check in out {
+        if !unique.contains(&check) {
+            unique.push(check);
+        }
+    }
+    unique
+}
+
+fn account_access(field: &NormalizedAccountField) -> AccountAccess {
+    let c = &field.constraints;
+    AccountAccess {
+        name: field.name.clone(),
+        ty: type_label(field),
+        signer: is_signer(field),
+        writable: is_writable(c),
+        seeds: c.seeds.as_ref().map(|seeds| seeds.iter().map(compact).collect()),
+        bump: c.bump.as_ref().map(|b| match b {
+            Bump::Canonical => "bump".to_string(),
+            Bump::Provided(e) => format!("bump = {}", compact(e)),
+        }),
+        constraints: constraint_list(c),
+    }
+}
+
+/// Summarizes every handler of the `#[program]` module from its Accounts struct and the methods it calls
+pub fn program_authorization(program: &NormalizedProgram, files: &[SourceFile]) -> ProgramAuthorization {
+    let mut instructions = Vec::new();
+    for handler in &program.functions_args {
+        let s = program.account_structs.iter().find(|s| s.name == handler.context);
+
+        let mut collector = CallCollector::default();
+        // bodies of the handler and the methods it calls, for checks done in code
+        let mut bodies: Vec<(String, String)> = Vec::new();
+        if let Some(item) = files
+            .iter()
+            .filter(|f| f.path == handler.file)
+            .flat_map(|f| program_handlers(&f.ast.items))
+            .find(|f| f.sig.ident == handler.name.as_str()) {
+            collector.visit_item_fn(item);
+            bodies.push((handler.name.clone(), item.block.to_token_stream().to_string().replace(' ', "")));
+        }
+        let (signers, accounts) = match s {
+            Some(s) => {
+                bodies.extend(
+                    program
+                        .impls_of(&s.name)
+                        .flat_map(|i| &i.methods)
+                        .filter(|m| collector.calls.contains(&m.sig.ident.to_string()))
+                        .map(|m| (m.sig.ident.to_string(), m.block.to_token_stream().to_string().replace(' ', ""))),
+                );
+                let signers = s
+                    .fields
+                    .iter()
+                    .filter(|f| is_signer(f))
+                    .map(|f| SignerAuthority { account: f.name.clone(), checked_against: signer_checks(s, &f.name, &bodies) })
+                    .collect();
+                (signers, s.fields.iter().map(account_access).collect())
+            }
+            // still listed, the struct may live in a file that wasn't analyzed
+            None => (Vec::new(), Vec::new()),
+        };
+
+        instructions.push(InstructionAuthorization {
+            instruction: handler.name.clone(),
+            context: handler.context.clone(),
+            context_found: s.is_some(),
+            args: handler.args.iter().map(|a| format!("{}: {}", a.name, a.ty)).collect(),
+            calls: collector.calls,
+            signers,
+            accounts,
+            location: Location::new(&handler.file, handler.line, handler.column),
+        });
+    }
+
+    ProgramAuthorization { program: program.name.clone(), instructions }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+
+    const ESCROW: &str = r#"
+        #[program]
+        pub mod escrow {
+            use super::*;
+            pub fn take(ctx: Context<'_, '_, '_, 'info, Take<'info>>) -> Result<()> {
+                ctx.accounts.check()?;
+                ctx.accounts.withdraw()
+            }
+            pub fn refund(ctx: Context<Refund>) -> Result<()> {
+                Ok(())
+            }
+            pub fn update(ctx: Context<Update>, fee: u16) -> Result<()> {
+                Ok(())
+            }
+            pub fn migrate(ctx: Context<Migrate>) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        #[account]
+        pub struct Escrow { pub maker: Pubkey, pub taker: Pubkey }
+        #[account]
+        pub struct Config { pub admin: Pubkey }
+
+        #[derive(Accounts)]
+        pub struct Take<'info> {
+            #[account(mut)]
+            pub taker: Signer<'info>,
+            #[account(mut)]
+            pub escrow: Account<'info, Escrow>,
+            pub config: Account<'info, Config>,
+        }
+
+        impl<'info> Take<'info> {
+            pub fn check(&self) -> Result<()> {
+                require_keys_eq!(self.escrow.taker, self.taker.key(), E::Taker);
+                require_keys_eq!(self.config.admin, self.taker.key(), E::Admin);
+                require_keys_eq!(self.escrow.taker, self.taker.key(), E::Taker);
+                Ok(())
+            }
+            pub fn withdraw(&mut self) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        #[derive(Accounts)]
+        pub struct Refund<'info> {
+            #[account(mut)]
+            pub maker: Signer<'info>,
+            #[account(mut, close = maker, has_one = maker)]
+            pub escrow: Account<'info, Escrow>,
+        }
+
+        #[derive(Accounts)]
+        pub struct Update<'info> {
+            pub admin: Signer<'info>,
+            #[account(mut, constraint = config.admin == admin.key())]
+            pub config: Account<'info, Config>,
+        }
+    "#;
+
+    fn authorization() -> ProgramAuthorization {
+        let files = [SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(ESCROW).unwrap(), source: ESCROW.to_string() }];
+        let program = normalize_program(&files);
+        program_authorization(&program, &files)
+    }
+
+    fn instruction<'a>(auth: &'a ProgramAuthorization, name: &str) -> &'a InstructionAuthorization {
+        auth.instructions.iter().find(|ix| ix.instruction == name).unwrap()
+    }
+
+    #[test]
+    fn every_handler_is_listed() {
+        let auth = authorization();
+        let names: Vec<&str> = auth.instructions.iter().map(|ix| ix.instruction.as_str()).collect();
+        assert_eq!(names, vec!["take", "refund", "update", "migrate"]);
+
+        // the Accounts struct is the last type argument, not the first lifetime
+        let take = instruction(&auth, "take");
+        assert_eq!(take.context, "Take");
+        assert!(take.context_found);
+        assert_eq!(take.calls, vec!["check", "withdraw"]);
+
+        let migrate = instruction(&auth, "migrate");
+        assert_eq!(migrate.context, "Migrate");
+        assert!(!migrate.context_found);
+        assert!(migrate.accounts.is_empty() && migrate.signers.is_empty());
+    }
+
+    #[test]
+    fn signer_bindings() {
+        let auth = authorization();
+        let checks = |ix: &str| instruction(&auth, ix).signers[0].checked_against.clone();
+        assert_eq!(checks("refund"), vec!["escrow.maker (has_one)"]);
+        assert_eq!(checks("update"), vec!["config.admin (constraint on config)"]);
+        // duplicates are dropped even when other checks come in between
+        assert_eq!(checks("take"), vec!["escrow.taker (checked in check)", "config.admin (checked in check)"]);
+    }
+}
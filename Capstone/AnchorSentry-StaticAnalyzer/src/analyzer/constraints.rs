@@ -0,0 +1,297 @@
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Expr, Ident, Token};
+
+/// Typed model of everything inside `#[account(...)]` on an Accounts struct field.
+#[derive(Debug, Clone, Default)]
+pub struct AccountConstraints {
+    pub init: bool,
+    pub init_if_needed: bool,
+    pub zero: bool,
+    pub mutable: Option<ConstraintExpr>,
+    pub signer: Option<ConstraintExpr>,
+    pub executable: bool,
+    pub payer: Option<Expr>,
+    pub space: Option<Expr>,
+    // seeds = [b"escrow", maker.key().as_ref()]
+    pub seeds: Option<Vec<Expr>>,
+    pub seeds_program: Option<Expr>,
+    pub bump: Option<Bump>,
+    pub has_one: Vec<ConstraintExpr>,
+    pub constraint: Vec<ConstraintExpr>,
+    pub close: Option<Expr>,
+    pub realloc: Option<Realloc>,
+    pub address: Option<ConstraintExpr>,
+    pub owner: Option<ConstraintExpr>,
+    pub rent_exempt: Option<Expr>,
+    // token::mint = mint, token::authority = escrow
+    pub token: Vec<NamespacedConstraint>,
+    pub mint: Vec<NamespacedConstraint>,
+    pub associated_token: Vec<NamespacedConstraint>,
+    // anything this model doesn't know about yet
+    pub other: Vec<NamespacedConstraint>,
+}
+
+/// A constraint value with an optional custom error, i.e `constraint = a == b @ MyError::Bad`
+#[derive(Debug, Clone)]
+pub struct ConstraintExpr {
+    pub expr: Option<Expr>,
+    pub error: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Bump {
+    // plain `bump`, anchor finds the canonical bump
+    Canonical,
+    // `bump = escrow.bump`
+    Provided(Box<Expr>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Realloc {
+    pub space: Option<Expr>,
+    pub payer: Option<Expr>,
+    pub zero: Option<Expr>,
+}
+
+/// `namespace::key = value`, the namespace is empty for unknown top level keys
+#[derive(Debug, Clone)]
+pub struct NamespacedConstraint {
+    pub namespace: String,
+    pub key: String,
+    pub value: Option<Expr>,
+}
+
+/// One comma separated entry of `#[account(...)]` as written in source.
+#[derive(Debug, Clone)]
+pub struct ConstraintToken {
+    pub namespace: Option<String>,
+    pub key: String,
+    pub value: Option<Expr>,
+    pub error: Option<Expr>,
+}
+
+impl Parse for ConstraintToken {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // `mut` is a keyword so a plain Ident parse would reject it
+        let first = Ident::parse_any(input)?;
+        let (namespace, key) = if input.peek(Token![::]) {
+            input.parse::<Token![::]>()?;
+            let key = Ident::parse_any(input)?;
+            (Some(first.to_string()), key.to_string())
+        } else {
+            (None, first.to_string())
+        };
+
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse::<Expr>()?)
+        } else {
+            None
+        };
+
+        let error = if input.peek(Token![@]) {
+            input.parse::<Token![@]>()?;
+            Some(input.parse::<Expr>()?)
+        } else {
+            None
+        };
+
+        Ok(ConstraintToken { namespace, key, value, error })
+    }
+}
+
+impl Parse for AccountConstraints {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let tokens = Punctuated::<ConstraintToken, Token![,]>::parse_terminated(input)?;
+        let mut c = AccountConstraints::default();
+        for t in tokens {
+            c.apply(t);
+        }
+        Ok(c)
+    }
+}
+
+impl AccountConstraints {
+    /// Parse every `#[account(...)]` attribute of a field, ignoring unrelated attributes.
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut c = AccountConstraints::default();
+        for attr in attrs {
+            if !attr.path().is_ident("account") {
+                continue;
+            }
+            // `#[account]` with no list carries no constraints
+            if let syn::Meta::List(_) = &attr.meta {
+                let tokens = attr.parse_args_with(Punctuated::<ConstraintToken, Token![,]>::parse_terminated)?;
+                for t in tokens {
+                    c.apply(t);
+                }
+            }
+        }
+        Ok(c)
+    }
+
+    fn apply(&mut self, t: ConstraintToken) {
+        let ConstraintToken { namespace, key, value, error } = t;
+        let namespaced = |namespace: String| NamespacedConstraint { namespace, key: key.clone(), value: value.clone() };
+
+        match (namespace.as_deref(), key.as_str()) {
+            (None, "init") => self.init = true,
+            (None, "init_if_needed") => self.init_if_needed = true,
+            (None, "zero") => self.zero = true,
+            (None, "executable") => self.executable = true,
+            (None, "mut") => self.mutable = Some(ConstraintExpr { expr: None, error }),
+            (None, "signer") => self.signer = Some(ConstraintExpr { expr: None, error }),
+            (None, "payer") => self.payer = value,
+            (None, "space") => self.space = value,
+            (None, "seeds") => {
+                self.seeds = value.map(|v| match v {
+                    Expr::Array(arr) => arr.elems.into_iter().collect(),
+                    other => vec![other],
+                })
+            }
+            (Some("seeds"), "program") => self.seeds_program = value,
+            (None, "bump") => {
+                self.bump = Some(match value {
+                    Some(v) => Bump::Provided(Box::new(v)),
+                    None => Bump::Canonical,
+                })
+            }
+            (None, "has_one") => self.has_one.push(ConstraintExpr { expr: value, error }),
+            (None, "constraint") => self.constraint.push(ConstraintExpr { expr: value, error }),
+            (None, "close") => self.close = value,
+            (None, "address") => self.address = Some(ConstraintExpr { expr: value, error }),
+            (None, "owner") => self.owner = Some(ConstraintExpr { expr: value, error }),
+            (None, "rent_exempt") => self.rent_exempt = value,
+            // `realloc::payer` may come before `realloc = ...`
+            (None, "realloc") => self.realloc_mut().space = value,
+            (Some("realloc"), "payer") => self.realloc_mut().payer = value,
+            (Some("realloc"), "zero") => self.realloc_mut().zero = value,
+            (Some("token"), _) => self.token.push(namespaced("token".to_string())),
+            (Some("mint"), _) => self.mint.push(namespaced("mint".to_string())),
+            (Some("associated_token"), _) => self.associated_token.push(namespaced("associated_token".to_string())),
+            (ns, _) => self.other.push(namespaced(ns.unwrap_or_default().to_string())),
+        }
+    }
+
+    fn realloc_mut(&mut self) -> &mut Realloc {
+        self.realloc.get_or_insert(Realloc { space: None, payer: None, zero: None })
+    }
+
+    pub fn is_init(&self) -> bool {
+        self.init || self.init_if_needed
+    }
+
+    pub fn is_mut(&self) -> bool {
+        self.mutable.is_some()
+    }
+
+    /// Names of the accounts tied through `has_one = x`
+    pub fn has_one_targets(&self) -> Vec<String> {
+        self.has_one
+            .iter()
+            .filter_map(|h| h.expr.as_ref())
+            .map(expr_to_string)
+            .collect()
+    }
+
+    /// Whether a namespaced constraint like `associated_token::authority` is set
+    pub fn namespaced(&self, namespace: &str, key: &str) -> Option<&Expr> {
+        let list = match namespace {
+            "token" => &self.token,
+            "mint" => &self.mint,
+            "associated_token" => &self.associated_token,
+            _ => &self.other,
+        };
+        list.iter().find(|c| c.key == key).and_then(|c| c.value.as_ref())
+    }
+}
+
+/// Render an expression back to source-like text, i.e `8 + Escrow :: INIT_SPACE`
+pub fn expr_to_string(e: &Expr) -> String {
+    e.to_token_stream().to_string()
+}
+
+/// Source text of a span, multi-line expressions are joined on one line
+pub fn span_text(source: &str, span: Span) -> String {
+    let (start, end) = (span.start(), span.end());
+    let lines: Vec<&str> = source.lines().collect();
+    if start.line == 0 || end.line > lines.len() {
+        return String::new();
+    }
+    let mut parts = Vec::new();
+    for n in start.line..=end.line {
+        let chars: Vec<char> = lines[n - 1].chars().collect();
+        let from = if n == start.line { start.column.min(chars.len()) } else { 0 };
+        let to = if n == end.line { end.column.min(chars.len()) } else { chars.len() };
+        parts.push(chars[from..to.max(from)].iter().collect::<String>().trim().to_string());
+    }
+    parts.join(" ")
+}
+
+/// Whether `name` appears in `text` as a whole identifier, `bump` matches `&[bump]` but not `bumps.vault`
+pub fn mentions_ident(text: &str, name: &str) -> bool {
+    text.match_indices(name).any(|(i, _)| {
+        let before = text[..i].chars().last();
+        let after = text[i + name.len()..].chars().next();
+        !before.is_some_and(|c| c.is_alphanumeric() || c == '_') && !after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(attr: &str) -> AccountConstraints {
+        let field: syn::Field = syn::parse::Parser::parse_str(syn::Field::parse_named, &format!("{attr} pub x: u8")).unwrap();
+        AccountConstraints::from_attrs(&field.attrs).unwrap()
+    }
+
+    #[test]
+    fn token_with_namespace_value_and_error() {
+        let t: ConstraintToken = syn::parse_str("token::authority = escrow @ EscrowError::BadAuthority").unwrap();
+        assert_eq!(t.namespace.as_deref(), Some("token"));
+        assert_eq!(t.key, "authority");
+        assert_eq!(expr_to_string(t.value.as_ref().unwrap()), "escrow");
+        assert_eq!(expr_to_string(t.error.as_ref().unwrap()), "EscrowError :: BadAuthority");
+    }
+
+    #[test]
+    fn keyword_key_without_value() {
+        let t: ConstraintToken = syn::parse_str("mut @ MyError::ReadOnly").unwrap();
+        assert_eq!(t.namespace, None);
+        assert_eq!(t.key, "mut");
+        assert!(t.value.is_none());
+        assert!(t.error.is_some());
+    }
+
+    #[test]
+    fn field_constraints() {
+        let c = parse(
+            "#[account(init_if_needed, payer = maker, space = 8 + Escrow::INIT_SPACE, \
+             seeds = [b\"escrow\", maker.key().as_ref()], bump, has_one = maker @ E::Maker, \
+             associated_token::mint = mint, realloc::payer = maker, realloc = 100, close = maker)]",
+        );
+        assert!(c.is_init() && !c.init);
+        assert_eq!(c.seeds.as_ref().map(Vec::len), Some(2));
+        assert!(matches!(c.bump, Some(Bump::Canonical)));
+        assert_eq!(c.has_one_targets(), vec!["maker"]);
+        assert!(c.has_one[0].error.is_some());
+        assert_eq!(c.namespaced("associated_token", "mint").map(expr_to_string).as_deref(), Some("mint"));
+        let realloc = c.realloc.as_ref().unwrap();
+        assert!(realloc.space.is_some() && realloc.payer.is_some());
+        assert!(c.close.is_some());
+    }
+
+    #[test]
+    fn provided_bump_and_unknown_keys() {
+        let c = parse("#[account(mut, bump = escrow.bump, foo::bar = 1)]");
+        assert!(c.is_mut());
+        assert!(matches!(&c.bump, Some(Bump::Provided(e)) if expr_to_string(e) == "escrow . bump"));
+        assert_eq!(c.other[0].namespace, "foo");
+        assert_eq!(c.other[0].key, "bar");
+    }
+}
@@ -0,0 +1,588 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result, bail};
+use quote::ToTokens;
+use serde::Deserialize;
+use serde_json::Value;
+use syn::{Expr, Type};
+use crate::analyzer::constraints::{AccountConstraints, Bump, ConstraintExpr};
+use crate::state::{FnArgs, NormalizedAccountField, NormalizedAccountStruct, NormalizedFunctionArgs, NormalizedProgram};
+
+/// Sysvar accounts, typed `Sysvar<'info, T>` in source
+const SYSVARS: &[&str] = &["rent", "clock", "instructions", "slot_hashes", "recent_blockhashes", "epoch_schedule", "stake_history"];
+
+/// Anchor IDL, both the legacy (`isMut`/`isSigner`, camelCase) and the 0.30 (`writable`/`signer`) layouts
+#[derive(Debug, Clone, Deserialize)]
+pub struct Idl {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub metadata: Option<IdlMetadata>,
+    pub instructions: Vec<IdlInstruction>,
+    // legacy IDLs define the account struct inline, 0.30 ones in `types`
+    #[serde(default)]
+    pub accounts: Vec<IdlTypeDef>,
+    #[serde(default)]
+    pub types: Vec<IdlTypeDef>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlMetadata {
+    #[serde(default)]
+    pub name: Option<String>,
+    // only present in 0.30 IDLs
+    #[serde(default)]
+    pub spec: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlInstruction {
+    pub name: String,
+    pub accounts: Vec<IdlAccountItem>,
+    #[serde(default)]
+    pub args: Vec<IdlField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlAccountItem {
+    pub name: String,
+    #[serde(default, alias = "isMut")]
+    pub writable: bool,
+    #[serde(default, alias = "isSigner")]
+    pub signer: bool,
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub pda: Option<IdlPda>,
+    // legacy: accounts this one has a `has_one` on, 0.30: accounts with a `has_one` on this one
+    #[serde(default)]
+    pub relations: Vec<String>,
+    // composite Accounts structs nest their accounts
+    #[serde(default)]
+    pub accounts: Vec<IdlAccountItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlPda {
+    pub seeds: Vec<IdlSeed>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlSeed {
+    pub kind: String,
+    #[serde(default)]
+    pub value: Option<Value>,
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlTypeDef {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+    #[serde(rename = "type", default)]
+    pub ty: Option<IdlTypeDefTy>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlTypeDefTy {
+    pub kind: String,
+    #[serde(default)]
+    pub fields: Vec<Value>,
+    #[serde(default)]
+    pub variants: Vec<IdlVariant>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlVariant {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Vec<Value>,
+}
+
+/// An IDL with the file it came from, kept to point findings at it
+#[derive(Debug, Clone)]
+pub struct LoadedIdl {
+    pub path: String,
+    pub text: String,
+    pub idl: Idl,
+    // first and last line of each entry of `instructions` and `accounts` in `text`
+    instruction_lines: Vec<(usize, usize)>,
+    account_lines: Vec<(usize, usize)>,
+}
+
+impl Idl {
+    pub fn program_name(&self) -> Option<&str> {
+        self.name.as_deref().or_else(|| self.metadata.as_ref()?.name.as_deref())
+    }
+
+    /// 0.30 IDLs list `relations` on the related account rather than on the one holding the key
+    pub fn is_legacy(&self) -> bool {
+        self.address.is_none() && self.metadata.as_ref().is_none_or(|m| m.spec.is_none())
+    }
+
+    /// Struct definition of an `#[account]` type, inline in legacy IDLs
+    pub fn account_def(&self, name: &str) -> Option<&IdlTypeDefTy> {
+        let account = self.accounts.iter().find(|a| a.name == name)?;
+        account.ty.as_ref().or_else(|| self.types.iter().find(|t| t.name == name)?.ty.as_ref())
+    }
+}
+
+impl LoadedIdl {
+    /// Reads `target/idl/x.json`, or the JSON inside an `idlgen!({ .. })` call of a Rust file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read IDL {}", path.display()))?;
+        let json = if path.extension().is_some_and(|e| e == "rs") { idlgen_json(&text)? } else { text.clone() };
+        let idl = serde_json::from_str(&json).with_context(|| format!("Failed to parse IDL {}", path.display()))?;
+        let instruction_lines = array_item_lines(&text, "instructions");
+        let account_lines = array_item_lines(&text, "accounts");
+        Ok(LoadedIdl { path: path.to_string_lossy().to_string(), text, idl, instruction_lines, account_lines })
+    }
+
+    /// Line of `"name": "<name>"` in the IDL file, the first line when it can't be found
+    pub fn line_of(&self, name: &str) -> usize {
+        name_line(self.text.lines().enumerate(), name).unwrap_or(1)
+    }
+
+    /// Line of the instruction `ix`'s `"name"`
+    pub fn instruction_line(&self, ix: &IdlInstruction) -> usize {
+        self.line_in(ix, &ix.name)
+    }
+
+    /// Line of `"name": "<name>"` within the instruction `ix`, an account passed to several
+    /// instructions is found in each of them
+    pub fn line_in(&self, ix: &IdlInstruction, name: &str) -> usize {
+        let index = self.idl.instructions.iter().position(|i| i.name == ix.name);
+        self.line_within(index.and_then(|i| self.instruction_lines.get(i)), name)
+    }
+
+    /// Line of the `#[account]` type `name` in the top level `accounts`, not of an instruction account
+    pub fn account_type_line(&self, name: &str) -> usize {
+        let index = self.idl.accounts.iter().position(|a| a.name == name);
+        self.line_within(index.and_then(|i| self.account_lines.get(i)), name)
+    }
+
+    fn line_within(&self, span: Option<&(usize, usize)>, name: &str) -> usize {
+        let Some(&(first, last)) = span else { return self.line_of(name) };
+        let lines = self.text.lines().enumerate().skip(first - 1).take(last + 1 - first);
+        name_line(lines, name).unwrap_or(first)
+    }
+}
+
+fn name_line<'a>(mut lines: impl Iterator<Item = (usize, &'a str)>, name: &str) -> Option<usize> {
+    let quoted = format!("\"{}\"", name);
+    lines.find(|(_, l)| l.contains("\"name\"") && l.contains(&quoted)).map(|(i, _)| i + 1)
+}
+
+/// First and last line of every object in the top level `"<key>": [..]` array of the IDL JSON,
+/// which starts at the `{` after `idlgen!` in a Rust file
+fn array_item_lines(text: &str, key: &str) -> Vec<(usize, usize)> {
+    let start = match text.find("idlgen!") {
+        Some(i) => text[i..].find('{').map(|j| i + j),
+        None => text.find('{'),
+    };
+    let Some(start) = start else { return Vec::new() };
+
+    let mut out = Vec::new();
+    let mut line = 1 + text[..start].matches('\n').count();
+    let (mut depth, mut item_start) = (0usize, 0usize);
+    let (mut in_string, mut escaped, mut in_array, mut key_matches) = (false, false, false, false);
+    let mut last_string = String::new();
+    for c in text[start..].chars() {
+        if c == '\n' {
+            line += 1;
+        }
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => last_string.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                last_string.clear();
+            }
+            ':' if depth == 1 => key_matches = last_string == key,
+            ',' if depth == 1 => key_matches = false,
+            '{' | '[' => {
+                depth += 1;
+                if depth == 2 && c == '[' && key_matches {
+                    in_array = true;
+                } else if depth == 3 && in_array {
+                    item_start = line;
+                }
+            }
+            '}' | ']' => {
+                if depth == 3 && in_array {
+                    out.push((item_start, line));
+                } else if depth == 2 && in_array {
+                    break;
+                }
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// `idlgen!({ "version": .. })` -> the JSON object
+fn idlgen_json(source: &str) -> Result<String> {
+    let file = syn::parse_file(source).context("Failed to parse IDL source")?;
+    for item in &file.items {
+        if let syn::Item::Macro(m) = item
+            && m.mac.path.segments.last().is_some_and(|s| s.ident == "idlgen") {
+            return Ok(m.mac.tokens.to_string());
+        }
+    }
+    bail!("No idlgen! invocation found")
+}
+
+/// `systemProgram` -> `system_program`, snake_case names are left alone
+pub fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// `place_bet` -> `PlaceBet`
+pub fn pascal_case(name: &str) -> String {
+    snake_case(name)
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Rust spelling of an IDL type, `{"vec": "u8"}` -> `Vec<u8>`
+pub fn idl_type(v: &Value) -> String {
+    match v {
+        Value::String(s) => match s.as_str() {
+            "string" => "String".to_string(),
+            "bytes" => "Vec<u8>".to_string(),
+            "publicKey" | "pubkey" => "Pubkey".to_string(),
+            other => other.to_string(),
+        },
+        Value::Object(o) => {
+            if let Some(inner) = o.get("vec") {
+                format!("Vec<{}>", idl_type(inner))
+            } else if let Some(inner) = o.get("option").or_else(|| o.get("coption")) {
+                format!("Option<{}>", idl_type(inner))
+            } else if let Some(Value::Array(arr)) = o.get("array")
+                && let [elem, len] = arr.as_slice() {
+                format!("[{}; {}]", idl_type(elem), len)
+            } else if let Some(defined) = o.get("defined") {
+                match defined {
+                    Value::String(name) => name.clone(),
+                    other => other.get("name").and_then(Value::as_str).unwrap_or_default().to_string(),
+                }
+            } else {
+                v.to_string()
+            }
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Source type in the same spelling as `idl_type`, paths and lifetimes dropped
+pub fn rust_type(ty: &Type) -> String {
+    match ty {
+        Type::Path(p) => {
+            let Some(seg) = p.path.segments.last() else { return String::new() };
+            let args: Vec<String> = match &seg.arguments {
+                syn::PathArguments::AngleBracketed(a) => a
+                    .args
+                    .iter()
+                    .filter_map(|g| match g {
+                        syn::GenericArgument::Type(t) => Some(rust_type(t)),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if args.is_empty() { seg.ident.to_string() } else { format!("{}<{}>", seg.ident, args.join(", ")) }
+        }
+        Type::Array(a) => format!("[{}; {}]", rust_type(&a.elem), a.len.to_token_stream().to_string().replace(' ', "")),
+        Type::Reference(r) => rust_type(&r.elem),
+        Type::Paren(p) => rust_type(&p.elem),
+        other => other.to_token_stream().to_string().replace(' ', ""),
+    }
+}
+
+/// `{"name": "maker", "type": "pubkey"}` -> `pub maker: Pubkey`
+fn field_source(v: &Value) -> Option<String> {
+    let name = v.get("name")?.as_str()?;
+    Some(format!("pub {}: {}", snake_case(name), idl_type(v.get("type")?)))
+}
+
+/// Rust source of an IDL struct or enum, so sizes and fields go through the same code as the program's
+fn type_source(name: &str, def: &IdlTypeDefTy, attr: &str) -> String {
+    if def.kind == "enum" {
+        let variants: Vec<String> = def
+            .variants
+            .iter()
+            .map(|v| {
+                if v.fields.is_empty() {
+                    v.name.clone()
+                } else if v.fields.iter().all(|f| f.get("name").is_some()) {
+                    format!("{} {{ {} }}", v.name, v.fields.iter().filter_map(field_source).collect::<Vec<_>>().join(", ").replace("pub ", ""))
+                } else {
+                    format!("{}({})", v.name, v.fields.iter().map(idl_type).collect::<Vec<_>>().join(", "))
+                }
+            })
+            .collect();
+        return format!("{} pub enum {} {{ {} }}", attr, name, variants.join(", "));
+    }
+    let fields: Vec<String> = def.fields.iter().filter_map(field_source).collect();
+    format!("{} pub struct {} {{ {} }}", attr, name, fields.join(", "))
+}
+
+fn parse_expr(text: &str) -> Option<Expr> {
+    syn::parse_str(text).ok()
+}
+
+/// Source-like seed expressions of a 0.30 PDA
+fn seed_exprs(pda: &IdlPda) -> Vec<Expr> {
+    pda.seeds
+        .iter()
+        .filter_map(|seed| match seed.kind.as_str() {
+            "const" => {
+                let bytes: Vec<u8> = seed.value.as_ref().and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or_default();
+                match String::from_utf8(bytes.clone()) {
+                    Ok(s) if s.chars().all(|c| c.is_ascii_graphic()) => parse_expr(&format!("b\"{}\"", s)),
+                    _ => parse_expr(&format!("{:?}", bytes)),
+                }
+            }
+            "account" => parse_expr(&format!("{}.key().as_ref()", snake_case(seed.path.as_deref()?).replace('.', "_"))),
+            "arg" => parse_expr(&format!("{}.to_le_bytes().as_ref()", snake_case(seed.path.as_deref()?))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Flattens composite account groups
+fn flat_accounts(items: &[IdlAccountItem]) -> Vec<&IdlAccountItem> {
+    items
+        .iter()
+        .flat_map(|a| if a.accounts.is_empty() { vec![a] } else { flat_accounts(&a.accounts) })
+        .collect()
+}
+
+impl LoadedIdl {
+    /// Accounts of an instruction, in order and with nested groups flattened
+    pub fn instruction_accounts<'a>(&self, ix: &'a IdlInstruction) -> Vec<&'a IdlAccountItem> {
+        flat_accounts(&ix.accounts)
+    }
+
+    /// A program model built from the IDL alone. Account types are guessed from names
+    /// (`escrow` is an `Account<Escrow>`, `*_program` a `Program`), which is enough for
+    /// the relationship and signer rules.
+    pub fn to_program(&self) -> NormalizedProgram {
+        let idl = &self.idl;
+        let mut program = NormalizedProgram { name: idl.program_name().unwrap_or_default().to_string(), ..Default::default() };
+
+        for account in &idl.accounts {
+            let Some(def) = idl.account_def(&account.name) else { continue };
+            let attr = match &account.discriminator {
+                Some(d) if d.len() != 8 => format!("#[account(discriminator = {:?})]", d),
+                _ => "#[account]".to_string(),
+            };
+            if let Ok(s) = syn::parse_str::<syn::ItemStruct>(&type_source(&account.name, def, &attr)) {
+                program.type_files.insert(account.name.clone(), self.path.clone());
+                program.storage_structs.push(s);
+            }
+        }
+        for t in idl.types.iter().filter(|t| !idl.accounts.iter().any(|a| a.name == t.name)) {
+            let Some(def) = &t.ty else { continue };
+            let source = type_source(&t.name, def, "");
+            if def.kind == "enum" {
+                if let Ok(e) = syn::parse_str::<syn::ItemEnum>(&source) {
+                    program.enums.push(e);
+                }
+            } else if let Ok(s) = syn::parse_str::<syn::ItemStruct>(&source) {
+                program.data_structs.push(s);
+            }
+            program.type_files.insert(t.name.clone(), self.path.clone());
+        }
+
+        for ix in &idl.instructions {
+            let context = pascal_case(&ix.name);
+            let accounts = self.instruction_accounts(ix);
+            let mut fields: Vec<NormalizedAccountField> = accounts.iter().map(|a| self.idl_field(ix, &context, a, &program)).collect();
+
+            // has_one constraints from `relations`
+            for a in &accounts {
+                for related in &a.relations {
+                    let (holder, target) = if idl.is_legacy() { (a.name.as_str(), related.as_str()) } else { (related.as_str(), a.name.as_str()) };
+                    if let Some(field) = fields.iter_mut().find(|f| f.name == snake_case(holder))
+                        && let Some(expr) = parse_expr(&snake_case(target)) {
+                        field.constraints.has_one.push(ConstraintExpr { expr: Some(expr), error: None });
+                    }
+                }
+            }
+
+            let line = self.instruction_line(ix);
+            program.account_structs.push(NormalizedAccountStruct { name: context.clone(), fields, line, file: self.path.clone() });
+            program.functions_args.push(NormalizedFunctionArgs {
+                name: snake_case(&ix.name),
+                context,
+                args: ix.args.iter().map(|a| FnArgs { name: snake_case(&a.name), ty: idl_type(&a.ty) }).collect(),
+                line,
+                column: 1,
+                file: self.path.clone(),
+            });
+        }
+        program
+    }
+
+    fn idl_field(&self, ix: &IdlInstruction, context: &str, a: &IdlAccountItem, program: &NormalizedProgram) -> NormalizedAccountField {
+        let name = snake_case(&a.name);
+        let storage = program.storage_structs.iter().map(|s| s.ident.to_string()).find(|t| snake_case(t) == name);
+        let (base_type, generic) = if a.signer {
+            ("Signer", None)
+        } else if name.ends_with("program") {
+            ("Program", None)
+        } else if SYSVARS.contains(&name.as_str()) {
+            ("Sysvar", None)
+        } else if let Some(t) = storage {
+            ("Account", Some(t))
+        } else if name.contains("mint") {
+            ("InterfaceAccount", Some("Mint".to_string()))
+        } else if name.split('_').any(|w| w == "ata") || name.contains("token_account") {
+            ("InterfaceAccount", Some("TokenAccount".to_string()))
+        } else {
+            ("UncheckedAccount", None)
+        };
+
+        let mut constraints = AccountConstraints::default();
+        if a.writable {
+            constraints.mutable = Some(ConstraintExpr { expr: None, error: None });
+        }
+        if let Some(address) = &a.address {
+            constraints.address = Some(ConstraintExpr { expr: parse_expr(&format!("{:?}", address)), error: None });
+        }
+        if let Some(pda) = &a.pda {
+            constraints.seeds = Some(seed_exprs(pda));
+            constraints.bump = Some(Bump::Canonical);
+        }
+
+        NormalizedAccountField {
+            context: context.to_string(),
+            name,
+            base_type: base_type.to_string(),
+            generic_args: std::iter::once("'info".to_string()).chain(generic).collect(),
+            constraints,
+            docs: Vec::new(),
+            line: self.line_in(ix, &a.name),
+            column: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDL: &str = r#"{
+  "version": "0.1.0",
+  "name": "tasks",
+  "instructions": [
+    {
+      "name": "complete",
+      "accounts": [
+        { "name": "task", "isMut": true, "isSigner": false },
+        { "name": "prereq", "isMut": false, "isSigner": false },
+        { "name": "metadata", "isMut": false, "isSigner": false }
+      ],
+      "args": []
+    },
+    {
+      "name": "update",
+      "accounts": [
+        { "name": "task", "isMut": true, "isSigner": false },
+        { "name": "prereq", "isMut": false, "isSigner": false },
+        { "name": "userAta", "isMut": true, "isSigner": false },
+        { "name": "ata", "isMut": true, "isSigner": false }
+      ],
+      "args": []
+    }
+  ],
+  "accounts": [
+    {
+      "name": "task",
+      "type": { "kind": "struct", "fields": [{ "name": "done", "type": "bool" }] }
+    }
+  ]
+}"#;
+
+    fn load(name: &str, text: &str) -> LoadedIdl {
+        let path = std::env::temp_dir().join(format!("anchor-sentry-{}-{}", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        let idl = LoadedIdl::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        idl
+    }
+
+    fn field_line(program: &NormalizedProgram, context: &str, name: &str) -> usize {
+        let s = program.account_structs.iter().find(|s| s.name == context).unwrap();
+        s.fields.iter().find(|f| f.name == name).unwrap().line
+    }
+
+    #[test]
+    fn accounts_point_into_their_own_instruction() {
+        let idl = load("lines.json", IDL);
+        let program = idl.to_program();
+        assert_eq!(field_line(&program, "Complete", "prereq"), 9);
+        assert_eq!(field_line(&program, "Update", "prereq"), 18);
+        assert_eq!(program.account_structs[1].line, 15);
+        assert_eq!(idl.account_type_line("task"), 27);
+    }
+
+    #[test]
+    fn idlgen_source_lines() {
+        let idl = load("lines.rs", &format!("// generated\nidlgen!({});\n", IDL));
+        assert_eq!(idl.line_in(&idl.idl.instructions[1], "prereq"), 19);
+        assert_eq!(idl.account_type_line("task"), 28);
+    }
+
+    #[test]
+    fn token_accounts_are_guessed_from_whole_words() {
+        let program = load("ata.json", IDL).to_program();
+        let base_type = |context: &str, name: &str| {
+            let s = program.account_structs.iter().find(|s| s.name == context).unwrap();
+            let f = s.fields.iter().find(|f| f.name == name).unwrap();
+            (f.base_type.clone(), f.generic_args.get(1).cloned())
+        };
+        assert_eq!(base_type("Complete", "metadata"), ("UncheckedAccount".to_string(), None));
+        assert_eq!(base_type("Update", "user_ata"), ("InterfaceAccount".to_string(), Some("TokenAccount".to_string())));
+        assert_eq!(base_type("Update", "ata"), ("InterfaceAccount".to_string(), Some("TokenAccount".to_string())));
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprAssign, ExprBinary, ExprMethodCall, Pat};
+use crate::analyzer::constraints::expr_to_string;
+use crate::analyzer::normalize::program_handlers;
+use crate::analyzer::rules::account_field;
+use crate::report::knowledge_base::Location;
+use crate::state::{NormalizedAccountField, NormalizedAccountStruct, NormalizedProgram, SourceFile};
+
+/// Account types whose data Anchor deserializes into a storage struct
+const STATE_ACCOUNT_TYPES: &[&str] = &["Account", "AccountLoader"];
+/// Methods that write the whole account, `self.bet.set_inner(Bet { .. })`
+const WRITE_METHODS: &[&str] = &["set_inner", "load_mut", "load_init"];
+
+/// What every instruction does to each storage type of a program
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProgramLifecycle {
+    pub program: String,
+    pub accounts: Vec<AccountLifecycle>,
+}
+
+/// One storage type, i.e `Bet`, and every instruction that touches it
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountLifecycle {
+    pub type_name: String,
+    // where the `#[account]` struct is declared
+    pub file: Option<String>,
+    pub steps: Vec<LifecycleStep>,
+}
+
+impl AccountLifecycle {
+    pub fn creators(&self) -> impl Iterator<Item = &LifecycleStep> {
+        self.steps.iter().filter(|s| s.created.is_some())
+    }
+
+    pub fn mutators(&self) -> impl Iterator<Item = &LifecycleStep> {
+        self.steps.iter().filter(|s| !s.writes.is_empty())
+    }
+
+    pub fn closers(&self) -> impl Iterator<Item = &LifecycleStep> {
+        self.steps.iter().filter(|s| s.close.is_some())
+    }
+}
+
+impl LifecycleStep {
+    /// `instruction` as shown in findings and tables, the struct name is marked when no handler uses it
+    pub fn instruction_label(&self, quote: bool) -> String {
+        let q = if quote { "`" } else { "" };
+        if self.handlers.is_empty() {
+            format!("{q}{}{q} (no handler)", self.instruction)
+        } else {
+            self.handlers.iter().map(|h| format!("{q}{h}{q}")).collect::<Vec<_>>().join(", ")
+        }
+    }
+}
+
+/// A storage account as passed to one instruction
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleStep {
+    // handler name from the `#[program]` module, the Accounts struct when no handler uses it
+    pub instruction: String,
+    // handlers taking the Accounts struct, empty when `instruction` is the struct itself
+    pub handlers: Vec<String>,
+    pub context: String,
+    pub account: String,
+    // `init`, `init_if_needed` or `zero`
+    pub created: Option<String>,
+    pub close: Option<CloseSite>,
+    pub writes: Vec<AccountWrite>,
+    // the field of the Accounts struct
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CloseSite {
+    // account receiving the rent
+    pub destination: String,
+    // `self.bet.close(..)` in code rather than `#[account(close = ..)]`
+    pub manual: bool,
+    pub function_name: Option<String>,
+    pub location: Location,
+}
+
+/// An assignment to the account data, `self.user_account.amount_staked += 1`
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountWrite {
+    pub function_name: String,
+    pub target: String,
+    pub location: Location,
+}
+
+/// Records data writes and manual closes of Accounts fields in handlers and Accounts impls
+#[derive(Default)]
+struct WriteCollector {
+    file: String,
+    current_fn: String,
+    writes: Vec<(String, AccountWrite)>,
+    closes: Vec<(String, CloseSite)>,
+    // `let bet = &mut self.bet;`
+    aliases: HashMap<String, String>,
+    // `&mut self` methods of storage types, `self.whitelist.add(..)` writes the account
+    mut_methods: Vec<String>,
+}
+
+impl WriteCollector {
+    fn enter(&mut self, name: String) {
+        self.current_fn = name;
+        self.aliases.clear();
+    }
+
+    /// The Accounts field a place expression writes into, `self.bet.amount` -> `bet`
+    fn written_field(&self, e: &Expr) -> Option<String> {
+        if let Some(field) = account_field(e) {
+            return Some(field);
+        }
+        match e {
+            Expr::Field(f) => self.written_field(&f.base),
+            Expr::Index(i) => self.written_field(&i.expr),
+            Expr::Paren(p) => self.written_field(&p.expr),
+            Expr::Unary(u) => self.written_field(&u.expr),
+            Expr::Reference(r) => self.written_field(&r.expr),
+            Expr::Try(t) => self.written_field(&t.expr),
+            Expr::MethodCall(m) => self.written_field(&m.receiver),
+            Expr::Path(p) => self.aliases.get(&p.path.get_ident()?.to_string()).cloned(),
+            _ => None,
+        }
+    }
+
+    fn push_write(&mut self, field: String, target: &Expr) {
+        self.writes.push((field, AccountWrite {
+            function_name: self.current_fn.clone(),
+            target: expr_to_string(target).replace(' ', ""),
+            location: Location::from_span(&self.file, target.span()),
+        }));
+    }
+}
+
+impl<'ast> Visit<'ast> for WriteCollector {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.enter(node.sig.ident.to_string());
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.enter(node.sig.ident.to_string());
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        visit::visit_local(self, local);
+        let pat = match &local.pat {
+            Pat::Type(t) => &*t.pat,
+            other => other,
+        };
+        let (Pat::Ident(ident), Some(init)) = (pat, &local.init) else { return };
+        let name = ident.ident.to_string();
+        // `let bet = &mut self.bet;`, `let mut pool = self.pool.load_mut()?;`
+        let mutable = matches!(&*init.expr, Expr::Reference(r) if r.mutability.is_some()) || expr_to_string(&init.expr).contains("load_mut");
+        match self.written_field(&init.expr) {
+            Some(field) if mutable => {
+                self.aliases.insert(name, field);
+            }
+            _ => {
+                self.aliases.remove(&name);
+            }
+        }
+    }
+
+    fn visit_expr_assign(&mut self, expr: &'ast ExprAssign) {
+        if let Some(field) = self.written_field(&expr.left) {
+            self.push_write(field, &expr.left);
+        }
+        visit::visit_expr_assign(self, expr);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast ExprBinary) {
+        let compound = matches!(
+            expr.op,
+            BinOp::AddAssign(_) | BinOp::SubAssign(_) | BinOp::MulAssign(_) | BinOp::DivAssign(_) | BinOp::RemAssign(_)
+                | BinOp::BitXorAssign(_) | BinOp::BitAndAssign(_) | BinOp::BitOrAssign(_) | BinOp::ShlAssign(_) | BinOp::ShrAssign(_)
+        );
+        if compound && let Some(field) = self.written_field(&expr.left) {
+            self.push_write(field, &expr.left);
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        let method = expr.method.to_string();
+        if (WRITE_METHODS.contains(&method.as_str()) || self.mut_methods.contains(&method))
+            && let Some(field) = account_field(&expr.receiver) {
+            self.push_write(field, &expr.receiver);
+        } else if method == "close"
+            && let Some(field) = account_field(&expr.receiver) {
+            // self.bet.close(self.player.to_account_info())
+            let destination = expr
+                .args
+                .first()
+                .map(|a| account_field(a).unwrap_or_else(|| expr_to_string(a).replace(' ', "")))
+                .unwrap_or_default();
+            self.closes.push((field, CloseSite {
+                destination,
+                manual: true,
+                function_name: Some(self.current_fn.clone()),
+                location: Location::from_span(&self.file, expr.span()),
+            }));
+        }
+        visit::visit_expr_method_call(self, expr);
+    }
+}
+
+/// `Account<'info, Bet>` -> `Bet`, for storage structs of this program
+fn storage_type<'a>(field: &'a NormalizedAccountField, program: &NormalizedProgram) -> Option<&'a str> {
+    if !STATE_ACCOUNT_TYPES.contains(&field.base_type.as_str()) {
+        return None;
+    }
+    let inner = field.generic_args.iter().rfind(|g| !g.starts_with('\''))?;
+    program.storage_structs.iter().any(|s| s.ident == inner.as_str()).then_some(inner.as_str())
+}
+
+fn storage_mut_methods(program: &NormalizedProgram) -> Vec<String> {
+    let names: Vec<String> = program.storage_structs.iter().map(|s| s.ident.to_string()).collect();
+    program
+        .impls
+        .iter()
+        .filter(|i| names.contains(&i.type_name))
+        .flat_map(|i| &i.methods)
+        .filter(|m| m.sig.receiver().is_some_and(|r| r.reference.is_some() && r.mutability.is_some()))
+        .map(|m| m.sig.ident.to_string())
+        .collect()
+}
+
+/// Writes and manual closes in the code run for the Accounts struct `s`
+fn collect_writes(s: &NormalizedAccountStruct, handlers: &[String], program: &NormalizedProgram, files: &[SourceFile]) -> WriteCollector {
+    let mut collector = WriteCollector { mut_methods: storage_mut_methods(program), ..Default::default() };
+    for imp in program.impls_of(&s.name) {
+        collector.file = imp.file.clone();
+        for m in &imp.methods {
+            collector.visit_impl_item_fn(m);
+        }
+    }
+    for file in files {
+        collector.file = file.path.clone();
+        for handler in program_handlers(&file.ast.items).into_iter().filter(|f| handlers.contains(&f.sig.ident.to_string())) {
+            collector.visit_item_fn(handler);
+        }
+    }
+    collector
+}
+
+/// Builds the lifecycle of every storage type from the Accounts structs and the code using them
+pub fn program_lifecycle(program: &NormalizedProgram, files: &[SourceFile]) -> ProgramLifecycle {
+    let mut accounts: Vec<AccountLifecycle> = program
+        .storage_structs
+        .iter()
+        .map(|s| AccountLifecycle {
+            type_name: s.ident.to_string(),
+            file: program.type_files.get(&s.ident.to_string()).cloned(),
+            steps: Vec::new(),
+        })
+        .collect();
+
+    for s in &program.account_structs {
+        let handlers: Vec<String> = program.functions_args.iter().filter(|f| f.context == s.name).map(|f| f.name.clone()).collect();
+        let instruction = if handlers.is_empty() { s.name.clone() } else { handlers.join(", ") };
+        let code = collect_writes(s, &handlers, program, files);
+
+        for field in &s.fields {
+            let Some(type_name) = storage_type(field, program) else { continue };
+            let c = &field.constraints;
+            let created = if c.init_if_needed {
+                Some("init_if_needed".to_string())
+            } else if c.init {
+                Some("init".to_string())
+            } else if c.zero {
+                Some("zero".to_string())
+            } else {
+                None
+            };
+            let location = Location::new(&s.file, field.line, field.column);
+            let close = c
+                .close
+                .as_ref()
+                .map(|target| CloseSite { destination: expr_to_string(target).replace(' ', ""), manual: false, function_name: None, location: location.clone() })
+                .or_else(|| code.closes.iter().find(|(f, _)| *f == field.name).map(|(_, site)| site.clone()));
+            let writes = code.writes.iter().filter(|(f, _)| *f == field.name).map(|(_, w)| w.clone()).collect();
+
+            let Some(account) = accounts.iter_mut().find(|a| a.type_name == type_name) else { continue };
+            account.steps.push(LifecycleStep {
+                instruction: instruction.clone(),
+                handlers: handlers.clone(),
+                context: s.name.clone(),
+                account: field.name.clone(),
+                created,
+                close,
+                writes,
+                location,
+            });
+        }
+    }
+
+    ProgramLifecycle { program: program.name.clone(), accounts }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+
+    pub(crate) const DICE: &str = r#"
+        #[program]
+        pub mod dice {
+            use super::*;
+            pub fn place_bet(ctx: Context<PlaceBet>, amount: u64) -> Result<()> {
+                ctx.accounts.bet.amount = amount;
+                Ok(())
+            }
+            pub fn refund_bet(ctx: Context<RefundBet>) -> Result<()> {
+                ctx.accounts.refund()
+            }
+        }
+
+        #[account]
+        pub struct Bet { pub amount: u64, pub slot: u64 }
+
+        #[derive(Accounts)]
+        pub struct PlaceBet<'info> {
+            #[account(init, payer = player, space = 8 + 16)]
+            pub bet: Account<'info, Bet>,
+            pub player: Signer<'info>,
+        }
+
+        #[derive(Accounts)]
+        pub struct RefundBet<'info> {
+            #[account(mut, close = player)]
+            pub bet: Account<'info, Bet>,
+            pub player: Signer<'info>,
+        }
+
+        impl<'info> RefundBet<'info> {
+            pub fn refund(&mut self) -> Result<()> {
+                self.bet.amount = 0;
+                Ok(())
+            }
+        }
+
+        #[derive(Accounts)]
+        pub struct ResolveBet<'info> {
+            #[account(mut)]
+            pub bet: Account<'info, Bet>,
+            pub house: Signer<'info>,
+        }
+
+        impl<'info> ResolveBet<'info> {
+            pub fn resolve(&mut self) -> Result<()> {
+                self.bet.close(self.house.to_account_info())?;
+                self.bet.slot = 1;
+                Ok(())
+            }
+        }
+    "#;
+
+    pub(crate) fn lifecycle_of(source: &str) -> ProgramLifecycle {
+        let files = [SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(source).unwrap(), source: source.to_string() }];
+        let program = normalize_program(&files);
+        program_lifecycle(&program, &files)
+    }
+
+    #[test]
+    fn steps_per_instruction() {
+        let lifecycle = lifecycle_of(DICE);
+        let bet = lifecycle.accounts.iter().find(|a| a.type_name == "Bet").unwrap();
+
+        let created: Vec<&str> = bet.creators().map(|s| s.instruction.as_str()).collect();
+        assert_eq!(created, vec!["place_bet"]);
+        assert_eq!(bet.creators().next().unwrap().created.as_deref(), Some("init"));
+
+        let mutated: Vec<String> = bet.mutators().map(|s| s.instruction_label(false)).collect();
+        assert_eq!(mutated, vec!["place_bet", "refund_bet", "ResolveBet (no handler)"]);
+
+        let closes: Vec<(String, bool, &str)> = bet
+            .closers()
+            .map(|s| {
+                let c = s.close.as_ref().unwrap();
+                (s.instruction_label(false), c.manual, c.destination.as_str())
+            })
+            .collect();
+        assert_eq!(closes, vec![("refund_bet".to_string(), false, "player"), ("ResolveBet (no handler)".to_string(), true, "house")]);
+    }
+}
@@ -1,8 +1,15 @@
 // Submodules
 pub mod parser;
 pub mod normalize;
+pub mod constraints;
+pub mod space;
+pub mod suppression;
+pub mod lifecycle;
+pub mod authorization;
+pub mod idl;
 pub mod rules;
 
 // Re-export everything from submodules for easier access
 pub use normalize::*;
+pub use constraints::*;
 pub use rules::*;
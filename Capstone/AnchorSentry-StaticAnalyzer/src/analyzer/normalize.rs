@@ -1,91 +1,97 @@
 use quote::ToTokens;
 use syn::spanned::Spanned;
-use syn::{Attribute, ItemStruct};
-use std::collections::HashMap;
+use syn::Attribute;
 use crate::state::*;
+use crate::analyzer::constraints::AccountConstraints;
 
 
 
-pub fn normalize_program(ast: &syn::File) -> (
-    Vec<NormalizedAccountStruct>,
-    Vec<ItemStruct>,
-    Vec<AccountInstructions>,
-    Vec<NormalizedFunctionArgs>,
-) {
-    let mut account_structs: Vec<NormalizedAccountStruct> = Vec::new();
-    let mut storage_structs: Vec<ItemStruct> = Vec::new();
+/// Builds one program model out of every parsed file, so structs and handlers
+/// split across `lib.rs`, `instructions/*.rs` and `state/*.rs` are seen together.
+pub fn normalize_program(files: &[SourceFile]) -> NormalizedProgram {
+    let mut program = NormalizedProgram::default();
 
-    let mut accounts_with_instructions: Vec<AccountInstructions> = Vec::new();
+    for file in files {
+        normalize_items(&file.ast.items, &file.path, &mut program);
+    }
 
-    let mut functions_args: Vec<NormalizedFunctionArgs> = Vec::new();
+    program
+}
 
-    for item in &ast.items {
+fn normalize_items(items: &[syn::Item], file: &str, program: &mut NormalizedProgram) {
+    for item in items {
         if let syn::Item::Struct(s) = item {
-            if is_anchor_account_struct(&s) {
-                // normalize_struct_field(s);
-
-                let normalized = normalize_struct(&s);
-                // println!("{:?}", normalized);
-                account_structs.push(normalized);
+            if is_anchor_account_struct(s) {
+                program.account_structs.push(normalize_struct(s, file));
             }
 
             if account_struct_has_instruction(s) {
-                // println!("Instruction Args : {:?}", extract_instruction_args(s));
-                accounts_with_instructions = extract_instruction_args(s);
+                program.accounts_with_instructions.extend(extract_instruction_args(s, file));
             }
-        }
 
-        if let syn::Item::Struct(s) = item {
-            if is_anchor_storage_struct(&s) {
-                storage_structs.push(s.clone());
+            if is_anchor_storage_struct(s) {
+                program.type_files.insert(s.ident.to_string(), file.to_string());
+                program.storage_structs.push(s.clone());
+            } else if !is_anchor_account_struct(s) {
+                program.type_files.insert(s.ident.to_string(), file.to_string());
+                program.data_structs.push(s.clone());
             }
         }
 
-        if let syn::Item::Mod(module) = item {
-            if let Some((_, mod_items)) = &module.content {
+        if let syn::Item::Enum(e) = item {
+            program.type_files.insert(e.ident.to_string(), file.to_string());
+            program.enums.push(e.clone());
+        }
+
+        if let syn::Item::Impl(i) = item
+            && let Some(normalized) = normalize_impl(i, file) {
+            program.impls.push(normalized);
+        }
+
+        if let syn::Item::Const(c) = item {
+            program.consts.push(c.clone());
+        }
+
+        if let syn::Item::Mod(module) = item
+            && let Some((_, mod_items)) = &module.content {
+            if is_program_module(module) {
                 for inner in mod_items {
                     if let syn::Item::Fn(func) = inner {
-                        // println!("Found function: {}", func.sig.ident);
-                        // println!("{:?}", extract_fn_args(func))
-                        functions_args.push(extract_fn_args(func));
+                        program.functions_args.push(extract_fn_args(func, file));
                     }
                 }
             }
+            normalize_items(mod_items, file, program);
         }
     }
+}
 
-    (
-        account_structs,
-        storage_structs,
-        accounts_with_instructions,
-        functions_args,
-    )
-
-    // rules__fn_missing_instruction_args(&accounts_with_instructions, &functions_args, r);
-
-    // // println!("{:?}", account_structs);
-    // for a in account_structs {
-    //     // println!("{:?}", a);
-    //     rules__missing_init_if_needed(&a, r);
-    //     rules__wrong_space_assignment(&a, &storage_structs, r);
-    //     // for f in a.fields{
-    //     //     let space = extract_space(f);
-    //     //     for s in &storage_structs {
-
-    //     //     }
-    //     // }
-    // }
-    // r.print();
+/// Inherent impls only, trait impls like `impl Space for X` don't hold instruction logic
+pub fn normalize_impl(i: &syn::ItemImpl, file: &str) -> Option<NormalizedImpl> {
+    if i.trait_.is_some() {
+        return None;
+    }
+    let syn::Type::Path(type_path) = &*i.self_ty else { return None };
+    let type_name = type_path.path.segments.last()?.ident.to_string();
+    let methods = i
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::ImplItem::Fn(f) => Some(f.clone()),
+            _ => None,
+        })
+        .collect();
+    Some(NormalizedImpl { type_name, methods, file: file.to_string() })
 }
 
-pub fn normalize_struct(s: &syn::ItemStruct) -> NormalizedAccountStruct {
+pub fn normalize_struct(s: &syn::ItemStruct, file: &str) -> NormalizedAccountStruct {
     let normalized_fields: Vec<NormalizedAccountField> = normalize_struct_field(s);
-    let normalized_struct = NormalizedAccountStruct {
+    NormalizedAccountStruct {
         name: s.ident.to_string(),
         fields: normalized_fields,
         line: s.ident.span().start().line,
-    };
-    normalized_struct
+        file: file.to_string(),
+    }
 }
 
 pub fn normalize_struct_field(s: &syn::ItemStruct) -> Vec<NormalizedAccountField> {
@@ -97,6 +103,7 @@ pub fn normalize_struct_field(s: &syn::ItemStruct) -> Vec<NormalizedAccountField
         let name = field.ident.as_ref().map(|i| i.to_string()).unwrap();
         // let base_type = &field.ty. path.segments.first()
         let line = field.ident.span().start().line;
+        let column = field.ident.span().start().column + 1;
 
         //base type : Account
         //generic type : ["'info", "TokenAccount"]
@@ -115,12 +122,10 @@ pub fn normalize_struct_field(s: &syn::ItemStruct) -> Vec<NormalizedAccountField
                             syn::GenericArgument::Lifetime(lt) => {
                                 generic_args.push(format!("'{}", lt.ident));
                             }
-                            syn::GenericArgument::Type(ty) => {
-                                // Nested type, like TokenAccount
-                                if let syn::Type::Path(inner_path) = ty {
-                                    if let Some(seg) = inner_path.path.segments.last() {
-                                        generic_args.push(seg.ident.to_string());
-                                    }
+                            // Nested type, like TokenAccount
+                            syn::GenericArgument::Type(syn::Type::Path(inner_path)) => {
+                                if let Some(seg) = inner_path.path.segments.last() {
+                                    generic_args.push(seg.ident.to_string());
                                 }
                             }
                             _ => {}
@@ -130,48 +135,20 @@ pub fn normalize_struct_field(s: &syn::ItemStruct) -> Vec<NormalizedAccountField
             }
         }
 
-        // attribute: seed = [*]
-        let mut attributes: HashMap<String, AttributeValue> = HashMap::new();
-
-        for attr in &field.attrs {
-            // println!("{}",attr.to_token_stream().to_string());
-            // if attr.to_token_stream().is_empty() {
-            //     println!("empty")
-            // }
-
-            let attr_str = attr.to_token_stream().to_string();
-            if attr_str.starts_with("# [account") || attr_str.starts_with("# [accounts") {
-                // filter `# [accounts (` or `# [account (`
-                let attr_str_trimmed = attr_str
-                    .trim_start_matches("# [accounts (")
-                    .trim_start_matches("# [account (")
-                    .trim_end_matches(")]")
-                    .trim();
-                //too stressful for now
-                // if attr_str_trimmed.contains("seeds") {
-                //     continue;
-                // }
-                for token in attr_str_trimmed.split(',') {
-                    let parts: Vec<&str> = token.split('=').collect();
-
-                    if parts.len() == 2 {
-                        let key = parts[0].trim().to_string();
-                        let value = parts[1].trim().to_string();
-                        attributes.insert(key, AttributeValue::String(value));
-                    } else {
-                        let key = parts[0].trim().to_string();
-                        attributes.insert(key, AttributeValue::Bool(true));
-                    }
-                }
-            }
-        }
+        let constraints = AccountConstraints::from_attrs(&field.attrs).unwrap_or_else(|e| {
+            eprintln!("Warning: could not parse #[account(...)] on {}.{}: {}", ctx, name, e);
+            AccountConstraints::default()
+        });
+
         normalized_fields.push(NormalizedAccountField {
             context: ctx,
             name,
             base_type,
             generic_args,
-            attributes,
+            constraints,
+            docs: doc_lines(&field.attrs),
             line,
+            column,
         })
     }
     // println!("{:#?}", normalized_fields);
@@ -184,15 +161,29 @@ pub fn normalize_struct_field(s: &syn::ItemStruct) -> Vec<NormalizedAccountField
     // println!("generic type : {:?}", generic_args);
 }
 
+/// `/// text` is sugar for `#[doc = " text"]`
+pub fn doc_lines(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|a| match &a.meta {
+            syn::Meta::NameValue(nv) => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
 
 pub fn is_anchor_account_struct(s: &syn::ItemStruct) -> bool {
-    if s.attrs.iter().any(|a: &Attribute| a.path().is_ident("derive")){
-        //@note: works for now
-        if s.attrs.iter().any(|c| c.to_token_stream().to_string().contains("Accounts")) {
-            // println!("Finally");
-            return true
-        }
-    }   
+    //@note: works for now
+    if s.attrs.iter().any(|a: &Attribute| a.path().is_ident("derive"))
+        && s.attrs.iter().any(|c| c.to_token_stream().to_string().contains("Accounts")) {
+        // println!("Finally");
+        return true
+    }
     false
 }
 
@@ -200,6 +191,28 @@ pub fn is_anchor_storage_struct(s: &syn::ItemStruct) -> bool {
     s.attrs.iter().any(|a: &Attribute| a.path().is_ident("account"))
 }
 
+pub fn is_program_module(m: &syn::ItemMod) -> bool {
+    m.attrs.iter().any(|a: &Attribute| a.path().is_ident("program"))
+}
+
+/// Handler fns of the `#[program]` module, wherever it is nested
+pub fn program_handlers(items: &[syn::Item]) -> Vec<&syn::ItemFn> {
+    let mut out = Vec::new();
+    for item in items {
+        if let syn::Item::Mod(m) = item
+            && let Some((_, inner)) = &m.content {
+            if is_program_module(m) {
+                out.extend(inner.iter().filter_map(|i| match i {
+                    syn::Item::Fn(f) => Some(f),
+                    _ => None,
+                }));
+            }
+            out.extend(program_handlers(inner));
+        }
+    }
+    out
+}
+
 pub fn account_struct_has_instruction(s: &syn::ItemStruct) -> bool {
     if s.attrs.iter().any(|a: &Attribute| a.path().is_ident("instruction")){
         //@note: works for now
@@ -209,7 +222,7 @@ pub fn account_struct_has_instruction(s: &syn::ItemStruct) -> bool {
     false
 }
 
-pub fn extract_instruction_args(s: &syn::ItemStruct) -> Vec<AccountInstructions> {
+pub fn extract_instruction_args(s: &syn::ItemStruct, file: &str) -> Vec<AccountInstructions> {
     let mut i_vec = Vec::new();
 
     for attr in &s.attrs {
@@ -239,9 +252,13 @@ pub fn extract_instruction_args(s: &syn::ItemStruct) -> Vec<AccountInstructions>
                 }
             }
 
+            let path_span = attr.path().span();
             i_vec.push(AccountInstructions {
                 ctx_name: s.ident.to_string(),
-                args: out
+                args: out,
+                file: file.to_string(),
+                line: path_span.start().line,
+                column: path_span.start().column + 1,
             });
         }
     }
@@ -250,13 +267,14 @@ pub fn extract_instruction_args(s: &syn::ItemStruct) -> Vec<AccountInstructions>
 }
 
 
-pub fn extract_fn_args(fn_item: &syn::ItemFn) -> NormalizedFunctionArgs {
+pub fn extract_fn_args(fn_item: &syn::ItemFn, file: &str) -> NormalizedFunctionArgs {
     let mut context_name = String::new();
     let mut args = Vec::new();
 
     // function name
     let func_name = fn_item.sig.ident.to_string();
     let line  = fn_item.sig.ident.span().start().line;
+    let column = fn_item.sig.ident.span().start().column + 1;
 
     for input in &fn_item.sig.inputs {
         if let syn::FnArg::Typed(pat_type) = input {
@@ -270,13 +288,15 @@ pub fn extract_fn_args(fn_item: &syn::ItemFn) -> NormalizedFunctionArgs {
                 let ty_ident = last.ident.to_string();
 
                 if ty_ident == "Context" {
-                    if let syn::PathArguments::AngleBracketed(args_generic) = &last.arguments {
-                        if let Some(syn::GenericArgument::Type(syn::Type::Path(inner))) = args_generic.args.first()
-                        {
-                            if let Some(seg) = inner.path.segments.last() {
-                                context_name = seg.ident.to_string();
-                            }
-                        }
+                    // the Accounts struct is the last type argument, `Context<'_, '_, '_, 'info, Claim<'info>>`
+                    if let syn::PathArguments::AngleBracketed(args_generic) = &last.arguments
+                        && let Some(inner) = args_generic.args.iter().rev().find_map(|a| match a {
+                            syn::GenericArgument::Type(syn::Type::Path(inner)) => Some(inner),
+                            _ => None,
+                        })
+                        && let Some(seg) = inner.path.segments.last()
+                    {
+                        context_name = seg.ident.to_string();
                     }
                     continue; 
                 }
@@ -293,6 +313,8 @@ pub fn extract_fn_args(fn_item: &syn::ItemFn) -> NormalizedFunctionArgs {
         name: func_name,
         context: context_name,
         args,
-        line
+        line,
+        column,
+        file: file.to_string(),
     }
 }
\ No newline at end of file
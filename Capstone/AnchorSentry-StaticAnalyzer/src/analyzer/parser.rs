@@ -0,0 +1,336 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use anyhow::{Context, Result, bail};
+use syn::Item;
+use crate::state::SourceFile;
+use crate::read_source_file;
+
+/// A program found inside a workspace, i.e `programs/anchor-escrow-q4-25`
+#[derive(Debug, Clone)]
+pub struct ProgramSource {
+    pub name: String,
+    pub files: Vec<SourceFile>,
+}
+
+/// Locate every Anchor program under `root` and load all of its source files.
+///
+/// `root` can be an Anchor workspace (`Anchor.toml`), a cargo workspace, or a single program crate.
+pub fn load_workspace(root: &Path) -> Result<Vec<ProgramSource>> {
+    let crates = find_program_crates(root)?;
+    if crates.is_empty() {
+        bail!("No Anchor program found under {}", root.display());
+    }
+
+    let mut programs = Vec::new();
+    for krate in crates {
+        let lib_rs = krate.join("src").join("lib.rs");
+        if !lib_rs.is_file() {
+            continue;
+        }
+        let name = krate
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        programs.push(ProgramSource {
+            name,
+            files: load_module_tree(&lib_rs)?,
+        });
+    }
+    Ok(programs)
+}
+
+/// Returns the crate directories that make up the workspace at `root`.
+pub fn find_program_crates(root: &Path) -> Result<Vec<PathBuf>> {
+    let cargo_toml = root.join("Cargo.toml");
+    let anchor_toml = root.join("Anchor.toml");
+
+    if cargo_toml.is_file() {
+        let raw = fs::read_to_string(&cargo_toml)
+            .with_context(|| format!("Failed to read {}", cargo_toml.display()))?;
+        let manifest: toml::Table = raw
+            .parse()
+            .with_context(|| format!("Failed to parse {}", cargo_toml.display()))?;
+
+        let mut crates = Vec::new();
+        if manifest.contains_key("package") {
+            crates.push(root.to_path_buf());
+        }
+        let workspace_list = |key: &str| -> Vec<&str> {
+            manifest
+                .get("workspace")
+                .and_then(|w| w.get(key))
+                .and_then(|m| m.as_array())
+                .map(|m| m.iter().filter_map(|m| m.as_str()).collect())
+                .unwrap_or_default()
+        };
+        for member in workspace_list("members") {
+            crates.extend(expand_member(root, member));
+        }
+        // `exclude` entries are paths, a crate inside an excluded directory is excluded too
+        let excluded: Vec<PathBuf> = workspace_list("exclude").into_iter().map(|e| normalize(&root.join(e))).collect();
+        crates.retain(|c| !excluded.iter().any(|e| normalize(c).starts_with(e)));
+        return Ok(crates);
+    }
+
+    if anchor_toml.is_file() {
+        // Anchor workspaces without a cargo manifest still keep programs in `programs/*`
+        return Ok(expand_member(root, "programs/*"));
+    }
+
+    bail!("No Anchor.toml or Cargo.toml found in {}", root.display())
+}
+
+/// Expand a workspace member, `*` and `?` match within a path component i.e `programs/*` or `crates/*-program`
+fn expand_member(root: &Path, member: &str) -> Vec<PathBuf> {
+    if !member.contains(['*', '?']) {
+        return vec![root.join(member)];
+    }
+
+    let mut dirs = vec![root.to_path_buf()];
+    for part in member.split('/').filter(|p| !p.is_empty() && *p != ".") {
+        dirs = dirs
+            .into_iter()
+            .flat_map(|dir| {
+                if !part.contains(['*', '?']) {
+                    return vec![dir.join(part)];
+                }
+                let mut matches: Vec<PathBuf> = fs::read_dir(&dir)
+                    .map(|entries| {
+                        entries
+                            .filter_map(|e| e.ok())
+                            .filter(|e| e.path().is_dir() && glob_match(part, &e.file_name().to_string_lossy()))
+                            .map(|e| e.path())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                matches.sort();
+                matches
+            })
+            .collect();
+    }
+    dirs.retain(|d| d.join("Cargo.toml").is_file());
+    dirs
+}
+
+/// `*` matches any run of characters, `?` a single one
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    // position of the last `*` in the pattern and of the name char it was matched up to
+    let (mut pi, mut ni, mut star) = (0, 0, None);
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// Drops `.` and resolves `..` without touching the file system, `exclude` entries may not exist
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Parse `entry` and every file reachable from it through `mod` declarations.
+pub fn load_module_tree(entry: &Path) -> Result<Vec<SourceFile>> {
+    let mut files = Vec::new();
+    let dir = entry.parent().unwrap_or(Path::new(".")).to_path_buf();
+    load_file(entry, &dir, &mut files, &mut HashSet::new())?;
+    Ok(files)
+}
+
+/// `visited` holds the canonical path of every file loaded so far, `#[path]` can point back at one
+fn load_file(path: &Path, mod_dir: &Path, files: &mut Vec<SourceFile>, visited: &mut HashSet<PathBuf>) -> Result<()> {
+    if !visited.insert(path.canonicalize().unwrap_or_else(|_| path.to_path_buf())) {
+        eprintln!("Warning: {} is declared as a module more than once, skipping it", path.display());
+        return Ok(());
+    }
+    let file = read_source_file(path)?;
+    let mut children = Vec::new();
+    // `#[path]` on a top level `mod` is relative to the declaring file, not to its module directory
+    let file_dir = path.parent().unwrap_or(Path::new("."));
+    collect_mod_files(&file.ast.items, mod_dir, file_dir, &mut children, visited)?;
+    files.push(file);
+    files.extend(children);
+    Ok(())
+}
+
+fn collect_mod_files(items: &[Item], mod_dir: &Path, path_dir: &Path, files: &mut Vec<SourceFile>, visited: &mut HashSet<PathBuf>) -> Result<()> {
+    for item in items {
+        let Item::Mod(module) = item else { continue };
+        let name = module.ident.to_string();
+
+        // inline module, its children (and their `#[path]`s) live in a sub directory named after it
+        if let Some((_, inner)) = &module.content {
+            let inner_dir = mod_dir.join(&name);
+            collect_mod_files(inner, &inner_dir, &inner_dir, files, visited)?;
+            continue;
+        }
+
+        let Some(child) = resolve_mod_path(mod_dir, path_dir, &name, path_attr(module).as_deref()) else {
+            eprintln!("Warning: could not find source for `mod {}` in {}", name, mod_dir.display());
+            continue;
+        };
+
+        // `foo/mod.rs` keeps its children next to it, `foo.rs` keeps them in `foo/`
+        let child_dir = if child.file_name().is_some_and(|f| f == "mod.rs") || path_attr(module).is_some() {
+            child.parent().unwrap_or(mod_dir).to_path_buf()
+        } else {
+            mod_dir.join(&name)
+        };
+
+        load_file(&child, &child_dir, files, visited)?;
+    }
+    Ok(())
+}
+
+fn resolve_mod_path(mod_dir: &Path, path_dir: &Path, name: &str, path_attr: Option<&str>) -> Option<PathBuf> {
+    if let Some(p) = path_attr {
+        let p = path_dir.join(p);
+        return p.is_file().then_some(p);
+    }
+    [mod_dir.join(format!("{name}.rs")), mod_dir.join(name).join("mod.rs")]
+        .into_iter()
+        .find(|p| p.is_file())
+}
+
+/// Reads `#[path = "..."]` on a module declaration
+fn path_attr(module: &syn::ItemMod) -> Option<String> {
+    module.attrs.iter().find_map(|a| {
+        if !a.path().is_ident("path") {
+            return None;
+        }
+        if let syn::Meta::NameValue(nv) = &a.meta
+            && let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) = &nv.value {
+            return Some(s.value());
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_attr_resolves_against_the_declaring_file() {
+        let root = std::env::temp_dir().join(format!("anchor-sentry-path-attr-{}", std::process::id()));
+        let src = root.join("src");
+        fs::create_dir_all(src.join("foo")).unwrap();
+        fs::write(src.join("lib.rs"), "mod foo;").unwrap();
+        // rustc looks for `src/bar_impl.rs`, next to foo.rs, not in `src/foo/`
+        fs::write(src.join("foo.rs"), "#[path = \"bar_impl.rs\"] mod bar;").unwrap();
+        fs::write(src.join("bar_impl.rs"), "pub fn bar() {}").unwrap();
+        fs::write(src.join("foo").join("bar_impl.rs"), "pub fn wrong() {}").unwrap();
+
+        let files = load_module_tree(&src.join("lib.rs")).unwrap();
+        let paths: Vec<PathBuf> = files.iter().map(|f| PathBuf::from(&f.path)).collect();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(paths, vec![src.join("lib.rs"), src.join("foo.rs"), src.join("bar_impl.rs")]);
+    }
+
+    /// A fresh directory under the system temp dir, removed by the caller
+    fn temp_dir(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("anchor-sentry-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn write(path: PathBuf, text: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn nested_and_inline_modules() {
+        let root = temp_dir("nested-mods");
+        let src = root.join("src");
+        write(src.join("lib.rs"), "mod instructions; mod state { mod bet; }");
+        write(src.join("instructions.rs"), "mod make;");
+        write(src.join("instructions").join("make.rs"), "pub fn make() {}");
+        write(src.join("state").join("bet").join("mod.rs"), "mod seeds;");
+        write(src.join("state").join("bet").join("seeds.rs"), "pub const SEED: &[u8] = b\"bet\";");
+
+        let files = load_module_tree(&src.join("lib.rs")).unwrap();
+        let paths: Vec<PathBuf> = files.iter().map(|f| PathBuf::from(&f.path)).collect();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(paths, vec![
+            src.join("lib.rs"),
+            src.join("instructions.rs"),
+            src.join("instructions").join("make.rs"),
+            src.join("state").join("bet").join("mod.rs"),
+            src.join("state").join("bet").join("seeds.rs"),
+        ]);
+    }
+
+    #[test]
+    fn path_attr_cycle_is_loaded_once() {
+        let root = temp_dir("mod-cycle");
+        let src = root.join("src");
+        write(src.join("lib.rs"), "mod a;");
+        write(src.join("a.rs"), "#[path = \"b.rs\"] mod b;");
+        write(src.join("b.rs"), "#[path = \"a.rs\"] mod a; #[path = \"lib.rs\"] mod root;");
+
+        let files = load_module_tree(&src.join("lib.rs")).unwrap();
+        let paths: Vec<PathBuf> = files.iter().map(|f| PathBuf::from(&f.path)).collect();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(paths, vec![src.join("lib.rs"), src.join("a.rs"), src.join("b.rs")]);
+    }
+
+    #[test]
+    fn workspace_members_globs_and_exclude() {
+        let root = temp_dir("members");
+        write(root.join("Cargo.toml"), r#"[workspace]
+members = ["programs/*", "crates/*-program", "tools/cli"]
+exclude = ["programs/legacy", "./crates/old-program"]
+"#);
+        for krate in ["programs/escrow", "programs/vault", "programs/legacy", "crates/dice-program", "crates/old-program", "crates/helpers", "tools/cli"] {
+            write(root.join(krate).join("Cargo.toml"), "[package]");
+        }
+        fs::create_dir_all(root.join("programs").join("docs")).unwrap();
+
+        let crates = find_program_crates(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(crates, vec![
+            root.join("programs").join("escrow"),
+            root.join("programs").join("vault"),
+            root.join("crates").join("dice-program"),
+            root.join("tools/cli"),
+        ]);
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_match("*", "escrow"));
+        assert!(glob_match("*-program", "dice-program"));
+        assert!(!glob_match("*-program", "dice-programs"));
+        assert!(glob_match("v?ult*", "vault-v2"));
+        assert!(glob_match("a*b*c", "axxbyybzc"));
+        assert!(!glob_match("a*b", "ac"));
+    }
+}
@@ -1,13 +1,55 @@
-// Submodules
-pub mod rule_init_if_needed;
-pub mod rule_missing_fn_arg;
-pub mod rule_wrong_space_assignment;
-pub mod rule_missing_account_verification;
-pub mod visit;
-
-// Re-export all rules for easier access
-pub use rule_init_if_needed::*;
-pub use rule_missing_fn_arg::*;
-pub use rule_wrong_space_assignment::*;
-pub use rule_missing_account_verification::*;
-pub use visit::*;
+pub mod registry;
+pub use registry::*;
+
+/// Declares the rule modules, re-exports them and lists the rules each one defines.
+/// `RuleRegistry::default` registers every listed rule, ordered by rule code.
+macro_rules! rules {
+    (
+        visit { $( $visit_module:ident => [$($visit_rule:ident),+ $(,)?], )* }
+        $( $module:ident => [$($rule:ident),+ $(,)?], )*
+    ) => {
+        $(
+            pub mod $module;
+            pub use $module::*;
+        )*
+
+        /// Rules built on a `syn::visit::Visit` walk of the handler code
+        pub mod visit {
+            $(
+                pub mod $visit_module;
+                pub use $visit_module::*;
+            )*
+        }
+        pub use visit::*;
+
+        /// One instance of every built-in rule, in declaration order
+        pub fn builtin_rules() -> Vec<Box<dyn Rule>> {
+            vec![
+                $( $( Box::new($visit_rule) as Box<dyn Rule>, )+ )*
+                $( $( Box::new($rule) as Box<dyn Rule>, )+ )*
+            ]
+        }
+    };
+}
+
+// A new detector is its file plus its line here
+rules! {
+    visit {
+        rule_arbitrary_cpi => [ArbitraryCpiRule],
+        rule_insecure_randomness => [InsecureRandomnessRule],
+        rule_instruction_introspection => [InstructionIntrospectionRule],
+        rule_unchecked_data_read => [UncheckedDataReadRule],
+        rule_non_canonical_bump => [NonCanonicalBumpRule],
+        rule_arithmetic_overflow => [LossyCastRule, UncheckedArithmeticRule],
+        rule_unsafe_close => [UnsafeAccountCloseRule],
+        rule_division_by_zero => [DivisionByZeroRule],
+    }
+    rule_init_if_needed => [MissingInitIfNeededRule],
+    rule_wrong_space_assignment => [WrongSpaceAssignmentRule],
+    rule_missing_fn_arg => [MissingInstructionArgsRule],
+    rule_duplicate_mutable_accounts => [DuplicateMutableAccountsRule],
+    rule_missing_has_one => [MissingHasOneRule],
+    rule_account_lifecycle => [WriteAfterCloseRule, AccountNeverClosedRule, MultipleClosePathsRule],
+    rule_idl_mismatch => [IdlMismatchRule],
+    rule_missing_account_verification => [MissingAccountVerificationRule, MissingCheckDocRule],
+}
@@ -0,0 +1,98 @@
+use std::cell::OnceCell;
+
+use crate::analyzer::idl::LoadedIdl;
+use crate::analyzer::lifecycle::{program_lifecycle, ProgramLifecycle};
+use crate::report::knowledge_base::RuleMetadata;
+use crate::report::report::Report;
+use crate::state::{NormalizedProgram, SourceFile};
+
+use super::*;
+
+/// Everything a rule gets to look at: the raw parsed files and the normalized program built from them.
+pub struct AnalysisContext<'a> {
+    pub files: &'a [SourceFile],
+    pub program: &'a NormalizedProgram,
+    // IDL passed with `--idl`, the program is built from it alone when there are no files
+    pub idl: Option<&'a LoadedIdl>,
+    // computed by the first rule that needs it and shared with the others
+    arithmetic: OnceCell<Vec<Vec<ArithmeticIssue>>>,
+    lifecycle: OnceCell<ProgramLifecycle>,
+}
+
+impl<'a> AnalysisContext<'a> {
+    pub fn new(files: &'a [SourceFile], program: &'a NormalizedProgram, idl: Option<&'a LoadedIdl>) -> Self {
+        AnalysisContext { files, program, idl, arithmetic: OnceCell::new(), lifecycle: OnceCell::new() }
+    }
+
+    /// Arithmetic and cast issues of each file, in `files` order, from a single walk shared by L-004 and M-006
+    pub fn arithmetic_issues(&self) -> &[Vec<ArithmeticIssue>] {
+        self.arithmetic.get_or_init(|| self.files.iter().map(|file| arithmetic_issues(file, self.program)).collect())
+    }
+
+    /// How every storage type is created, written and closed, shared by the three lifecycle rules
+    pub fn lifecycle(&self) -> &ProgramLifecycle {
+        self.lifecycle.get_or_init(|| program_lifecycle(self.program, self.files))
+    }
+}
+
+/// A detector. Implement this in its own file under `analyzer::rules` with its `RuleMetadata`
+/// static next to it and add the file to the `rules!` list in `rules/mod.rs`, which declares the
+/// module and registers the rule. The text, JSON and SARIF output pick its findings up without further changes.
+pub trait Rule {
+    /// Stable machine name i.e `missing_init_if_needed`
+    fn id(&self) -> &'static str;
+
+    fn metadata(&self) -> &'static RuleMetadata;
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report);
+}
+
+pub struct RuleRegistry {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl RuleRegistry {
+    pub fn empty() -> Self {
+        RuleRegistry { rules: Vec::new() }
+    }
+
+    pub fn register(&mut self, rule: Box<dyn Rule>) {
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> &[Box<dyn Rule>] {
+        &self.rules
+    }
+
+    pub fn get(&self, id_or_code: &str) -> Option<&dyn Rule> {
+        self.rules
+            .iter()
+            .find(|r| r.id() == id_or_code || r.metadata().code == id_or_code)
+            .map(|r| r.as_ref())
+    }
+
+    /// Keeps the rules whose id or code is in `ids`
+    pub fn retain(&mut self, ids: &[&str]) {
+        self.rules.retain(|r| ids.contains(&r.id()) || ids.contains(&r.metadata().code));
+    }
+
+    /// Metadata of every registered rule, in registration order
+    pub fn metadata(&self) -> Vec<&'static RuleMetadata> {
+        self.rules.iter().map(|r| r.metadata()).collect()
+    }
+
+    pub fn run(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for rule in &self.rules {
+            rule.check(ctx, r);
+        }
+    }
+}
+
+impl Default for RuleRegistry {
+    /// All built-in rules in rule code order: high, medium then low, each by number
+    fn default() -> Self {
+        let mut rules = builtin_rules();
+        rules.sort_by_key(|r| (r.metadata().severity, r.metadata().code));
+        RuleRegistry { rules }
+    }
+}
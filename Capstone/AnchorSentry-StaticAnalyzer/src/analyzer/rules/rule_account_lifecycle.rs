@@ -0,0 +1,297 @@
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::analyzer::lifecycle::{AccountLifecycle, CloseSite, LifecycleStep};
+use crate::state::NormalizedProgram;
+use super::registry::{AnalysisContext, Rule};
+
+/* ────────────────────────────────────────────────────────────────
+   M-009 — Account Written After Close
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_WRITE_AFTER_CLOSE: RuleMetadata = RuleMetadata {
+    code: "M-009",
+    title: "Account Data Written After Close",
+    description:
+        "An instruction writes to a state account it also closes. `close = target` runs when the instruction \
+         exits and a manual `close()` hands the account to the system program, so Anchor never serializes the \
+         write: the update the code relies on, such as a status flag or a decremented counter, is silently lost. \
+         Other instructions that read that state later keep seeing the old value, or the account is revived \
+         with stale data.",
+    severity: Severity::Medium,
+    recommendation:
+        "Move the update to an account that outlives the instruction, i.e decrement the counter on the user \
+         account rather than on the closed stake account, or drop the write when closing is the intended final \
+         state. Never write to an account after calling `close()` on it.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/references/account-constraints#accountclose--target\n\
+         https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/9-closing-accounts"
+    ),
+};
+
+/* ────────────────────────────────────────────────────────────────
+   L-005 — State Account Never Closed
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_ACCOUNT_NEVER_CLOSED: RuleMetadata = RuleMetadata {
+    code: "L-005",
+    title: "State Account Never Closed",
+    description:
+        "Some instruction creates accounts of this type but no instruction closes them. The rent paid on \
+         creation stays locked forever, and for PDAs the address can never be reused, so a user who finished \
+         with a bet, stake or escrow is stuck with it. It often means a settle or withdraw path forgot its \
+         `close = ..` constraint.",
+    severity: Severity::Low,
+    recommendation:
+        "Add `close = <rent receiver>` to the account in the instruction that ends its lifecycle, or document \
+         why the account lives forever, i.e a global config.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/references/account-constraints#accountclose--target\n\
+         https://solana.com/docs/core/fees#rent"
+    ),
+};
+
+/* ────────────────────────────────────────────────────────────────
+   L-006 — Multiple Close Paths
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_MULTIPLE_CLOSE_PATHS: RuleMetadata = RuleMetadata {
+    code: "L-006",
+    title: "State Account Closed by Multiple Instructions",
+    description:
+        "Accounts of this type can be closed by more than one instruction, i.e a bet can be refunded or \
+         resolved. Each path settles the account differently, so they must exclude each other: a player who \
+         sees an unfavourable resolve coming can race it with a refund unless the refund is only allowed \
+         after a timeout or in a state the resolve can't reach.",
+    severity: Severity::Low,
+    recommendation:
+        "Check the state or time each close path requires, such as a status field, a slot timeout or the \
+         signer allowed to call it, and make sure no two paths accept the same account at the same time.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/references/account-constraints#accountclose--target\n\
+         https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/9-closing-accounts"
+    ),
+};
+
+pub struct AccountNeverClosedRule;
+
+impl Rule for AccountNeverClosedRule {
+    fn id(&self) -> &'static str {
+        "account_never_closed"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_ACCOUNT_NEVER_CLOSED
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for account in &ctx.lifecycle().accounts {
+            rules_account_never_closed(account, ctx.program, r);
+        }
+    }
+}
+
+pub struct MultipleClosePathsRule;
+
+impl Rule for MultipleClosePathsRule {
+    fn id(&self) -> &'static str {
+        "multiple_close_paths"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_MULTIPLE_CLOSE_PATHS
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for account in &ctx.lifecycle().accounts {
+            rules_multiple_close_paths(account, r);
+        }
+    }
+}
+
+pub struct WriteAfterCloseRule;
+
+impl Rule for WriteAfterCloseRule {
+    fn id(&self) -> &'static str {
+        "write_after_close"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_WRITE_AFTER_CLOSE
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for account in &ctx.lifecycle().accounts {
+            rules_write_after_close(account, r);
+        }
+    }
+}
+
+/// `close = player` or `bet.close(player)`
+fn describe_close(step: &LifecycleStep, close: &CloseSite) -> String {
+    if close.manual {
+        format!("`{}.close({})`", step.account, close.destination)
+    } else {
+        format!("`close = {}`", close.destination)
+    }
+}
+
+/// Where the `#[account]` struct of `type_name` is declared
+fn type_location(account: &AccountLifecycle, program: &NormalizedProgram) -> Option<Location> {
+    let file = account.file.as_ref()?;
+    let s = program.storage_structs.iter().find(|s| s.ident == account.type_name.as_str())?;
+    Some(Location::from_span(file, s.ident.span()))
+}
+
+pub fn rules_account_never_closed(account: &AccountLifecycle, program: &NormalizedProgram, r: &mut Report) {
+    if account.closers().next().is_some() {
+        return;
+    }
+    let creators: Vec<&LifecycleStep> = account.creators().collect();
+    let Some(first) = creators.first() else { return };
+    let created_by: Vec<String> = creators.iter().map(|s| s.instruction_label(true)).collect();
+
+    let mut finding = Finding::new(
+        &RULE_ACCOUNT_NEVER_CLOSED,
+        first.location.clone(),
+        &first.context,
+        &account.type_name,
+        format!(
+            "`{}` accounts are created by {} but no instruction closes them, their rent stays locked",
+            account.type_name, created_by.join(", ")
+        ),
+    )
+    .detail("Account Type", &account.type_name)
+    .detail("Created By", created_by.join(", "));
+    if let Some(decl) = type_location(account, program) {
+        finding = finding.label(decl, format!("`{}` is declared here", account.type_name));
+    }
+    r.add(finding);
+}
+
+pub fn rules_multiple_close_paths(account: &AccountLifecycle, r: &mut Report) {
+    let closers: Vec<&LifecycleStep> = account.closers().collect();
+    let mut instructions: Vec<String> = Vec::new();
+    for step in &closers {
+        let label = step.instruction_label(false);
+        if !instructions.contains(&label) {
+            instructions.push(label);
+        }
+    }
+    if instructions.len() < 2 {
+        return;
+    }
+    let paths: Vec<String> = closers
+        .iter()
+        .filter_map(|s| s.close.as_ref().map(|c| format!("{} with {}", s.instruction_label(true), describe_close(s, c))))
+        .collect();
+    let last = closers[closers.len() - 1];
+
+    let mut finding = Finding::new(
+        &RULE_MULTIPLE_CLOSE_PATHS,
+        last.location.clone(),
+        &last.context,
+        &account.type_name,
+        format!(
+            "`{}` accounts are closed by {}, each path must rule out the others for the same account",
+            account.type_name, paths.join(" and ")
+        ),
+    )
+    .detail("Account Type", &account.type_name)
+    .detail("Close Paths", instructions.join(", "));
+    for other in &closers[..closers.len() - 1] {
+        if let Some(close) = &other.close {
+            finding = finding.label(close.location.clone(), format!("also closed by {} here", other.instruction_label(true)));
+        }
+    }
+    r.add(finding);
+}
+
+pub fn rules_write_after_close(account: &AccountLifecycle, r: &mut Report) {
+    for step in account.closers() {
+        let Some(close) = &step.close else { continue };
+        // a manual close only loses the writes after it, `close = ..` runs on exit and loses every write
+        let lost: Vec<_> = step
+            .writes
+            .iter()
+            .filter(|w| {
+                !close.manual
+                    || (close.function_name.as_deref() == Some(w.function_name.as_str())
+                        && (w.location.line, w.location.column) > (close.location.line, close.location.column))
+            })
+            .collect();
+        let Some(first) = lost.first() else { continue };
+        let when = if close.manual { "after" } else { "before" };
+
+        let mut finding = Finding::new(
+            &RULE_WRITE_AFTER_CLOSE,
+            first.location.clone(),
+            &step.context,
+            &step.account,
+            format!(
+                "`{}` writes `{}` {} `{}` is closed by {}, the write is never persisted",
+                first.function_name, first.target, when, step.account, describe_close(step, close)
+            ),
+        )
+        .detail("Account", &step.account)
+        .detail("Account Type", &account.type_name)
+        .detail("Instruction", step.instruction_label(false))
+        .label(close.location.clone(), format!("`{}` is closed here", step.account));
+        for other in &lost[1..] {
+            finding = finding.label(other.location.clone(), format!("`{}` is lost as well", other.target));
+        }
+        r.add(finding);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::lifecycle::tests::{lifecycle_of, DICE};
+
+    /// Messages of `rule` for every account type of `source`
+    fn messages(source: &str, rule: fn(&AccountLifecycle, &mut Report)) -> Vec<String> {
+        let mut r = Report::default();
+        for account in &lifecycle_of(source).accounts {
+            rule(account, &mut r);
+        }
+        r.findings.into_iter().map(|f| f.message).collect()
+    }
+
+    #[test]
+    fn never_closed() {
+        let never_closed = |a: &AccountLifecycle, r: &mut Report| rules_account_never_closed(a, &NormalizedProgram::default(), r);
+        assert!(messages(DICE, never_closed).is_empty());
+
+        let without_closes = DICE.replace("#[account(mut, close = player)]", "#[account(mut)]").replace("self.bet.close(self.house.to_account_info())?;", "");
+        assert_eq!(
+            messages(&without_closes, never_closed),
+            vec!["`Bet` accounts are created by `place_bet` but no instruction closes them, their rent stays locked"]
+        );
+    }
+
+    #[test]
+    fn multiple_close_paths_name_instructions() {
+        assert_eq!(
+            messages(DICE, rules_multiple_close_paths),
+            vec![
+                "`Bet` accounts are closed by `refund_bet` with `close = player` and `ResolveBet` (no handler) with \
+                 `bet.close(house)`, each path must rule out the others for the same account"
+            ]
+        );
+        let single = DICE.replace("self.bet.close(self.house.to_account_info())?;", "");
+        assert!(messages(&single, rules_multiple_close_paths).is_empty());
+    }
+
+    #[test]
+    fn write_after_close() {
+        // `close = player` runs on exit, the refund write is lost; the manual close loses the write after it
+        let lost = messages(DICE, rules_write_after_close);
+        assert_eq!(lost.len(), 2);
+        assert!(lost[0].starts_with("`refund` writes `self.bet.amount` before `bet` is closed by `close = player`"));
+        assert!(lost[1].starts_with("`resolve` writes `self.bet.slot` after `bet` is closed by `bet.close(house)`"));
+
+        // writing before a manual close is persisted
+        let write_first = DICE.replace(
+            "self.bet.close(self.house.to_account_info())?;\n                self.bet.slot = 1;",
+            "self.bet.slot = 1;\n                self.bet.close(self.house.to_account_info())?;",
+        );
+        assert_eq!(messages(&write_first, rules_write_after_close).len(), 1);
+    }
+}
@@ -0,0 +1,311 @@
+use syn::Expr;
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::analyzer::constraints::{expr_to_string, mentions_ident};
+use crate::state::{NormalizedAccountField, NormalizedAccountStruct};
+use super::registry::{AnalysisContext, Rule};
+
+/* ────────────────────────────────────────────────────────────────
+   M-004 — Duplicate Mutable Accounts
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_DUPLICATE_MUTABLE_ACCOUNTS: RuleMetadata = RuleMetadata {
+    code: "M-004",
+    title: "Duplicate Mutable Accounts",
+    description:
+        "Two mutable accounts of the same type can be satisfied by passing the same account twice. Anchor \
+         does not reject duplicates, so logic that assumes two distinct accounts (a transfer from one token \
+         account to another, crediting one state account while debiting another) operates on a single account \
+         through two handles. Writes through one handle are then overwritten by the other on serialization, \
+         which can mint balances out of nothing or bypass accounting.",
+    severity: Severity::Medium,
+    recommendation:
+        "Make the accounts provably distinct: add `constraint = a.key() != b.key()`, derive them from seeds \
+         that differ in a constant part, or tie them to authorities that cannot be the same account. If \
+         aliasing is intended, document it and make sure the instruction logic is correct when both are equal.",
+    additional_links: Some(
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/6-duplicate-mutable-accounts\n\
+         https://solana.com/developers/courses/program-security/duplicate-mutable-accounts"
+    ),
+};
+
+/// Typed accounts Anchor deserializes and writes back on exit, where one handle overwrites the other.
+/// Raw accounts are usually handed to a CPI that validates them itself.
+const DATA_ACCOUNT_TYPES: &[&str] = &["Account", "InterfaceAccount", "AccountLoader"];
+/// Types that only say "some address", any two of them may be the same wallet
+const WALLET_TYPES: &[&str] = &["Signer", "SystemAccount", "UncheckedAccount", "AccountInfo"];
+
+// derivations like ata -> authority -> ... are shallow, this only guards against cycles
+const MAX_DEPTH: usize = 4;
+
+pub struct DuplicateMutableAccountsRule;
+
+impl Rule for DuplicateMutableAccountsRule {
+    fn id(&self) -> &'static str {
+        "duplicate_mutable_accounts"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_DUPLICATE_MUTABLE_ACCOUNTS
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for s in &ctx.program.account_structs {
+            rules_duplicate_mutable_accounts(s, r);
+        }
+    }
+}
+
+/// `InterfaceAccount<'info, TokenAccount>` -> `InterfaceAccount<TokenAccount>`, lifetimes dropped
+pub fn type_key(field: &NormalizedAccountField) -> String {
+    let generics: Vec<&str> = field
+        .generic_args
+        .iter()
+        .filter(|g| !g.starts_with('\''))
+        .map(String::as_str)
+        .collect();
+    if generics.is_empty() {
+        field.base_type.clone()
+    } else {
+        format!("{}<{}>", field.base_type, generics.join(", "))
+    }
+}
+
+/// Writable and possibly pre-existing, `init` accounts can't alias an existing one
+fn is_writable_existing(field: &NormalizedAccountField) -> bool {
+    let c = &field.constraints;
+    !c.init && !c.zero && (c.is_mut() || c.init_if_needed || c.realloc.is_some())
+}
+
+fn is_literal(e: &Expr) -> bool {
+    matches!(e, Expr::Lit(_))
+        || matches!(e, Expr::MethodCall(m) if m.method == "as_ref" && is_literal(&m.receiver))
+        || matches!(e, Expr::Reference(r) if is_literal(&r.expr))
+}
+
+/// The field an expression like `taker` or `taker.key()` names
+fn referenced_field<'a>(s: &'a NormalizedAccountStruct, e: &Expr) -> Option<&'a NormalizedAccountField> {
+    let name = match e {
+        Expr::Path(p) => p.path.get_ident()?.to_string(),
+        Expr::MethodCall(m) if m.method == "key" => match &*m.receiver {
+            Expr::Path(p) => p.path.get_ident()?.to_string(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    s.fields.iter().find(|f| f.name == name)
+}
+
+/// Whether `a` and `b` may resolve to the same account, based on expressions deriving them
+fn exprs_may_alias(s: &NormalizedAccountStruct, a: &Expr, b: &Expr, depth: usize) -> bool {
+    match (referenced_field(s, a), referenced_field(s, b)) {
+        (Some(fa), Some(fb)) => may_alias(s, fa, fb, depth + 1),
+        _ => {
+            // two different constants are different accounts, anything else we can't tell apart
+            expr_to_string(a) == expr_to_string(b) || !(is_constant(a) && is_constant(b))
+        }
+    }
+}
+
+/// A literal or a `SCREAMING_CASE` path like `CORE_PROGRAM_ID`
+fn is_constant(e: &Expr) -> bool {
+    match e {
+        Expr::Path(p) => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|seg| seg.ident.to_string().chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')),
+        other => is_literal(other),
+    }
+}
+
+/// Seeds that differ in a constant part, i.e `b"vault"` vs `b"escrow"`, always derive different PDAs
+fn seeds_distinct(a: &[Expr], b: &[Expr]) -> bool {
+    a.iter()
+        .zip(b)
+        .any(|(x, y)| is_literal(x) && is_literal(y) && expr_to_string(x) != expr_to_string(y))
+}
+
+/// `constraint = a.key() != b.key()` on any field of the struct
+fn explicitly_distinct(s: &NormalizedAccountStruct, a: &str, b: &str) -> bool {
+    s.fields.iter().flat_map(|f| &f.constraints.constraint).filter_map(|c| c.expr.as_ref()).any(|e| {
+        let text = expr_to_string(e).replace(' ', "");
+        text.contains("!=") && mentions_ident(&text, a) && mentions_ident(&text, b)
+    })
+}
+
+/// Token account derivation: (mint, authority) of an ATA or a `token::` constrained account
+fn token_derivation(field: &NormalizedAccountField) -> Option<(&Expr, &Expr)> {
+    let c = &field.constraints;
+    ["associated_token", "token"]
+        .iter()
+        .find_map(|ns| Some((c.namespaced(ns, "mint")?, c.namespaced(ns, "authority")?)))
+}
+
+/// Conservative: true unless something in the struct guarantees `a` and `b` are different accounts
+pub fn may_alias(s: &NormalizedAccountStruct, a: &NormalizedAccountField, b: &NormalizedAccountField, depth: usize) -> bool {
+    if a.name == b.name {
+        return true;
+    }
+    if depth > MAX_DEPTH {
+        return true;
+    }
+    let (ca, cb) = (&a.constraints, &b.constraints);
+
+    // a fresh account can't be an existing one
+    if ca.init || cb.init || ca.zero || cb.zero {
+        return false;
+    }
+    if explicitly_distinct(s, &a.name, &b.name) {
+        return false;
+    }
+
+    // typed accounts of different types are different accounts, wallets of any kind may coincide
+    let (wa, wb) = (WALLET_TYPES.contains(&a.base_type.as_str()), WALLET_TYPES.contains(&b.base_type.as_str()));
+    if !(wa && wb) && type_key(a) != type_key(b) {
+        return false;
+    }
+
+    // a PDA can never sign a transaction
+    if (a.base_type == "Signer" && cb.seeds.is_some()) || (b.base_type == "Signer" && ca.seeds.is_some()) {
+        return false;
+    }
+    if let (Some(sa), Some(sb)) = (&ca.seeds, &cb.seeds)
+        && seeds_distinct(sa, sb) {
+        return false;
+    }
+    if let (Some(aa), Some(ab)) = (ca.address.as_ref().and_then(|c| c.expr.as_ref()), cb.address.as_ref().and_then(|c| c.expr.as_ref()))
+        && expr_to_string(aa) != expr_to_string(ab) {
+        return false;
+    }
+
+    // same derivation only if both the mints and the authorities may be the same
+    if let (Some((mint_a, auth_a)), Some((mint_b, auth_b))) = (token_derivation(a), token_derivation(b)) {
+        return exprs_may_alias(s, mint_a, mint_b, depth) && exprs_may_alias(s, auth_a, auth_b, depth);
+    }
+    true
+}
+
+pub fn rules_duplicate_mutable_accounts(s: &NormalizedAccountStruct, r: &mut Report) {
+    let candidates: Vec<&NormalizedAccountField> = s
+        .fields
+        .iter()
+        .filter(|f| DATA_ACCOUNT_TYPES.contains(&f.base_type.as_str()) && is_writable_existing(f))
+        .collect();
+
+    for (i, first) in candidates.iter().enumerate() {
+        for second in &candidates[i + 1..] {
+            if type_key(first) != type_key(second) || !may_alias(s, first, second, 0) {
+                continue;
+            }
+            let ty = type_key(first);
+            r.add(Finding::new(
+                &RULE_DUPLICATE_MUTABLE_ACCOUNTS,
+                Location::new(&s.file, second.line, second.column),
+                &s.name,
+                format!("{}/{}", first.name, second.name),
+                format!(
+                    "`{}` and `{}` in `{}` are both mutable `{}` and nothing prevents passing the same account for both",
+                    first.name, second.name, s.name, ty
+                ),
+            )
+            .detail("Accounts", format!("{}, {}", first.name, second.name))
+            .detail("Context", &s.name)
+            .detail("Type", &ty)
+            .label(
+                Location::new(&s.file, first.line, first.column),
+                format!("`{}` has the same type", first.name),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+    use crate::state::SourceFile;
+
+    /// `first/second` pairs flagged in the Accounts structs of `source`
+    fn flagged(source: &str) -> Vec<String> {
+        let files = [SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(source).unwrap(), source: source.to_string() }];
+        let program = normalize_program(&files);
+        let mut r = Report::default();
+        for s in &program.account_structs {
+            rules_duplicate_mutable_accounts(s, &mut r);
+        }
+        r.findings.into_iter().map(|f| f.subject).collect()
+    }
+
+    /// A `Transfer` struct with two `Vault` accounts declared as `from` and `to`
+    fn transfer(from: &str, to: &str) -> String {
+        format!(
+            r#"
+            #[derive(Accounts)]
+            pub struct Transfer<'info> {{
+                pub user: Signer<'info>,
+                {} pub from: Account<'info, Vault>,
+                {} pub to: Account<'info, Vault>,
+            }}
+            "#,
+            from, to
+        )
+    }
+
+    #[test]
+    fn same_typed_mutable_pair() {
+        assert_eq!(flagged(&transfer("#[account(mut)]", "#[account(mut)]")), vec!["from/to"]);
+        // only one of them is written
+        assert!(flagged(&transfer("#[account(mut)]", "")).is_empty());
+    }
+
+    #[test]
+    fn distinct_seeds_or_key_constraint() {
+        assert!(flagged(&transfer(
+            r#"#[account(mut, seeds = [b"from", user.key().as_ref()], bump)]"#,
+            r#"#[account(mut, seeds = [b"to", user.key().as_ref()], bump)]"#,
+        ))
+        .is_empty());
+        assert!(flagged(&transfer("#[account(mut, constraint = from.key() != to.key())]", "#[account(mut)]")).is_empty());
+        // the same constant seed prefix tells nothing apart
+        assert_eq!(
+            flagged(&transfer(
+                r#"#[account(mut, seeds = [b"vault", user.key().as_ref()], bump)]"#,
+                r#"#[account(mut, seeds = [b"vault", from.key().as_ref()], bump)]"#,
+            )),
+            vec!["from/to"]
+        );
+    }
+
+    #[test]
+    fn escrow_take_token_accounts() {
+        let take = r#"
+            #[derive(Accounts)]
+            pub struct Take<'info> {
+                #[account(mut)]
+                pub taker: Signer<'info>,
+                /// CHECK: receives the rent
+                pub maker: UncheckedAccount<'info>,
+                pub mint_a: InterfaceAccount<'info, Mint>,
+                pub mint_b: InterfaceAccount<'info, Mint>,
+                #[account(init_if_needed, payer = taker, associated_token::mint = mint_a, associated_token::authority = taker)]
+                pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+                #[account(mut, associated_token::mint = mint_b, associated_token::authority = taker)]
+                pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+                #[account(init_if_needed, payer = taker, associated_token::mint = mint_b, associated_token::authority = maker)]
+                pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+                #[account(mut, close = maker, has_one = maker, seeds = [b"escrow", maker.key().as_ref()], bump = escrow.bump)]
+                pub escrow: Account<'info, Escrow>,
+                #[account(mut, associated_token::mint = mint_a, associated_token::authority = escrow)]
+                pub vault: InterfaceAccount<'info, TokenAccount>,
+            }
+        "#;
+        // the taker may be the maker and mint_a may be mint_b, the vault belongs to the escrow PDA
+        assert_eq!(flagged(take), vec!["taker_ata_a/taker_ata_b", "taker_ata_a/maker_ata_b", "taker_ata_b/maker_ata_b"]);
+
+        let distinct_mints = take.replace(
+            "pub mint_b: InterfaceAccount<'info, Mint>,",
+            "#[account(constraint = mint_a.key() != mint_b.key())]\n                pub mint_b: InterfaceAccount<'info, Mint>,",
+        );
+        assert_eq!(flagged(&distinct_mints), vec!["taker_ata_b/maker_ata_b"]);
+    }
+}
@@ -0,0 +1,351 @@
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::analyzer::idl::{idl_type, rust_type, snake_case, IdlInstruction, LoadedIdl};
+use crate::analyzer::normalize::program_handlers;
+use crate::analyzer::space::SpaceCalculator;
+use crate::state::{NormalizedAccountField, NormalizedFunctionArgs, NormalizedProgram, SourceFile};
+use super::registry::{AnalysisContext, Rule};
+
+/* ────────────────────────────────────────────────────────────────
+   M-010 — IDL Out of Sync With Source
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_IDL_MISMATCH: RuleMetadata = RuleMetadata {
+    code: "M-010",
+    title: "IDL Out of Sync With Source",
+    description:
+        "The IDL shipped to clients no longer describes the program. An instruction or account present on \
+         only one side, arguments in a different order or of a different type, `isMut`/`isSigner` flags that \
+         disagree with the Accounts struct, or an account layout of a different size make clients build \
+         transactions the program rejects or, worse, serialize data the program decodes into other fields. \
+         Audits and frontends that trust the IDL then reason about a program that is not deployed.",
+    severity: Severity::Medium,
+    recommendation:
+        "Regenerate the IDL with `anchor build` from the same commit that is deployed and publish it with \
+         `anchor idl upgrade`. Hand written IDLs, or ones embedded with `idlgen!`, must be updated together with \
+         the handler signatures, Accounts structs and `#[account]` types they describe.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/basics/idl\n\
+         https://www.anchor-lang.com/docs/references/cli#idl"
+    ),
+};
+
+pub struct IdlMismatchRule;
+
+impl Rule for IdlMismatchRule {
+    fn id(&self) -> &'static str {
+        "idl_mismatch"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_IDL_MISMATCH
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        // only runs when an IDL is passed with `--idl`
+        let Some(idl) = ctx.idl else { return };
+        rules_idl_instructions(idl, ctx.program, ctx.files, r);
+        rules_idl_account_types(idl, ctx.program, r);
+    }
+}
+
+fn idl_location(idl: &LoadedIdl, ix: &IdlInstruction, name: &str) -> Location {
+    Location::new(&idl.path, idl.line_in(ix, name), 1)
+}
+
+fn handler_location(handler: &NormalizedFunctionArgs) -> Location {
+    Location::new(&handler.file, handler.line, handler.column)
+}
+
+/// `(name, type)` of every handler argument after the `Context`, types spelled like `idl_type`
+fn handler_args(handler: &NormalizedFunctionArgs, files: &[SourceFile]) -> Vec<(String, String)> {
+    let Some(item) = files
+        .iter()
+        .filter(|f| f.path == handler.file)
+        .flat_map(|f| program_handlers(&f.ast.items))
+        .find(|f| f.sig.ident == handler.name.as_str())
+    else {
+        return handler.args.iter().map(|a| (a.name.clone(), a.ty.clone())).collect();
+    };
+    item.sig
+        .inputs
+        .iter()
+        .skip(1)
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(t) => match &*t.pat {
+                syn::Pat::Ident(p) => Some((p.ident.to_string(), rust_type(&t.ty))),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Fields of an Accounts struct with composite fields (`pub common: Common<'info>`) expanded
+fn flat_fields<'a>(context: &str, program: &'a NormalizedProgram, depth: usize) -> Vec<&'a NormalizedAccountField> {
+    let Some(s) = program.account_structs.iter().find(|s| s.name == context) else { return Vec::new() };
+    s.fields
+        .iter()
+        .flat_map(|f| {
+            if depth < 4 && program.account_structs.iter().any(|s| s.name == f.base_type) {
+                flat_fields(&f.base_type, program, depth + 1)
+            } else {
+                vec![f]
+            }
+        })
+        .collect()
+}
+
+/// What Anchor puts in the IDL for an account, `init` on a keypair account requires its signature
+fn source_flags(field: &NormalizedAccountField) -> (bool, bool) {
+    let c = &field.constraints;
+    let writable = c.is_mut() || c.is_init() || c.zero || c.close.is_some() || c.realloc.is_some();
+    let keypair_init = c.is_init() && c.seeds.is_none() && c.associated_token.is_empty();
+    let signer = field.base_type == "Signer" || c.signer.is_some() || keypair_init;
+    (writable, signer)
+}
+
+fn flag_names(writable: bool, signer: bool) -> String {
+    match (writable, signer) {
+        (true, true) => "writable, signer",
+        (true, false) => "writable",
+        (false, true) => "signer",
+        (false, false) => "read-only",
+    }
+    .to_string()
+}
+
+pub fn rules_idl_instructions(idl: &LoadedIdl, program: &NormalizedProgram, files: &[SourceFile], r: &mut Report) {
+    for ix in &idl.idl.instructions {
+        let name = snake_case(&ix.name);
+        let Some(handler) = program.functions_args.iter().find(|h| h.name == name) else {
+            r.add(
+                Finding::new(
+                    &RULE_IDL_MISMATCH,
+                    idl_location(idl, ix, &ix.name),
+                    "",
+                    &name,
+                    format!("The IDL declares instruction `{}` but the `#[program]` module has no such handler", name),
+                )
+                .detail("Instruction", &name)
+                .detail("IDL", &idl.path),
+            );
+            continue;
+        };
+        rules_idl_args(idl, ix, handler, files, r);
+        rules_idl_accounts(idl, ix, handler, program, r);
+    }
+
+    for handler in &program.functions_args {
+        if idl.idl.instructions.iter().any(|ix| snake_case(&ix.name) == handler.name) {
+            continue;
+        }
+        r.add(
+            Finding::new(
+                &RULE_IDL_MISMATCH,
+                handler_location(handler),
+                &handler.context,
+                &handler.name,
+                format!("Handler `{}` is missing from the IDL, clients built from it can't call the instruction", handler.name),
+            )
+            .detail("Instruction", &handler.name)
+            .detail("IDL", &idl.path),
+        );
+    }
+}
+
+fn rules_idl_args(idl: &LoadedIdl, ix: &IdlInstruction, handler: &NormalizedFunctionArgs, files: &[SourceFile], r: &mut Report) {
+    let source = handler_args(handler, files);
+    let declared: Vec<(String, String)> = ix.args.iter().map(|a| (snake_case(&a.name), idl_type(&a.ty))).collect();
+
+    let mut problems = Vec::new();
+    for (i, (name, ty)) in declared.iter().enumerate() {
+        match source.iter().position(|(n, _)| n == name) {
+            None => problems.push(format!("`{}: {}` is only in the IDL", name, ty)),
+            Some(j) if source[j].1 != *ty => problems.push(format!("`{}` is `{}` in the IDL but `{}` in source", name, ty, source[j].1)),
+            Some(j) if j != i => problems.push(format!("`{}` is argument {} in the IDL but {} in source", name, i + 1, j + 1)),
+            Some(_) => {}
+        }
+    }
+    for (name, ty) in source.iter().filter(|(n, _)| !declared.iter().any(|(d, _)| d == n)) {
+        problems.push(format!("`{}: {}` is missing from the IDL", name, ty));
+    }
+    if problems.is_empty() {
+        return;
+    }
+
+    let render = |args: &[(String, String)]| args.iter().map(|(n, t)| format!("{}: {}", n, t)).collect::<Vec<_>>().join(", ");
+    r.add(
+        Finding::new(
+            &RULE_IDL_MISMATCH,
+            handler_location(handler),
+            &handler.context,
+            &handler.name,
+            format!(
+                "Arguments of `{}` don't match the IDL: {}, clients serialize the instruction data differently than the program reads it",
+                handler.name,
+                problems.join("; ")
+            ),
+        )
+        .detail("Instruction", &handler.name)
+        .detail("Source Args", render(&source))
+        .detail("IDL Args", render(&declared))
+        .label(idl_location(idl, ix, &ix.name), "declared in the IDL here"),
+    );
+}
+
+fn rules_idl_accounts(idl: &LoadedIdl, ix: &IdlInstruction, handler: &NormalizedFunctionArgs, program: &NormalizedProgram, r: &mut Report) {
+    let Some(s) = program.account_structs.iter().find(|s| s.name == handler.context) else { return };
+    let fields = flat_fields(&s.name, program, 0);
+    let accounts = idl.instruction_accounts(ix);
+
+    for account in &accounts {
+        let name = snake_case(&account.name);
+        let Some(field) = fields.iter().find(|f| f.name == name) else {
+            r.add(
+                Finding::new(
+                    &RULE_IDL_MISMATCH,
+                    idl_location(idl, ix, &account.name),
+                    &s.name,
+                    &name,
+                    format!("The IDL passes account `{}` to `{}` but `{}` has no such field", name, handler.name, s.name),
+                )
+                .detail("Instruction", &handler.name)
+                .detail("Account", &name)
+                .label(Location::new(&s.file, s.line, 1), format!("`{}` is declared here", s.name)),
+            );
+            continue;
+        };
+
+        let (writable, signer) = source_flags(field);
+        if (writable, signer) == (account.writable, account.signer) {
+            continue;
+        }
+        let mut diffs = Vec::new();
+        if writable != account.writable {
+            diffs.push(format!("`isMut` is {} but the source makes it {}", account.writable, if writable { "writable" } else { "read-only" }));
+        }
+        if signer != account.signer {
+            diffs.push(format!("`isSigner` is {} but the source {} a signature", account.signer, if signer { "requires" } else { "doesn't require" }));
+        }
+        r.add(
+            Finding::new(
+                &RULE_IDL_MISMATCH,
+                Location::new(&s.file, field.line, field.column),
+                &s.name,
+                &field.name,
+                format!("Account `{}` of `{}` disagrees with the IDL: {}", field.name, handler.name, diffs.join(", ")),
+            )
+            .detail("Instruction", &handler.name)
+            .detail("Account", &field.name)
+            .detail("Source", flag_names(writable, signer))
+            .detail("IDL", flag_names(account.writable, account.signer))
+            .label(idl_location(idl, ix, &account.name), "declared in the IDL here"),
+        );
+    }
+
+    for field in fields.iter().filter(|f| !accounts.iter().any(|a| snake_case(&a.name) == f.name)) {
+        r.add(
+            Finding::new(
+                &RULE_IDL_MISMATCH,
+                Location::new(&s.file, field.line, field.column),
+                &s.name,
+                &field.name,
+                format!("Account `{}` of `{}` is missing from the IDL, clients won't pass it", field.name, handler.name),
+            )
+            .detail("Instruction", &handler.name)
+            .detail("Account", &field.name)
+            .label(idl_location(idl, ix, &ix.name), "instruction declared in the IDL here"),
+        );
+    }
+}
+
+/// Named fields of a struct as `(name, type)`
+fn struct_fields(s: &syn::ItemStruct) -> Vec<(String, String)> {
+    s.fields
+        .iter()
+        .filter_map(|f| Some((f.ident.as_ref()?.to_string(), rust_type(&f.ty))))
+        .collect()
+}
+
+pub fn rules_idl_account_types(idl: &LoadedIdl, program: &NormalizedProgram, r: &mut Report) {
+    let declared = idl.to_program();
+    let source_calc = SpaceCalculator::new(program);
+    let idl_calc = SpaceCalculator::new(&declared);
+
+    for account in &idl.idl.accounts {
+        let location = Location::new(&idl.path, idl.account_type_line(&account.name), 1);
+        let Some(item) = source_calc.storage_struct(&account.name) else {
+            r.add(
+                Finding::new(
+                    &RULE_IDL_MISMATCH,
+                    location,
+                    "",
+                    &account.name,
+                    format!("The IDL declares account type `{}` but no `#[account]` struct has that name", account.name),
+                )
+                .detail("Account Type", &account.name)
+                .detail("IDL", &idl.path),
+            );
+            continue;
+        };
+        let file = program.type_files.get(&account.name).cloned().unwrap_or_default();
+        let source_location = Location::from_span(&file, item.ident.span());
+
+        let source_fields = struct_fields(item);
+        let idl_fields = idl_calc.storage_struct(&account.name).map(struct_fields).unwrap_or_default();
+        if !idl_fields.is_empty() && source_fields != idl_fields {
+            let render = |fields: &[(String, String)]| fields.iter().map(|(n, t)| format!("{}: {}", n, t)).collect::<Vec<_>>().join(", ");
+            r.add(
+                Finding::new(
+                    &RULE_IDL_MISMATCH,
+                    source_location.clone(),
+                    "",
+                    &account.name,
+                    format!("Fields of `{}` don't match the IDL, clients decode the account with a different layout", account.name),
+                )
+                .detail("Account Type", &account.name)
+                .detail("Source Fields", render(&source_fields))
+                .detail("IDL Fields", render(&idl_fields))
+                .label(location.clone(), "declared in the IDL here"),
+            );
+            continue;
+        }
+
+        // only comparable when both sides have bounded fields, `bytes` in an IDL has no max_len
+        if let (Some(source_size), Some(idl_size)) = (source_calc.account_size(&account.name), idl_calc.account_size(&account.name))
+            && source_size != idl_size {
+            r.add(
+                Finding::new(
+                    &RULE_IDL_MISMATCH,
+                    source_location,
+                    "",
+                    &account.name,
+                    format!("`{}` takes {} bytes in source but {} bytes per the IDL", account.name, source_size, idl_size),
+                )
+                .detail("Account Type", &account.name)
+                .detail("Source Size", source_size)
+                .detail("IDL Size", idl_size)
+                .label(location, "declared in the IDL here"),
+            );
+        }
+    }
+
+    for item in &program.storage_structs {
+        let name = item.ident.to_string();
+        if idl.idl.accounts.iter().any(|a| a.name == name) {
+            continue;
+        }
+        let file = program.type_files.get(&name).cloned().unwrap_or_default();
+        r.add(
+            Finding::new(
+                &RULE_IDL_MISMATCH,
+                Location::from_span(&file, item.ident.span()),
+                "",
+                &name,
+                format!("Account type `{}` is missing from the IDL, clients can't decode it", name),
+            )
+            .detail("Account Type", &name)
+            .detail("IDL", &idl.path),
+        );
+    }
+}
@@ -1,21 +1,74 @@
 use crate::report::report::*;
-use crate::report::knowledge_base::{MissingInitIfNeededFinding, Finding, RULE_MISSING_INIT_IF_NEEDED};
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
 use crate::state::NormalizedAccountStruct;
+use super::registry::{AnalysisContext, Rule};
+
+/* ────────────────────────────────────────────────────────────────
+   H-001 — Use init_if_needed Instead of init
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_MISSING_INIT_IF_NEEDED: RuleMetadata = RuleMetadata {
+    code: "H-001",
+    title: "Use init_if_needed Instead of init",
+    description:
+        "When initializing an Associated Token Account (ATA), using `init` will unconditionally \
+         fail if the ATA already exists. This introduces fragility in programs, since ATAs are \
+         often created by wallets, prior interactions, or other programs. A failed ATA creation \
+         results in a panic and causes the entire transaction to revert. Using `init_if_needed` \
+         ensures the instruction is idempotent and safe, regardless of the ATA’s existence state. \
+         This pattern aligns with modern Solana security expectations and significantly reduces \
+         user-triggered transaction failures.",
+    severity: Severity::High,
+    recommendation:
+        "Replace `init` with `init_if_needed` for ATA initialization. This ensures safe, \
+         idempotent behavior even when an ATA already exists. Always prefer `init_if_needed` \
+         unless you have a strict requirement that the account must be newly created.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/references/account-constraints#accountinit_if_needed\n\
+         https://medium.com/@calc1f4r/init-vs-init-if-needed-a-deep-dive-d33fe59e4de5\n\
+         https://rareskills.io/post/init-if-needed-anchor\n\
+         https://solodit.cyfrin.io/issues/m-02-dos-of-createbondingcurve-pashov-audit-group-none-pumpscience_2024-12-24-markdown_\n\
+         https://solodit.cyfrin.io/issues/attacker-can-create-token-account-for-nft-position-to-cause-deposit-dos-cantina-none-olas-pdf\n\
+         https://solodit.cyfrin.io/issues/ability-to-initialize-multiple-times-ottersec-none-composable-vaults-pdf",         
+    ),
+};
+
+pub struct MissingInitIfNeededRule;
+
+impl Rule for MissingInitIfNeededRule {
+    fn id(&self) -> &'static str {
+        "missing_init_if_needed"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_MISSING_INIT_IF_NEEDED
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for s in &ctx.program.account_structs {
+            rules_missing_init_if_needed(s, r);
+        }
+    }
+}
 
 pub fn rules_missing_init_if_needed(s: &NormalizedAccountStruct, r: &mut Report) {
     // println!("Here");
     for field in &s.fields {
-        if field.contains_attr("associated_token") {
+        if !field.constraints.associated_token.is_empty() {
             // println!("Probably Token");
-            if field.has_bool_attribute("init") {
+            if field.constraints.init {
                 // println!("Bug here");
-                r.add(Finding::MissingInitIfNeeded(MissingInitIfNeededFinding {
-                    rule: &RULE_MISSING_INIT_IF_NEEDED,
-                    account: field.name.clone(),
-                    line: field.line,
-                    context: s.name.clone()
-                    
-                }));
+                r.add(Finding::new(
+                    &RULE_MISSING_INIT_IF_NEEDED,
+                    Location::new(&s.file, field.line, field.column),
+                    &s.name,
+                    &field.name,
+                    format!(
+                        "Associated token account `{}` in `{}` uses `init` instead of `init_if_needed`",
+                        field.name, s.name
+                    ),
+                )
+                .detail("Account", &field.name)
+                .detail("Context", &s.name));
             }
         }
     }
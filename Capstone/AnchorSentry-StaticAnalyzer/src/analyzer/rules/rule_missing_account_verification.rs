@@ -1,22 +1,190 @@
+use crate::analyzer::constraints::expr_to_string;
 use crate::report::report::*;
-use crate::report::knowledge_base::{PossibleMissingAccountVerificationFinding, Finding, RULE_MISSING_ACCOUNT_VERIFICATION};
-use crate::state::{NormalizedAccountStruct};
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::state::{NormalizedAccountField, NormalizedAccountStruct};
+use super::registry::{AnalysisContext, Rule};
+
+/* ────────────────────────────────────────────────────────────────
+   L-002 — Possible Missing Account Verification
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_MISSING_ACCOUNT_VERIFICATION: RuleMetadata = RuleMetadata {
+    code: "L-002",
+    title: "Unconstrained account may require additional validation",
+    description:
+        "AccountInfo or UncheckedAccount structs represent raw Solana accounts with no automatic \
+        deserialization or built-in security checks. When such fields appear without explicit constraints, the \
+        program may unintentionally accept arbitrary accounts provided by the caller. While some program logic \
+        may perform manual validation, the absence of explicit constraints at the account-validation layer increases \
+        risk and complicates audits.",
+    severity: Severity::Low,
+    recommendation:
+        "Review this account field to determine whether a signer constraint or additional validation is required. \
+        If the account represents an authority or must be controlled by a specific party, explicitly annotate it with \
+        #[account(constraint = ...)], or other relevant Anchor constraints. If the field is intentionally \
+        unconstrained, consider documenting its expected behavior to reduce ambiguity for auditors.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/references/account-constraints\n\
+        https://solana.com/pt/developers/courses/program-security/signer-auth\n\
+        https://syedashar1.medium.com/program-security-in-anchor-framework-solana-smart-contract-security-b619e1e4d939\n\
+        https://solodit.cyfrin.io/issues/missing-signer-check-ottersec-none-definitive-pdf\n\
+        https://solodit.cyfrin.io/issues/m-01-any-wallet-can-self-assign-as-super_admin-for-arbitrary-mint-pashov-audit-group-none-pump_2025-06-26-markdown\n\
+        https://solodit.cyfrin.io/issues/m-04-unauthorized-global-and-oracle-state-initialization-pashov-audit-group-none-pump_2025-03-18-markdown"
+    ),
+};
+
+/* ────────────────────────────────────────────────────────────────
+   L-003 — Missing CHECK Documentation
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_MISSING_CHECK_DOC: RuleMetadata = RuleMetadata {
+    code: "L-003",
+    title: "Unchecked account is missing a CHECK doc comment",
+    description:
+        "Anchor requires every AccountInfo or UncheckedAccount field to carry a `/// CHECK:` doc comment \
+        explaining why no type or ownership checks are needed. Without it `anchor build` refuses to compile the \
+        program unless safety checks are disabled, and reviewers have no record of why the raw account is trusted.",
+    severity: Severity::Low,
+    recommendation:
+        "Add a `/// CHECK:` doc comment directly above the field stating what validates the account (a constraint, \
+        a manual check in the handler, or why any account is acceptable), or switch to a typed account such as \
+        Account<'info, T>, Signer<'info> or Program<'info, T>.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/references/account-types\n\
+        https://www.anchor-lang.com/docs/references/account-constraints"
+    ),
+};
+
+pub struct MissingAccountVerificationRule;
+
+impl Rule for MissingAccountVerificationRule {
+    fn id(&self) -> &'static str {
+        "missing_account_verification"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_MISSING_ACCOUNT_VERIFICATION
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for s in &ctx.program.account_structs {
+            rules_missing_signer_check(s, r);
+        }
+    }
+}
+
+pub struct MissingCheckDocRule;
+
+impl Rule for MissingCheckDocRule {
+    fn id(&self) -> &'static str {
+        "missing_check_doc"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_MISSING_CHECK_DOC
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for s in &ctx.program.account_structs {
+            rules_missing_check_doc(s, r);
+        }
+    }
+}
+
+pub fn is_unchecked_account(field: &NormalizedAccountField) -> bool {
+    field.base_type == "AccountInfo" || field.base_type == "UncheckedAccount"
+}
+
+/// Text of the `/// CHECK:` doc comment, continuation lines included
+pub fn check_doc(field: &NormalizedAccountField) -> Option<String> {
+    let start = field.docs.iter().position(|d| d.starts_with("CHECK"))?;
+    let text = field.docs[start..].join(" ");
+    Some(text.trim_start_matches("CHECK").trim_start_matches(':').trim().to_string())
+}
+
+/// Constraint that actually pins down which account can be passed for `field`:
+/// its own signer/address/owner/seeds/constraint, or a sibling's `has_one` or constraint referencing it
+pub fn enforcing_constraint(s: &NormalizedAccountStruct, field: &NormalizedAccountField) -> Option<String> {
+    let c = &field.constraints;
+    if c.signer.is_some() {
+        return Some("signer".to_string());
+    }
+    if c.address.is_some() {
+        return Some("address".to_string());
+    }
+    if c.owner.is_some() {
+        return Some("owner".to_string());
+    }
+    if c.seeds.is_some() {
+        return Some("seeds".to_string());
+    }
+    if !c.constraint.is_empty() {
+        return Some("constraint".to_string());
+    }
+
+    let key_ref = format!("{}.key()", field.name);
+    for other in s.fields.iter().filter(|f| f.name != field.name) {
+        if other.constraints.has_one_targets().contains(&field.name) {
+            return Some(format!("{}: has_one = {}", other.name, field.name));
+        }
+        let references = other
+            .constraints
+            .constraint
+            .iter()
+            .chain(other.constraints.address.iter())
+            .filter_map(|ce| ce.expr.as_ref())
+            .any(|e| expr_to_string(e).replace(' ', "").contains(&key_ref));
+        if references {
+            return Some(format!("{}: constraint", other.name));
+        }
+    }
+    None
+}
 
 pub fn rules_missing_signer_check(s: &NormalizedAccountStruct, r: &mut Report) {
-    for field in &s.fields {
-        if field.base_type == "AccountInfo" || field.base_type == "UncheckedAccount" {
-            // println!("{:?}", field);
-            if field.has_bool_attribute("constraint") || field.has_bool_attribute("signer") {
-                continue;
-            }
-            r.add(Finding::PossibleMissingAccountVerification(
-            PossibleMissingAccountVerificationFinding {
-                rule: &RULE_MISSING_ACCOUNT_VERIFICATION,
-                line: field.line,
-                account_name: field.name.clone(),
-                field_type: field.base_type.clone(),
-            }
-        ));
+    for field in s.fields.iter().filter(|f| is_unchecked_account(f)) {
+        if enforcing_constraint(s, field).is_some() {
+            continue;
+        }
+
+        let doc = check_doc(field);
+        let message = match &doc {
+            Some(text) => format!(
+                "`{}` account `{}` in `{}` is only justified by `/// CHECK: {}`, no constraint enforces it",
+                field.base_type, field.name, s.name, text
+            ),
+            None => format!("`{}` account `{}` in `{}` has no constraint", field.base_type, field.name, s.name),
+        };
+
+        let mut finding = Finding::new(
+            &RULE_MISSING_ACCOUNT_VERIFICATION,
+            Location::new(&s.file, field.line, field.column),
+            &s.name,
+            &field.name,
+            message,
+        )
+        .detail("Account", &field.name)
+        .detail("Context", &s.name)
+        .detail("Type", &field.base_type);
+        if let Some(text) = doc {
+            finding = finding.detail("Check", text);
+        }
+        r.add(finding);
+    }
+}
+
+pub fn rules_missing_check_doc(s: &NormalizedAccountStruct, r: &mut Report) {
+    for field in s.fields.iter().filter(|f| is_unchecked_account(f)) {
+        if check_doc(field).is_some() {
+            continue;
         }
+        r.add(Finding::new(
+            &RULE_MISSING_CHECK_DOC,
+            Location::new(&s.file, field.line, field.column),
+            &s.name,
+            &field.name,
+            format!("`{}` account `{}` in `{}` has no `/// CHECK:` doc comment", field.base_type, field.name, s.name),
+        )
+        .detail("Account", &field.name)
+        .detail("Context", &s.name)
+        .detail("Type", &field.base_type));
     }
-}
\ No newline at end of file
+}
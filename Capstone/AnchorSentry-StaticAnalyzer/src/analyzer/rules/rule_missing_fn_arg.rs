@@ -1,8 +1,49 @@
 use crate::report::report::*;
-use crate::report::knowledge_base::{MissingRequiredInstructionArgumentFinding, Finding, RULE_MISSING_REQUIRED_INSTRUCTION_ARGUMENT};
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
 use crate::state::{NormalizedFunctionArgs, AccountInstructions};
+use super::registry::{AnalysisContext, Rule};
 
-pub fn rules_fn_missing_instruction_args(v: &Vec<AccountInstructions>, f: &Vec<NormalizedFunctionArgs>, r: &mut Report) {
+/* ────────────────────────────────────────────────────────────────
+   M-002 — Missing Required Argument in Instruction
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_MISSING_REQUIRED_INSTRUCTION_ARGUMENT: RuleMetadata = RuleMetadata {
+    code: "M-002",
+    title: "Missing Required Argument in Instruction",
+    description:
+        "The context struct declares one or more `#[instruction(...)]` arguments that the \
+         function must receive, but the function signature omits them. This creates \
+         inconsistencies between expected and actual instruction parameters, potentially \
+         leading to logic bugs, incorrect PDA derivations, or unintended behavior when \
+         serializing input data. A function that does not accept all of its declared \
+         instruction parameters cannot rely on deterministic inputs.",
+    severity: Severity::Medium,
+    recommendation:
+        "Ensure the function signature includes all arguments declared inside \
+         `#[instruction(...)]`. The context definition and function parameters must match \
+         exactly so callers provide the expected values.",
+    additional_links: Some(
+        "https://www.quicknode.com/guides/solana-development/anchor/how-to-use-constraints-in-anchor#utilizing-instruction-data-in-constraints\n\
+        https://solana.stackexchange.com/questions/5946/what-is-anchor-instruction-macro"
+    ),
+};
+
+pub struct MissingInstructionArgsRule;
+
+impl Rule for MissingInstructionArgsRule {
+    fn id(&self) -> &'static str {
+        "missing_instruction_args"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_MISSING_REQUIRED_INSTRUCTION_ARGUMENT
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        rules_fn_missing_instruction_args(&ctx.program.accounts_with_instructions, &ctx.program.functions_args, r);
+    }
+}
+
+pub fn rules_fn_missing_instruction_args(v: &[AccountInstructions], f: &[NormalizedFunctionArgs], r: &mut Report) {
     for field in v {
         let name = field.ctx_name.clone();
         for func in f {
@@ -15,13 +56,22 @@ pub fn rules_fn_missing_instruction_args(v: &Vec<AccountInstructions>, f: &Vec<N
                     // println!(
                     //     "Missing required instruction argument in function: `{}`: {}: {}",
                     //     func.name, required_arg.name, required_arg.ty);
-                        r.add(Finding::MissingRequiredInstructionArgument(MissingRequiredInstructionArgumentFinding {
-                            rule: &RULE_MISSING_REQUIRED_INSTRUCTION_ARGUMENT,
-                            fn_name : func.name.clone(),
-                            line: func.line,
-                            required_arg_name: required_arg.name.clone(),
-                            required_arg_type: required_arg.ty.clone()
-                        }));
+                        r.add(Finding::new(
+                            &RULE_MISSING_REQUIRED_INSTRUCTION_ARGUMENT,
+                            Location::new(&func.file, func.line, func.column),
+                            &func.name,
+                            &required_arg.name,
+                            format!(
+                                "Function `{}` does not take the instruction argument `{}: {}`",
+                                func.name, required_arg.name, required_arg.ty
+                            ),
+                        )
+                        .detail("Function", &func.name)
+                        .detail("Missing Argument", format!("{}: {}", required_arg.name, required_arg.ty))
+                        .label(
+                            Location::new(&field.file, field.line, field.column),
+                            format!("`{}` declares `{}` here", name, required_arg.name),
+                        ));
                 }
             }
             }
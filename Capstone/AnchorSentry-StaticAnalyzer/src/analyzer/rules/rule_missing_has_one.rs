@@ -0,0 +1,226 @@
+use quote::ToTokens;
+use syn::{ItemStruct, Type};
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::analyzer::constraints::{expr_to_string, mentions_ident};
+use crate::state::{NormalizedAccountField, NormalizedAccountStruct, NormalizedProgram};
+use super::registry::{AnalysisContext, Rule};
+
+/* ────────────────────────────────────────────────────────────────
+   M-008 — Missing has_one Relationship
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_MISSING_HAS_ONE: RuleMetadata = RuleMetadata {
+    code: "M-008",
+    title: "Missing has_one Relationship Check",
+    description:
+        "A state account stores the public key of a related account (its maker, authority or mint) and the \
+         instruction also takes that account, but nothing checks the two match. Anchor only verifies the \
+         state account's owner and discriminator, so a caller can pass their own wallet or mint next to someone \
+         else's state account and act as its maker, authority or collect what belongs to it.",
+    severity: Severity::Medium,
+    recommendation:
+        "Add `has_one = <field>` to the state account, which checks `state.<field> == <field>.key()`, or derive \
+         the state account from the related account with seeds. Use an explicit \
+         `constraint = state.<field> == <account>.key()` when the names differ.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/references/account-constraints#accounthas_one--target\n\
+         https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/1-account-data-matching\n\
+         https://solana.com/developers/courses/program-security/account-data-matching"
+    ),
+};
+
+/// Account types whose data Anchor deserializes into a storage struct
+const STATE_ACCOUNT_TYPES: &[&str] = &["Account", "AccountLoader"];
+
+pub struct MissingHasOneRule;
+
+impl Rule for MissingHasOneRule {
+    fn id(&self) -> &'static str {
+        "missing_has_one"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_MISSING_HAS_ONE
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for s in &ctx.program.account_structs {
+            rules_missing_has_one(s, ctx.program, r);
+        }
+    }
+}
+
+/// The storage struct behind `Account<'info, Escrow>`
+fn state_struct<'a>(field: &NormalizedAccountField, program: &'a NormalizedProgram) -> Option<&'a ItemStruct> {
+    if !STATE_ACCOUNT_TYPES.contains(&field.base_type.as_str()) {
+        return None;
+    }
+    let inner = field.generic_args.iter().rfind(|g| !g.starts_with('\''))?;
+    program.storage_structs.iter().find(|s| s.ident == inner.as_str())
+}
+
+fn is_pubkey(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.path.segments.last().is_some_and(|s| s.ident == "Pubkey"))
+}
+
+/// Compact text of every constraint-like expression on `field`, `has_one` excluded
+fn constraint_texts(field: &NormalizedAccountField) -> Vec<String> {
+    let c = &field.constraints;
+    let namespaced = c.token.iter().chain(&c.mint).chain(&c.associated_token).filter_map(|n| n.value.as_ref());
+    c.constraint
+        .iter()
+        .chain(c.address.iter())
+        .filter_map(|ce| ce.expr.as_ref())
+        .chain(c.seeds.iter().flatten())
+        .chain(namespaced)
+        .map(|e| expr_to_string(e).replace(' ', ""))
+        .collect()
+}
+
+/// How the struct links `account.<stored>` to the passed `target` account, if it does
+fn linking_constraint(s: &NormalizedAccountStruct, account: &NormalizedAccountField, stored: &str, target: &NormalizedAccountField) -> Option<String> {
+    if account.constraints.has_one_targets().iter().any(|t| t == stored) {
+        return Some("has_one".to_string());
+    }
+    // escrow.maker == maker.key() anywhere, or `address = escrow.maker`
+    let stored_ref = format!("{}.{}", account.name, stored);
+    for field in &s.fields {
+        // `maker` inside `escrow.maker` is not a reference to the `maker` account
+        let names_target = |t: &String| field.name == target.name || mentions_ident(&t.replace(&stored_ref, ""), &target.name);
+        if constraint_texts(field).iter().any(|t| t.contains(&stored_ref) && names_target(t)) {
+            return Some(format!("{}: constraint", field.name));
+        }
+    }
+    // the state account is derived from the target (seeds = [b"escrow", maker.key()]) or the other way round
+    let derived = |from: &NormalizedAccountField, to: &str| from.constraints.seeds.iter().flatten().any(|e| mentions_ident(&expr_to_string(e), to));
+    if derived(account, &target.name) {
+        return Some(format!("{}: seeds", account.name));
+    }
+    if constraint_texts(target).iter().any(|t| mentions_ident(t, &account.name)) {
+        return Some(format!("{}: derived from {}", target.name, account.name));
+    }
+    None
+}
+
+/// A handler comparing the stored key by hand, `require_keys_eq!(self.escrow.maker, self.maker.key())`
+fn checked_in_impl(program: &NormalizedProgram, s: &NormalizedAccountStruct, account: &str, stored: &str, target: &str) -> bool {
+    let stored_ref = format!("{}.{}", account, stored);
+    let target_ref = format!("{}.key()", target);
+    program.impls_of(&s.name).flat_map(|imp| &imp.methods).any(|m| {
+        let body = m.block.to_token_stream().to_string().replace(' ', "");
+        body.split(';').any(|stmt| {
+            stmt.contains(&stored_ref)
+                && stmt.contains(&target_ref)
+                && (stmt.contains("==") || stmt.contains("!=") || stmt.contains("require_keys_eq!"))
+        })
+    })
+}
+
+pub fn rules_missing_has_one(s: &NormalizedAccountStruct, program: &NormalizedProgram, r: &mut Report) {
+    for account in &s.fields {
+        // a freshly created account is being filled in, there is nothing stored to compare yet
+        if account.constraints.is_init() || account.constraints.zero {
+            continue;
+        }
+        let Some(state) = state_struct(account, program) else { continue };
+
+        for stored in state.fields.iter().filter(|f| is_pubkey(&f.ty)) {
+            let Some(stored_name) = stored.ident.as_ref().map(|i| i.to_string()) else { continue };
+            let Some(target) = s.fields.iter().find(|f| f.name == stored_name && f.name != account.name) else { continue };
+            if linking_constraint(s, account, &stored_name, target).is_some()
+                || checked_in_impl(program, s, &account.name, &stored_name, &target.name) {
+                continue;
+            }
+
+            let mut finding = Finding::new(
+                &RULE_MISSING_HAS_ONE,
+                Location::new(&s.file, target.line, target.column),
+                &s.name,
+                format!("{}.{}", account.name, stored_name),
+                format!(
+                    "`{}` stores `{}` but `{}` never checks that the passed `{}` account is `{}.{}`, add `has_one = {}` to `{}`",
+                    account.name, stored_name, s.name, target.name, account.name, stored_name, stored_name, account.name
+                ),
+            )
+            .detail("Account", &account.name)
+            .detail("Stored Key", format!("{}.{}", state.ident, stored_name))
+            .detail("Context", &s.name)
+            .label(
+                Location::new(&s.file, account.line, account.column),
+                format!("`{}` is loaded here without `has_one = {}`", account.name, stored_name),
+            );
+            if let Some(file) = program.type_files.get(&state.ident.to_string())
+                && let Some(ident) = &stored.ident {
+                finding = finding.label(
+                    Location::from_span(file, ident.span()),
+                    format!("`{}.{}` is stored here", state.ident, stored_name),
+                );
+            }
+            r.add(finding);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+    use crate::state::SourceFile;
+
+    /// Subjects flagged for a `RefundBet` struct with `bet` declared under `bet_attr` and `body` as its method
+    fn flagged(bet_attr: &str, body: &str) -> Vec<String> {
+        let source = format!(
+            r#"
+            #[account]
+            pub struct Bet {{ pub player: Pubkey, pub seed: u128, pub slot: u64, pub bump: u8 }}
+
+            #[derive(Accounts)]
+            pub struct RefundBet<'info> {{
+                #[account(mut)]
+                pub player: Signer<'info>,
+                /// CHECK: house
+                pub house: UncheckedAccount<'info>,
+                #[account(mut, seeds = [b"vault", house.key().as_ref()], bump)]
+                pub vault: SystemAccount<'info>,
+                {}
+                pub bet: Account<'info, Bet>,
+            }}
+
+            impl<'info> RefundBet<'info> {{
+                pub fn refund_bet(&mut self) -> Result<()> {{ {} Ok(()) }}
+            }}
+            "#,
+            bet_attr, body
+        );
+        let files = [SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(&source).unwrap(), source }];
+        let program = normalize_program(&files);
+        let mut r = Report::default();
+        for s in &program.account_structs {
+            rules_missing_has_one(s, &program, &mut r);
+        }
+        r.findings.into_iter().map(|f| f.subject).collect()
+    }
+
+    const DICE_BET: &str = r#"#[account(mut, close = player, seeds = [b"bet", vault.key().as_ref(), bet.seed.to_le_bytes().as_ref()], bump = bet.bump)]"#;
+
+    #[test]
+    fn dice_refund_bet_player() {
+        // any signer can refund someone else's bet to themselves
+        assert_eq!(flagged(DICE_BET, ""), vec!["bet.player"]);
+    }
+
+    #[test]
+    fn linked_by_constraints() {
+        assert!(flagged("#[account(mut, has_one = player, close = player)]", "").is_empty());
+        assert!(flagged("#[account(mut, close = player, constraint = bet.player == player.key())]", "").is_empty());
+        assert!(flagged(r#"#[account(mut, close = player, seeds = [b"bet", player.key().as_ref()], bump = bet.bump)]"#, "").is_empty());
+    }
+
+    #[test]
+    fn compared_in_impl() {
+        assert!(flagged(DICE_BET, "require_keys_eq!(self.bet.player, self.player.key(), E::Player);").is_empty());
+        assert!(flagged(DICE_BET, "if self.bet.player != self.player.key() { return err!(E::Player); }").is_empty());
+        // reading both is not comparing them
+        assert_eq!(flagged(DICE_BET, "msg!(\"{}\", self.bet.player); let p = self.player.key();"), vec!["bet.player"]);
+    }
+}
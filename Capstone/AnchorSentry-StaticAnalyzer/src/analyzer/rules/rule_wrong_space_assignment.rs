@@ -1,86 +1,95 @@
-use evalexpr::*;
-use syn::{ItemStruct};
 use crate::report::report::*;
-use crate::report::knowledge_base::{WrongSpaceAssignmentFinding, Finding, RULE_WRONG_SPACE_ASSIGNMENT};
-use crate::state::{NormalizedAccountField, NormalizedAccountStruct};
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use syn::spanned::Spanned;
+use crate::state::{NormalizedAccountStruct, NormalizedProgram};
+use crate::analyzer::constraints::expr_to_string;
+use crate::analyzer::space::SpaceCalculator;
+use super::registry::{AnalysisContext, Rule};
 
+/* ────────────────────────────────────────────────────────────────
+   M-001 — Incorrect Space Assignment
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_WRONG_SPACE_ASSIGNMENT: RuleMetadata = RuleMetadata {
+    code: "M-001",
+    title: "Incorrect Space Assignment",
+    description:
+        "A mismatch between a struct’s declared `space` value and its actual serialized size \
+         leads to account truncation or unsafe overwrites. If `space` is too small, random data \
+         corruption or panic conditions may occur when Anchor attempts to serialize state. If too \
+         large, excessive rent is charged. Accurate space calculation is essential for safe and \
+         predictable on-chain storage behavior.",
+    severity: Severity::Medium,
+    recommendation:
+        "Recalculate the exact byte-length of the struct and update the `space` attribute to \
+         reflect the correct size. Ensure that every field is included in the calculation and \
+         account for all padding, discriminators, and aggregate data types.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/references/space\n\
+         https://rareskills.io/post/solana-initialize-account\n\
+         https://www.sec3.dev/blog/all-about-anchor-account-size\n\
+         https://solodit.cyfrin.io/issues/improper-space-allocation-for-pda-initialization-quantstamp-exceed-finance-liquid-staking-early-purchase-markdown\n\
+         https://solodit.cyfrin.io/issues/insufficient-vector-space-allocation-ottersec-none-polkastarter-pdf"
+    ),
+};
 
-pub fn extract_space(f: &NormalizedAccountField) -> Option<Value> {
-    let s= f.has_str_attribute("space").unwrap_or_default();
-    // println!("{:?}", s);
-    if !s.is_empty() {
-        let result = eval(s).unwrap();
-        println!("Result: {:?}", result);
-        return Some(result)
-    }
-    None
-}
+pub struct WrongSpaceAssignmentRule;
 
-pub fn extract_types_from_storage_struct(s: &syn::ItemStruct) -> usize {
-    let mut sum = 8; //8 cos were starting with discriminator
-    for field in &s.fields {
-        // if let syn::Type::Path(type_path) = &field.ty {
-        //     println!("{:?}", type_path.path.get_ident().unwrap());
-        // }
-        if let syn::Type::Path(type_path) = &field.ty {
-            if let Some(last_seg) = type_path.path.segments.last() {
-                let num = anchor_type_size(&last_seg.ident.to_string()).unwrap();
-                sum += num;
-                // println!("{:?}", anchor_type_size(&last_seg.ident.to_string()).unwrap());
-                // print_type_of(&last_seg.ident);
-            }
-        }
+impl Rule for WrongSpaceAssignmentRule {
+    fn id(&self) -> &'static str {
+        "wrong_space_assignment"
     }
-    // println!("{:?}",sum);
-    sum
-}
 
-pub fn anchor_type_size(ty: &str) -> Option<usize> {
-    match ty {
-        "u8" | "i8" | "bool" => Some(1),
-        "u16" | "i16" => Some(2),
-        "u32" | "i32" => Some(4),
-        "u64" | "i64" => Some(8),
-        "u128" | "i128" => Some(16),
-        "Pubkey" => Some(32),
-        _ => None,
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_WRONG_SPACE_ASSIGNMENT
     }
-}
 
-fn compare_usize_and_evalexpr_value(usize_val: usize, eval_val: evalexpr::Value) -> bool {
-    if let evalexpr::Value::Int(n) = eval_val {
-        n as usize == usize_val
-    } else {
-        false
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        let calc = SpaceCalculator::new(ctx.program);
+        for s in &ctx.program.account_structs {
+            rules_wrong_space_assignment(s, ctx.program, &calc, r);
+        }
     }
 }
 
-pub fn rules_wrong_space_assignment(s: &NormalizedAccountStruct, v: &Vec<ItemStruct>, r: &mut Report) {
+
+pub fn rules_wrong_space_assignment(s: &NormalizedAccountStruct, program: &NormalizedProgram, calc: &SpaceCalculator, r: &mut Report) {
     for field in &s.fields {
-        //for formatting remember to add prints liek checking for space err in field.ident...
-        if field.contains_attr("space") {
-            let account_space = extract_space(&field);
-            let account_data_type = field.generic_args.get(1);
-            println!("{:?}", account_data_type.unwrap());
+        let Some(space) = &field.constraints.space else { continue };
+        // AccountLoader is zero copy, sized by its repr(C) layout rather than borsh
+        if field.base_type != "Account" {
+            continue;
+        }
+        let Some(account_data_type) = field.generic_args.get(1) else { continue };
+        let Some(expected_space) = calc.account_size(account_data_type) else { continue };
+        // expressions we can't resolve (`size_of`, unknown consts...) are skipped
+        let Some(account_space) = calc.eval(space) else { continue };
+
+        if account_space != expected_space {
+            let verdict = if account_space < expected_space { "too small" } else { "larger than needed" };
+            let mut finding = Finding::new(
+                &RULE_WRONG_SPACE_ASSIGNMENT,
+                Location::from_span(&s.file, space.span()),
+                &s.name,
+                &field.name,
+                format!(
+                    "Account `{}` in `{}` allocates {} bytes but `{}` needs {}, the allocation is {}",
+                    field.name, s.name, account_space, account_data_type, expected_space, verdict
+                ),
+            )
+            .detail("Account", &field.name)
+            .detail("Context", &s.name)
+            .detail("Space", expr_to_string(space))
+            .detail("Expected", expected_space)
+            .detail("Actual", account_space);
 
-            for item in v {
-                if item.ident.to_string() == *account_data_type.unwrap() {
-                    let expected_space = extract_types_from_storage_struct(item);
-                    if compare_usize_and_evalexpr_value(expected_space, account_space.clone().unwrap()) {
-                        println!("No Issue")
-                    }else {
-                        // println!("Issue");
-                        r.add(Finding::WrongSpaceAssignment(WrongSpaceAssignmentFinding {
-                            rule: &RULE_WRONG_SPACE_ASSIGNMENT,
-                            account: field.name.clone(),
-                            line: field.line,
-                            expected: expected_space,
-                            actual: account_space.clone().unwrap().to_string(),
-                        }));
-                    }
-                }
+            if let Some(item) = calc.storage_struct(account_data_type)
+                && let Some(file) = program.type_files.get(account_data_type) {
+                finding = finding.label(
+                    Location::from_span(file, item.ident.span()),
+                    format!("`{}` is defined here, it needs {} bytes", account_data_type, expected_space),
+                );
             }
+            r.add(finding);
         }
-        
     }
-}
\ No newline at end of file
+}
@@ -1,2 +0,0 @@
-pub mod rule_division_by_zero;
-pub use rule_division_by_zero::*;
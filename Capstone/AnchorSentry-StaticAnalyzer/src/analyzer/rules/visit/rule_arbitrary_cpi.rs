@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::punctuated::Punctuated;
+use syn::{BinOp, Expr, ExprBinary, ExprCall, ExprMethodCall, ExprStruct, FnArg, Member, Pat, Signature, Token};
+use crate::analyzer::rules::registry::{AnalysisContext, Rule};
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::state::{NormalizedAccountField, NormalizedProgram, SourceFile};
+
+/* ────────────────────────────────────────────────────────────────
+   H-002 — Arbitrary CPI
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_ARBITRARY_CPI: RuleMetadata = RuleMetadata {
+    code: "H-002",
+    title: "Arbitrary CPI Program",
+    description:
+        "A cross-program invocation takes the program to call from an account passed by the caller. When that \
+         account is an `UncheckedAccount` or `AccountInfo` without an `address` constraint, nothing checks it is \
+         the intended program. An attacker can pass their own program, which receives the same accounts and \
+         signer seeds and can fake the expected behavior, i.e report a transfer that never happened.",
+    severity: Severity::High,
+    recommendation:
+        "Declare the program account as `Program<'info, T>` or `Interface<'info, T>` so Anchor checks its id, or \
+         add `#[account(address = <PROGRAM_ID>)]` to the raw account. A manual `require_keys_eq!` on the key \
+         before the CPI works too but is easy to drop in later changes.",
+    additional_links: Some(
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/5-arbitrary-cpi\n\
+         https://solana.com/developers/courses/program-security/arbitrary-cpi\n\
+         https://www.anchor-lang.com/docs/references/account-types#programinfo-t"
+    ),
+};
+
+/// Account types Anchor checks against a fixed program id
+const PROGRAM_TYPES: &[&str] = &["Program", "Interface"];
+/// `CpiContext` constructors, the program is the first argument
+const CPI_CONTEXT_FNS: &[&str] = &["new", "new_with_signer"];
+
+pub struct ArbitraryCpiRule;
+
+impl Rule for ArbitraryCpiRule {
+    fn id(&self) -> &'static str {
+        "arbitrary_cpi"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_ARBITRARY_CPI
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for file in ctx.files {
+            rules_arbitrary_cpi(file, ctx.program, r);
+        }
+    }
+}
+
+/// A CPI whose program account is a field of an Accounts struct
+#[derive(Debug, Clone)]
+pub struct CpiCall {
+    pub function_name: String,
+    // Accounts struct the program field belongs to
+    pub context: String,
+    pub field: String,
+    // `CpiContext::new`, `UpdatePluginV1CpiBuilder::new`, `Instruction`
+    pub via: String,
+    pub span: Span,
+    // the fn checks the program key itself, `require_keys_eq!(self.program.key(), ID)`
+    pub key_checked: bool,
+}
+
+/// Walks handlers and impl methods, linking each CPI to the program account it invokes
+#[derive(Default)]
+pub struct CpiChecker {
+    pub calls: Vec<CpiCall>,
+    pub current_fn: String,
+    // program fields whose key the current fn compares, `require_keys_eq!(self.program.key(), ID)`
+    compared: Vec<String>,
+    // first call of the current fn in `calls`
+    first_call: usize,
+    // Accounts struct behind `self` or `ctx.accounts`
+    context: Option<String>,
+    self_type: Option<String>,
+    // `let cpi_program = self.token_program.to_account_info();`
+    aliases: HashMap<String, String>,
+}
+
+impl CpiChecker {
+    fn enter(&mut self, sig: &Signature) {
+        self.current_fn = sig.ident.to_string();
+        self.compared.clear();
+        self.first_call = self.calls.len();
+        self.aliases.clear();
+        // handlers take `ctx: Context<X>`, impl methods work on `self`
+        self.context = sig
+            .inputs
+            .iter()
+            .find_map(|input| match input {
+                FnArg::Typed(t) => context_target(&t.ty),
+                FnArg::Receiver(_) => None,
+            })
+            .or_else(|| self.self_type.clone());
+    }
+
+    /// The Accounts field a program expression names, through `let` aliases
+    fn program_field(&self, e: &Expr) -> Option<String> {
+        match e {
+            Expr::Reference(r) => self.program_field(&r.expr),
+            Expr::Paren(p) => self.program_field(&p.expr),
+            Expr::Unary(u) => self.program_field(&u.expr),
+            Expr::MethodCall(m) if ["to_account_info", "key", "clone", "as_ref"].contains(&m.method.to_string().as_str()) => {
+                self.program_field(&m.receiver)
+            }
+            Expr::Field(f) if matches!(&f.member, Member::Named(m) if m == "key") => self.program_field(&f.base),
+            Expr::Path(p) => self.aliases.get(&p.path.get_ident()?.to_string()).cloned(),
+            other => super::account_field(other),
+        }
+    }
+
+    fn push(&mut self, program: &Expr, via: String) {
+        let (Some(field), Some(context)) = (self.program_field(program), self.context.clone()) else { return };
+        self.calls.push(CpiCall {
+            function_name: self.current_fn.clone(),
+            context,
+            field,
+            via,
+            span: program.span(),
+            key_checked: false,
+        });
+    }
+
+    /// `a == b` where one side is a program field and the other something else, i.e its expected id
+    fn compare(&mut self, a: &Expr, b: &Expr) {
+        for (x, y) in [(a, b), (b, a)] {
+            if let Some(field) = self.program_field(x)
+                && self.program_field(y).as_ref() != Some(&field)
+                && !self.compared.contains(&field) {
+                self.compared.push(field);
+            }
+        }
+    }
+
+    /// Marks the calls of the fn just walked whose program it compares anywhere in its body
+    fn leave(&mut self) {
+        for call in &mut self.calls[self.first_call..] {
+            call.key_checked = self.compared.contains(&call.field);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CpiChecker {
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let name = match &*node.self_ty {
+            syn::Type::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()),
+            _ => None,
+        };
+        let previous = std::mem::replace(&mut self.self_type, name);
+        visit::visit_item_impl(self, node);
+        self.self_type = previous;
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.enter(&node.sig);
+        visit::visit_item_fn(self, node);
+        self.leave();
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.enter(&node.sig);
+        visit::visit_impl_item_fn(self, node);
+        self.leave();
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast ExprBinary) {
+        if matches!(expr.op, BinOp::Eq(_) | BinOp::Ne(_)) {
+            self.compare(&expr.left, &expr.right);
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        if (expr.method == "eq" || expr.method == "ne")
+            && let Some(arg) = expr.args.first() {
+            self.compare(&expr.receiver, arg);
+        }
+        visit::visit_expr_method_call(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        // require_keys_eq!(a, b) / assert_ne!(a, b), require!(a == b) is walked for its args
+        let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) else { return };
+        let name = mac.path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default();
+        if (name.ends_with("_eq") || name.ends_with("_ne") || name.ends_with("_neq"))
+            && let (Some(a), Some(b)) = (args.get(0), args.get(1)) {
+            self.compare(a, b);
+        }
+        for arg in &args {
+            self.visit_expr(arg);
+        }
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        visit::visit_local(self, local);
+        if let Pat::Ident(ident) = &local.pat
+            && let Some(init) = &local.init
+            && let Some(field) = self.program_field(&init.expr) {
+            self.aliases.insert(ident.ident.to_string(), field);
+        }
+    }
+
+    fn visit_expr_call(&mut self, expr: &'ast ExprCall) {
+        if let Expr::Path(p) = &*expr.func {
+            let segments: Vec<String> = p.path.segments.iter().map(|s| s.ident.to_string()).collect();
+            let last = segments.last().map(String::as_str).unwrap_or_default();
+            let owner = segments.iter().rev().nth(1).map(String::as_str).unwrap_or_default();
+            let program = if owner == "CpiContext" && CPI_CONTEXT_FNS.contains(&last) {
+                // CpiContext::new(self.token_program.to_account_info(), accounts)
+                expr.args.first()
+            } else if owner.ends_with("CpiBuilder") && last == "new" {
+                // UpdatePluginV1CpiBuilder::new(&self.core_program)
+                expr.args.first()
+            } else {
+                None
+            };
+            if let Some(program) = program {
+                self.push(program, format!("{}::{}", owner, last));
+            }
+        }
+        visit::visit_expr_call(self, expr);
+    }
+
+    fn visit_expr_struct(&mut self, expr: &'ast ExprStruct) {
+        // invoke(&Instruction { program_id: self.program.key(), .. }, ..)
+        if expr.path.segments.last().is_some_and(|s| s.ident == "Instruction")
+            && let Some(program_id) = expr.fields.iter().find(|f| matches!(&f.member, Member::Named(m) if m == "program_id")) {
+            self.push(&program_id.expr, "Instruction".to_string());
+        }
+        visit::visit_expr_struct(self, expr);
+    }
+}
+
+/// `Context<'_, '_, '_, 'info, Swap<'info>>` -> `Swap`
+pub fn context_target(ty: &syn::Type) -> Option<String> {
+    let syn::Type::Path(p) = ty else { return None };
+    let last = p.path.segments.last()?;
+    if last.ident != "Context" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &last.arguments else { return None };
+    args.args.iter().rev().find_map(|a| match a {
+        syn::GenericArgument::Type(syn::Type::Path(t)) => t.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    })
+}
+
+/// Whether Anchor pins the program id of `field`: a `Program`/`Interface` type or an `address` constraint
+pub fn program_pinned(field: &NormalizedAccountField) -> bool {
+    PROGRAM_TYPES.contains(&field.base_type.as_str()) || field.constraints.address.is_some()
+}
+
+pub fn rules_arbitrary_cpi(file: &SourceFile, program: &NormalizedProgram, r: &mut Report) {
+    let mut checker = CpiChecker::default();
+    checker.visit_file(&file.ast);
+
+    for call in checker.calls {
+        let Some(s) = program.account_structs.iter().find(|s| s.name == call.context) else { continue };
+        let Some(field) = s.fields.iter().find(|f| f.name == call.field) else { continue };
+        if program_pinned(field) || call.key_checked {
+            continue;
+        }
+
+        r.add(Finding::new(
+            &RULE_ARBITRARY_CPI,
+            Location::from_span(&file.path, call.span),
+            &s.name,
+            &field.name,
+            format!(
+                "`{}` invokes `{}` through `{}`, but `{}` (`{}`) has no `address` constraint, the caller picks the program",
+                call.function_name, field.name, call.via, field.name, field.base_type
+            ),
+        )
+        .detail("Program Account", &field.name)
+        .detail("Context", &s.name)
+        .detail("Function", &call.function_name)
+        .detail("CPI", &call.via)
+        .label(
+            Location::new(&s.file, field.line, field.column),
+            format!("`{}` is declared here, use `Program<'info, T>` or `#[account(address = ..)]`", field.name),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+
+    /// Fields flagged for a `Swap` struct with `program` declared as `decl` and `body` as its `swap` method
+    fn flagged(decl: &str, body: &str) -> Vec<String> {
+        let source = format!(
+            r#"
+            #[derive(Accounts)]
+            pub struct Swap<'info> {{
+                pub user: Signer<'info>,
+                {}
+            }}
+            impl<'info> Swap<'info> {{
+                pub fn swap(&mut self) -> Result<()> {{
+                    {}
+                    let ctx = CpiContext::new(self.program.to_account_info(), Transfer {{ from: self.user.to_account_info() }});
+                    transfer(ctx, 10)
+                }}
+            }}
+            "#,
+            decl, body
+        );
+        let files = [SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(&source).unwrap(), source }];
+        let program = normalize_program(&files);
+        let mut r = Report::default();
+        rules_arbitrary_cpi(&files[0], &program, &mut r);
+        r.findings.into_iter().map(|f| f.subject).collect()
+    }
+
+    const UNCHECKED: &str = "/// CHECK: the program to call\npub program: UncheckedAccount<'info>,";
+
+    #[test]
+    fn unchecked_program_account() {
+        assert_eq!(flagged(UNCHECKED, ""), vec!["program"]);
+        assert_eq!(flagged("pub program: AccountInfo<'info>,", ""), vec!["program"]);
+        // naming the program is not comparing it
+        assert_eq!(flagged(UNCHECKED, "msg!(\"{}\", self.program.key()); let id = self.program.key();"), vec!["program"]);
+    }
+
+    #[test]
+    fn pinned_program_account() {
+        assert!(flagged("pub program: Program<'info, Token>,", "").is_empty());
+        assert!(flagged("pub program: Interface<'info, TokenInterface>,", "").is_empty());
+        assert!(flagged("/// CHECK: pinned\n#[account(address = token::ID)]\npub program: UncheckedAccount<'info>,", "").is_empty());
+    }
+
+    #[test]
+    fn key_compared_in_the_body() {
+        assert!(flagged(UNCHECKED, "require_keys_eq!(self.program.key(), token::ID, E::Program);").is_empty());
+        assert!(flagged(UNCHECKED, "let program = self.program.to_account_info(); require!(program.key == &token::ID, E::Program);").is_empty());
+        assert!(flagged(UNCHECKED, "if self.program.key() != token::ID { return err!(E::Program); }").is_empty());
+        // comparing some other account proves nothing about the program
+        assert_eq!(flagged(UNCHECKED, "require_keys_eq!(self.user.key(), ADMIN, E::Admin);"), vec!["program"]);
+    }
+}
@@ -0,0 +1,635 @@
+use std::collections::HashMap;
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::punctuated::Punctuated;
+use syn::{BinOp, Expr, ExprBinary, ExprCast, FnArg, GenericArgument, Lit, Member, Pat, PathArguments, Signature, Token, Type};
+use crate::analyzer::constraints::span_text;
+use crate::analyzer::rules::registry::{AnalysisContext, Rule};
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::state::{NormalizedProgram, SourceFile};
+
+/* ────────────────────────────────────────────────────────────────
+   M-006 — Lossy Integer Cast
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_LOSSY_CAST: RuleMetadata = RuleMetadata {
+    code: "M-006",
+    title: "Lossy Integer Cast",
+    description:
+        "An `as` cast between integer types never fails. Casting to a narrower type silently drops the high \
+         bits, and casting a signed value to an unsigned type turns negative values into huge positive ones. \
+         Unlike `+` and `-` this is not caught by `overflow-checks`, so a timestamp difference, balance or \
+         reward computed in u64/i64 and cast to u32 or u8 can wrap without any error.",
+    severity: Severity::Medium,
+    recommendation:
+        "Convert with `u32::try_from(value)` (or `TryInto`) and map the error to a program error, or keep the \
+         computation in the wider type and store it in a field large enough to hold it.",
+    additional_links: Some(
+        "https://doc.rust-lang.org/reference/expressions/operator-expr.html#numeric-cast\n\
+         https://www.sec3.dev/blog/understanding-arithmetic-overflow-underflows-in-rust-and-solana-smart-contracts\n\
+         https://www.helius.dev/blog/solana-arithmetic"
+    ),
+};
+
+/* ────────────────────────────────────────────────────────────────
+   L-004 — Unchecked Arithmetic
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_UNCHECKED_ARITHMETIC: RuleMetadata = RuleMetadata {
+    code: "L-004",
+    title: "Unchecked Arithmetic on Account State or Arguments",
+    description:
+        "`+`, `-` and `*` on account fields or instruction arguments can overflow or underflow. With \
+         `overflow-checks` enabled the transaction panics, otherwise the value silently wraps, e.g. a counter \
+         decremented below zero becomes `u8::MAX` and a balance sum restarts at zero. Values from account state \
+         and callers are exactly the ones an attacker can push to the edges of their range.",
+    severity: Severity::Low,
+    recommendation:
+        "Use `checked_add`, `checked_sub` and `checked_mul` and turn `None` into a program error, or \
+         `saturating_*` where clamping is the intended behavior. Keep `overflow-checks = true` in the release \
+         profile as a backstop.",
+    additional_links: Some(
+        "https://www.sec3.dev/blog/understanding-arithmetic-overflow-underflows-in-rust-and-solana-smart-contracts\n\
+         https://www.helius.dev/blog/solana-arithmetic\n\
+         https://rareskills.io/post/rust-arithmetic-operators"
+    ),
+};
+
+/// Methods whose result has the type of their receiver, `x.checked_add(1).unwrap()` is still `x`
+const PASSTHROUGH_METHODS: &[&str] = &[
+    "unwrap", "expect", "clone", "to_owned", "unwrap_or", "unwrap_or_default", "ok_or", "ok_or_else", "min", "max", "abs", "pow",
+];
+/// Fields of `Clock`, the usual source of timestamps and slots
+const CLOCK_FIELDS: &[(&str, &str)] = &[
+    ("slot", "u64"),
+    ("epoch_start_timestamp", "i64"),
+    ("epoch", "u64"),
+    ("leader_schedule_epoch", "u64"),
+    ("unix_timestamp", "i64"),
+];
+
+pub struct UncheckedArithmeticRule;
+
+impl Rule for UncheckedArithmeticRule {
+    fn id(&self) -> &'static str {
+        "unchecked_arithmetic"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_UNCHECKED_ARITHMETIC
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for (file, issues) in ctx.files.iter().zip(ctx.arithmetic_issues()) {
+            rules_arithmetic(file, issues, ArithmeticKind::Unchecked, r);
+        }
+    }
+}
+
+pub struct LossyCastRule;
+
+impl Rule for LossyCastRule {
+    fn id(&self) -> &'static str {
+        "lossy_cast"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_LOSSY_CAST
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for (file, issues) in ctx.files.iter().zip(ctx.arithmetic_issues()) {
+            rules_arithmetic(file, issues, ArithmeticKind::Cast, r);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticKind {
+    // `+ - *` and their compound assignments
+    Unchecked,
+    // `as` into a type that can't hold every value of the source
+    Cast,
+}
+
+/// Where a value in an expression comes from, i.e an account field or an instruction argument
+#[derive(Debug, Clone)]
+pub struct ValueSource {
+    // the operand as written, `self.bet.slot`
+    pub name: String,
+    // "a field of `UserAccount` (`u8`)"
+    pub what: String,
+    // the local the value went through, `elapsed` in `let elapsed = .. - self.stake.staked_at`
+    pub via: Option<String>,
+    // declaration of the field in its struct
+    pub decl: Option<Location>,
+}
+
+impl ValueSource {
+    fn describe(&self) -> String {
+        match &self.via {
+            Some(local) => format!("`{}` comes from `{}`, {}", local, self.name, self.what),
+            None => format!("`{}` is {}", self.name, self.what),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArithmeticIssue {
+    pub kind: ArithmeticKind,
+    pub function_name: String,
+    pub expression: String,
+    pub span: Span,
+    pub message: String,
+    pub source: Option<ValueSource>,
+}
+
+/// A field of a struct the checker can resolve, with its type name
+struct FieldInfo {
+    ty: String,
+    owner: String,
+    // a field of an `#[account]` or nested data struct, not an Accounts handle
+    is_data: bool,
+    decl: Option<Location>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct LocalInfo {
+    ty: Option<String>,
+    source: Option<ValueSource>,
+}
+
+/// Walks fn bodies resolving just enough types to tell account state and arguments apart from constants
+pub struct ArithmeticChecker<'a> {
+    pub program: &'a NormalizedProgram,
+    pub source: &'a str,
+    pub issues: Vec<ArithmeticIssue>,
+    pub current_fn: String,
+    // `Unstake` inside `impl<'info> Unstake<'info>`
+    self_type: Option<String>,
+    params: HashMap<String, String>,
+    locals: HashMap<String, LocalInfo>,
+    // inside an already reported arithmetic expression
+    in_reported: bool,
+}
+
+impl<'a> ArithmeticChecker<'a> {
+    pub fn new(program: &'a NormalizedProgram, source: &'a str) -> Self {
+        ArithmeticChecker {
+            program,
+            source,
+            issues: Vec::new(),
+            current_fn: String::new(),
+            self_type: None,
+            params: HashMap::new(),
+            locals: HashMap::new(),
+            in_reported: false,
+        }
+    }
+
+    fn enter(&mut self, sig: &Signature) {
+        self.current_fn = sig.ident.to_string();
+        self.locals.clear();
+        self.params = sig
+            .inputs
+            .iter()
+            .filter_map(|input| match input {
+                FnArg::Typed(t) => match &*t.pat {
+                    Pat::Ident(p) => Some((p.ident.to_string(), type_name_of(&t.ty))),
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .collect();
+    }
+
+    /// Parameters a caller controls, the Anchor context and bumps are not values
+    fn is_caller_param(&self, name: &str) -> bool {
+        self.params
+            .get(name)
+            .is_some_and(|ty| !ty.starts_with("Context") && !ty.ends_with("Bumps"))
+    }
+
+    fn field_info(&self, type_name: &str, member: &str) -> Option<FieldInfo> {
+        if let Some(target) = type_name.strip_prefix("Context<").and_then(|t| t.strip_suffix('>')) {
+            return (member == "accounts").then(|| FieldInfo { ty: target.to_string(), owner: type_name.to_string(), is_data: false, decl: None });
+        }
+        if type_name == "Clock" {
+            let (_, ty) = CLOCK_FIELDS.iter().find(|(name, _)| *name == member)?;
+            return Some(FieldInfo { ty: ty.to_string(), owner: "Clock".to_string(), is_data: false, decl: None });
+        }
+        // ctx.accounts.<field> / self.<field> resolves to the account's data type
+        if let Some(s) = self.program.account_structs.iter().find(|s| s.name == type_name) {
+            let field = s.fields.iter().find(|f| f.name == member)?;
+            let ty = field
+                .generic_args
+                .iter()
+                .rfind(|g| !g.starts_with('\''))
+                .cloned()
+                .unwrap_or_else(|| field.base_type.clone());
+            return Some(FieldInfo { ty: ty.replace(' ', ""), owner: s.name.clone(), is_data: false, decl: None });
+        }
+        let item = self
+            .program
+            .storage_structs
+            .iter()
+            .chain(&self.program.data_structs)
+            .find(|s| s.ident == type_name)?;
+        let field = item.fields.iter().find(|f| f.ident.as_ref().is_some_and(|i| i == member))?;
+        let decl = self
+            .program
+            .type_files
+            .get(type_name)
+            .zip(field.ident.as_ref())
+            .map(|(file, ident)| Location::from_span(file, ident.span()));
+        Some(FieldInfo { ty: type_name_of(&field.ty), owner: type_name.to_string(), is_data: true, decl })
+    }
+
+    /// Best effort type name of an expression, `None` when it can't be told without type inference
+    fn expr_type(&self, e: &Expr) -> Option<String> {
+        match e {
+            Expr::Lit(l) => match &l.lit {
+                Lit::Int(i) if !i.suffix().is_empty() => Some(i.suffix().to_string()),
+                _ => None,
+            },
+            Expr::Paren(p) => self.expr_type(&p.expr),
+            Expr::Group(g) => self.expr_type(&g.expr),
+            Expr::Reference(r) => self.expr_type(&r.expr),
+            Expr::Unary(u) => self.expr_type(&u.expr),
+            Expr::Try(t) => self.expr_type(&t.expr),
+            Expr::Cast(c) => Some(type_name_of(&c.ty)),
+            Expr::Path(p) => {
+                let ident = p.path.get_ident()?.to_string();
+                if ident == "self" {
+                    return self.self_type.clone();
+                }
+                self.locals
+                    .get(&ident)
+                    .and_then(|l| l.ty.clone())
+                    .or_else(|| self.params.get(&ident).cloned())
+            }
+            Expr::Field(f) => {
+                let Member::Named(member) = &f.member else { return None };
+                let base = self.expr_type(&f.base)?;
+                Some(self.field_info(&base, &member.to_string())?.ty)
+            }
+            Expr::Binary(b) if is_arithmetic(&b.op) => self.expr_type(&b.left).or_else(|| self.expr_type(&b.right)),
+            Expr::MethodCall(m) => {
+                let method = m.method.to_string();
+                if PASSTHROUGH_METHODS.contains(&method.as_str()) || ["checked_", "saturating_", "wrapping_"].iter().any(|p| method.starts_with(p)) {
+                    self.expr_type(&m.receiver)
+                } else if method == "key" {
+                    Some("Pubkey".to_string())
+                } else if method == "len" {
+                    Some("usize".to_string())
+                } else {
+                    None
+                }
+            }
+            // Clock::get()
+            Expr::Call(c) => match &*c.func {
+                Expr::Path(p) if p.path.segments.len() == 2 && p.path.segments[1].ident == "get" => {
+                    Some(p.path.segments[0].ident.to_string())
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The account field, argument or derived local an expression reads, if any
+    fn value_source(&self, e: &Expr) -> Option<ValueSource> {
+        match e {
+            Expr::Paren(p) => self.value_source(&p.expr),
+            Expr::Group(g) => self.value_source(&g.expr),
+            Expr::Reference(r) => self.value_source(&r.expr),
+            Expr::Unary(u) => self.value_source(&u.expr),
+            Expr::Try(t) => self.value_source(&t.expr),
+            Expr::Cast(c) => self.value_source(&c.expr),
+            Expr::Index(i) => self.value_source(&i.expr),
+            Expr::Binary(b) if is_arithmetic(&b.op) => self.value_source(&b.left).or_else(|| self.value_source(&b.right)),
+            Expr::MethodCall(m) if PASSTHROUGH_METHODS.contains(&m.method.to_string().as_str()) => self.value_source(&m.receiver),
+            Expr::Path(p) => {
+                let ident = p.path.get_ident()?.to_string();
+                if let Some(local) = self.locals.get(&ident) {
+                    return local.source.clone().map(|s| ValueSource { via: Some(ident), ..s });
+                }
+                self.is_caller_param(&ident).then(|| ValueSource {
+                    what: format!("an argument of `{}` (`{}`)", self.current_fn, self.params[&ident]),
+                    name: ident,
+                    via: None,
+                    decl: None,
+                })
+            }
+            Expr::Field(f) => {
+                let Member::Named(member) = &f.member else { return None };
+                let info = self.expr_type(&f.base).and_then(|base| self.field_info(&base, &member.to_string()));
+                match info {
+                    Some(info) if info.is_data => Some(ValueSource {
+                        name: span_text(self.source, f.span()),
+                        what: format!("a field of `{}` (`{}`)", info.owner, info.ty),
+                        via: None,
+                        decl: info.decl,
+                    }),
+                    // `args.amount` on a caller argument
+                    _ => self.value_source(&f.base),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn check_arithmetic(&mut self, expr: &ExprBinary) -> bool {
+        let Some((verb, checked)) = overflow_kind(&expr.op) else { return false };
+        if self.current_fn.is_empty() || (is_literal(&expr.left) && is_literal(&expr.right)) {
+            return false;
+        }
+        let ty = self.expr_type(&expr.left).or_else(|| self.expr_type(&expr.right));
+        if ty.as_deref().is_some_and(|t| int_info(t).is_none()) {
+            return false;
+        }
+        let Some(source) = self.value_source(&expr.left).or_else(|| self.value_source(&expr.right)) else { return false };
+
+        let text = span_text(self.source, expr.span());
+        let ty = ty.map(|t| format!(" `{}`", t)).unwrap_or_default();
+        self.issues.push(ArithmeticIssue {
+            kind: ArithmeticKind::Unchecked,
+            function_name: self.current_fn.clone(),
+            message: format!(
+                "`{}` in `{}` can {}{} without a check, {}. Use `{}`",
+                text, self.current_fn, verb, ty, source.describe(), checked
+            ),
+            expression: text,
+            span: expr.span(),
+            source: Some(source),
+        });
+        true
+    }
+
+    fn check_cast(&mut self, expr: &ExprCast) {
+        if self.current_fn.is_empty() {
+            return;
+        }
+        let target = type_name_of(&expr.ty);
+        let Some(from) = self.expr_type(&expr.expr) else { return };
+        let (Some(src), Some(dst)) = (int_info(&from), int_info(&target)) else { return };
+        let Some(loss) = cast_loss(src, dst, &target) else { return };
+
+        let text = span_text(self.source, expr.span());
+        let value = span_text(self.source, expr.expr.span());
+        let source = self.value_source(&expr.expr);
+        let origin = source.as_ref().map(|s| format!(", {}", s.describe())).unwrap_or_default();
+        self.issues.push(ArithmeticIssue {
+            kind: ArithmeticKind::Cast,
+            function_name: self.current_fn.clone(),
+            message: format!(
+                "`{}` in `{}` casts `{}` from `{}` to `{}`: {}{}. Use `{}::try_from({})`",
+                text, self.current_fn, value, from, target, loss, origin, target, value
+            ),
+            expression: text,
+            span: expr.span(),
+            source,
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for ArithmeticChecker<'_> {
+    fn visit_item_impl(&mut self, node: &'ast syn::ItemImpl) {
+        let previous = self.self_type.replace(type_name_of(&node.self_ty));
+        visit::visit_item_impl(self, node);
+        self.self_type = previous;
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.enter(&node.sig);
+        visit::visit_item_fn(self, node);
+        self.current_fn.clear();
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.enter(&node.sig);
+        visit::visit_impl_item_fn(self, node);
+        self.current_fn.clear();
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        // the initializer is evaluated before the binding exists
+        visit::visit_local(self, local);
+        let (pat, annotated) = match &local.pat {
+            Pat::Type(t) => (&*t.pat, Some(type_name_of(&t.ty))),
+            other => (other, None),
+        };
+        let Pat::Ident(ident) = pat else { return };
+        let init = local.init.as_ref().map(|i| &*i.expr);
+        let info = LocalInfo {
+            ty: annotated.or_else(|| init.and_then(|e| self.expr_type(e))),
+            source: init.and_then(|e| self.value_source(e)),
+        };
+        self.locals.insert(ident.ident.to_string(), info);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast ExprBinary) {
+        // `a + b * c` is reported once, as the outermost expression
+        if !self.in_reported && self.check_arithmetic(expr) {
+            self.in_reported = true;
+            visit::visit_expr_binary(self, expr);
+            self.in_reported = false;
+            return;
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_expr_cast(&mut self, expr: &'ast ExprCast) {
+        self.check_cast(expr);
+        visit::visit_expr_cast(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        // require!(self.bet.slot - slot > 1000) is arithmetic too
+        if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+fn is_arithmetic(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Add(_) | BinOp::Sub(_) | BinOp::Mul(_) | BinOp::Div(_) | BinOp::Rem(_)
+            | BinOp::AddAssign(_) | BinOp::SubAssign(_) | BinOp::MulAssign(_) | BinOp::DivAssign(_) | BinOp::RemAssign(_)
+    )
+}
+
+/// What can go wrong with an operator and its checked replacement
+fn overflow_kind(op: &BinOp) -> Option<(&'static str, &'static str)> {
+    match op {
+        BinOp::Add(_) | BinOp::AddAssign(_) => Some(("overflow", "checked_add")),
+        BinOp::Sub(_) | BinOp::SubAssign(_) => Some(("underflow", "checked_sub")),
+        BinOp::Mul(_) | BinOp::MulAssign(_) => Some(("overflow", "checked_mul")),
+        _ => None,
+    }
+}
+
+fn is_literal(e: &Expr) -> bool {
+    match e {
+        Expr::Lit(_) => true,
+        Expr::Paren(p) => is_literal(&p.expr),
+        Expr::Cast(c) => is_literal(&c.expr),
+        _ => false,
+    }
+}
+
+/// (bits, signed) of a primitive integer type
+fn int_info(ty: &str) -> Option<(u32, bool)> {
+    Some(match ty {
+        "u8" => (8, false),
+        "u16" => (16, false),
+        "u32" => (32, false),
+        "u64" | "usize" => (64, false),
+        "u128" => (128, false),
+        "i8" => (8, true),
+        "i16" => (16, true),
+        "i32" => (32, true),
+        "i64" | "isize" => (64, true),
+        "i128" => (128, true),
+        _ => return None,
+    })
+}
+
+/// Why casting `src` to `dst` loses information, `None` when every value fits
+fn cast_loss((src_bits, src_signed): (u32, bool), (dst_bits, dst_signed): (u32, bool), target: &str) -> Option<String> {
+    let mut losses = Vec::new();
+    let max_fits = match (src_signed, dst_signed) {
+        (true, false) => dst_bits + 1 >= src_bits,
+        (false, true) => dst_bits > src_bits,
+        _ => dst_bits >= src_bits,
+    };
+    if !max_fits && !src_signed && dst_signed && dst_bits == src_bits {
+        losses.push(format!("values above `{}::MAX` become negative", target));
+    } else if !max_fits {
+        losses.push(format!("values above `{}::MAX` are truncated", target));
+    }
+    if src_signed && !dst_signed {
+        losses.push("negative values wrap to large positive ones".to_string());
+    } else if src_signed && dst_bits < src_bits {
+        losses.push(format!("values below `{}::MIN` are truncated", target));
+    }
+    (!losses.is_empty()).then(|| losses.join(" and "))
+}
+
+/// `Account<'info, Bet>` -> `Bet`, `&u64` -> `u64`, `Context<'_, '_, '_, 'info, Refund<'info>>` -> `Context<Refund>`
+fn type_name_of(ty: &Type) -> String {
+    match ty {
+        Type::Reference(r) => type_name_of(&r.elem),
+        Type::Paren(p) => type_name_of(&p.elem),
+        Type::Group(g) => type_name_of(&g.elem),
+        Type::Path(p) => {
+            let Some(last) = p.path.segments.last() else { return String::new() };
+            if last.ident == "Context"
+                && let PathArguments::AngleBracketed(args) = &last.arguments
+                && let Some(GenericArgument::Type(target)) = args.args.iter().rfind(|a| matches!(a, GenericArgument::Type(_))) {
+                return format!("Context<{}>", type_name_of(target));
+            }
+            last.ident.to_string()
+        }
+        other => other.to_token_stream().to_string().replace(' ', ""),
+    }
+}
+
+/// Both kinds of issues in `file`, the rules pick theirs from `AnalysisContext::arithmetic_issues`
+pub fn arithmetic_issues(file: &SourceFile, program: &NormalizedProgram) -> Vec<ArithmeticIssue> {
+    let mut checker = ArithmeticChecker::new(program, &file.source);
+    checker.visit_file(&file.ast);
+    checker.issues
+}
+
+pub fn rules_arithmetic(file: &SourceFile, issues: &[ArithmeticIssue], kind: ArithmeticKind, r: &mut Report) {
+    let rule = match kind {
+        ArithmeticKind::Unchecked => &RULE_UNCHECKED_ARITHMETIC,
+        ArithmeticKind::Cast => &RULE_LOSSY_CAST,
+    };
+    for issue in issues.iter().filter(|i| i.kind == kind) {
+        let mut finding = Finding::new(
+            rule,
+            Location::from_span(&file.path, issue.span),
+            &issue.function_name,
+            &issue.expression,
+            &issue.message,
+        )
+        .detail("Function", &issue.function_name)
+        .detail("Expression", &issue.expression);
+        if let Some(source) = &issue.source {
+            finding = finding.detail("Value Source", source.describe());
+            if let Some(decl) = &source.decl {
+                finding = finding.label(decl.clone(), format!("`{}` reads this field", source.name));
+            }
+        }
+        r.add(finding);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+
+    const STATE: &str = r#"
+        #[account]
+        pub struct UserAccount { pub points: u32, pub amount_staked: u8 }
+        #[account]
+        pub struct StakeConfig { pub points_per_stake: u8 }
+        #[account]
+        pub struct Bet { pub slot: u64, pub amount: u64 }
+        #[derive(Accounts)]
+        pub struct Unstake<'info> {
+            pub user_account: Account<'info, UserAccount>,
+            pub config: Account<'info, StakeConfig>,
+            pub bet: Account<'info, Bet>,
+        }
+    "#;
+
+    /// (kind, message) of every issue in `body`, a method of `Unstake` taking `elapsed: i64`
+    fn issues(body: &str) -> Vec<(ArithmeticKind, String)> {
+        let source = format!("{}\nimpl<'info> Unstake<'info> {{ pub fn unstake(&mut self, elapsed: i64) -> Result<()> {{ {} Ok(()) }} }}", STATE, body);
+        let files = [SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(&source).unwrap(), source }];
+        let program = normalize_program(&files);
+        arithmetic_issues(&files[0], &program).into_iter().map(|i| (i.kind, i.message)).collect()
+    }
+
+    fn unchecked(body: &str) -> Vec<String> {
+        issues(body).into_iter().filter(|(k, _)| *k == ArithmeticKind::Unchecked).map(|(_, m)| m).collect()
+    }
+
+    #[test]
+    fn unchecked_account_arithmetic() {
+        let reward = unchecked("let reward = self.config.points_per_stake as u32 * elapsed as u32;");
+        assert_eq!(reward.len(), 1);
+        assert!(reward[0].starts_with("`self.config.points_per_stake as u32 * elapsed as u32` in `unstake` can overflow `u32`"));
+
+        let decrement = unchecked("self.user_account.amount_staked -= 1;");
+        assert_eq!(decrement.len(), 1);
+        assert!(decrement[0].contains("can underflow `u8`"));
+    }
+
+    #[test]
+    fn names_the_operand_it_explains() {
+        let refund = unchecked("let slot = Clock::get()?.slot; require!((self.bet.slot - slot) > 1000, E::Timeout);");
+        assert_eq!(refund.len(), 1);
+        assert!(refund[0].contains("`self.bet.slot` is a field of `Bet` (`u64`)"), "{}", refund[0]);
+    }
+
+    #[test]
+    fn checked_math_and_widening_casts_are_fine() {
+        assert!(issues("self.user_account.amount_staked = self.user_account.amount_staked.checked_sub(1).unwrap();").is_empty());
+        assert!(issues("self.user_account.points = self.user_account.points.saturating_add(5);").is_empty());
+        assert!(issues("let wide = self.user_account.amount_staked as u64; let points = self.user_account.points as u128;").is_empty());
+
+        let narrowing = issues("let points = self.bet.amount as u32;");
+        assert_eq!(narrowing.len(), 1);
+        assert_eq!(narrowing[0].0, ArithmeticKind::Cast);
+    }
+}
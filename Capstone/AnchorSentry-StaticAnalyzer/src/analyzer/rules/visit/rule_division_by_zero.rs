@@ -1,57 +1,473 @@
-use syn::visit::{self, Visit};
+use quote::ToTokens;
 use syn::spanned::Spanned;
-use syn::{Expr, ExprBinary, ExprPath};
-use crate::state::{PossibleDivisionByZeroFindingCheckerInstance};
+use syn::visit::{self, Visit};
+use syn::punctuated::Punctuated;
+use syn::{BinOp, Expr, ExprBinary, ExprIf, ExprMethodCall, FnArg, Lit, Pat, Signature, Stmt, Token};
+use crate::analyzer::constraints::span_text;
+use crate::state::{PossibleDivisionByZeroFindingCheckerInstance, SourceFile};
 use crate::report::report::*;
-use crate::report::knowledge_base::{Finding, PossibleDivisionByZeroFinding, RULE_POSSIBLE_DIVISION_BY_ZERO};
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::analyzer::rules::registry::{AnalysisContext, Rule};
+
+/* ────────────────────────────────────────────────────────────────
+   L-001 — Possible Division by Zero
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_POSSIBLE_DIVISION_BY_ZERO: RuleMetadata = RuleMetadata {
+    code: "L-001",
+    title: "Possible Division by Zero",
+    description:
+        "This code performs a division using a runtime-controlled variable. If the \
+         divisor evaluates to zero, the program will panic and revert the entire transaction. \
+         Division involving user-provided values, account data, or unvalidated computations \
+         requires explicit zero-checks. On-chain financial logic such as fee splits, reward \
+         calculations, or escrow payments frequently exhibit this weakness when insufficient \
+         input validation is applied.",
+    severity: Severity::Low,
+    recommendation:
+        "Before performing the division, add a strict check ensuring the divisor is non-zero. \
+         Reject or sanitize invalid inputs. For performance and safety, perform this validation \
+         as early as possible in the function logic, and document expected input constraints. \
+         `checked_div` and `checked_rem` only help if the `None` is turned into an error, unwrapping it \
+         panics the same way.",
+    additional_links: Some(
+        "https://exvul.com/rust-smart-contract-security-guide-in-solana/\n\
+         https://rareskills.io/post/rust-arithmetic-operators\n\
+         https://www.sec3.dev/blog/understanding-arithmetic-overflow-underflows-in-rust-and-solana-smart-contracts\n\
+         https://www.helius.dev/blog/solana-arithmetic\n\
+         https://solodit.cyfrin.io/issues/risk-of-division-by-zero-ottersec-none-switchboard-off-chain-pdf\n\
+         https://solodit.cyfrin.io/issues/h-07-user-cannot-claim-rewards-or-close_position-due-to-vulnerable-division-by-zero-handling-code4rena-mantra-mantra-git"
+    ),
+};
+
+/// `checked_*` ops that return `None` on a zero divisor
+const CHECKED_DIVISIONS: &[&str] = &["checked_div", "checked_rem", "checked_div_euclid", "checked_rem_euclid"];
+/// Ways to turn an `Option` into a panic
+const UNWRAPS: &[&str] = &["unwrap", "expect", "unwrap_unchecked"];
+/// Macros whose first argument must hold, `require!(d > 0, E)`
+const ASSERT_MACROS: &[&str] = &["require", "assert", "debug_assert"];
+/// Comparison macros, `require_neq!(d, 0)` / `require_gt!(d, 0)` / `require_gte!(d, 1)`
+const COMPARE_MACROS: &[&str] = &["require_neq", "assert_ne", "require_gt", "require_gte"];
+/// Macros that never return
+const DIVERGING_MACROS: &[&str] = &["panic", "unreachable", "err", "error"];
+
+pub struct DivisionByZeroRule;
+
+impl Rule for DivisionByZeroRule {
+    fn id(&self) -> &'static str {
+        "possible_division_by_zero"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_POSSIBLE_DIVISION_BY_ZERO
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for file in ctx.files {
+            rules_division_by_variable(file, r);
+        }
+    }
+}
 
-pub struct PossibleDivisionByZeroChecker {
+pub struct PossibleDivisionByZeroChecker<'a> {
     pub instance: Vec<PossibleDivisionByZeroFindingCheckerInstance>,
-    pub current_fn: String
+    pub current_fn: String,
+    pub file: String,
+    pub source: &'a str,
+    // divisors proven non-zero at the current point of the fn, i.e `d` after `require!(d > 0)`
+    guarded: Vec<String>,
+    // every value assigned to so far in the fn, a guard doesn't survive leaving a scope that reassigned it
+    assigned: Vec<String>,
+    // `NonZeroU64` params and locals
+    non_zero: Vec<String>,
 }
 
-impl<'ast> Visit<'ast> for PossibleDivisionByZeroChecker {
-    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
-        self.current_fn = node.sig.ident.to_string();
+impl<'a> PossibleDivisionByZeroChecker<'a> {
+    pub fn new(file: &'a SourceFile) -> Self {
+        PossibleDivisionByZeroChecker {
+            instance: Vec::new(),
+            current_fn: String::new(),
+            file: file.path.clone(),
+            source: &file.source,
+            guarded: Vec::new(),
+            assigned: Vec::new(),
+            non_zero: Vec::new(),
+        }
+    }
+
+    fn enter(&mut self, sig: &Signature) {
+        self.current_fn = sig.ident.to_string();
+        self.guarded.clear();
+        self.assigned.clear();
+        self.non_zero = sig
+            .inputs
+            .iter()
+            .filter_map(|input| match input {
+                FnArg::Typed(t) if t.ty.to_token_stream().to_string().contains("NonZero") => match &*t.pat {
+                    Pat::Ident(p) => Some(p.ident.to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+    }
+
+    /// Whether the divisor can't be zero here: a non-zero constant, `x.max(1)`, a `NonZero*` or a guarded value
+    fn is_safe(&self, divisor: &Expr) -> bool {
+        let divisor = strip(divisor);
+        match divisor {
+            Expr::Lit(l) => !matches!(&l.lit, Lit::Int(i) if i.base10_digits() == "0"),
+            Expr::Path(p) if p.path.segments.last().is_some_and(|s| is_const_name(&s.ident.to_string())) => true,
+            Expr::MethodCall(m) if m.method == "max" && m.args.first().is_some_and(|a| matches!(strip(a), Expr::Lit(_)) && self.is_safe(a)) => true,
+            // NonZeroU64::get
+            Expr::MethodCall(m) if m.method == "get" && self.is_safe(&m.receiver) => true,
+            _ => {
+                let key = divisor_key(divisor);
+                self.guarded.contains(&key) || self.non_zero.contains(&key)
+            }
+        }
+    }
+
+    fn push(&mut self, divisor: &Expr, operator: &str, unwrapped: bool) {
+        if self.current_fn.is_empty() || self.is_safe(divisor) {
+            return;
+        }
+        let span = strip(divisor).span();
+        let (start, end) = (span.start(), span.end());
+        self.instance.push(PossibleDivisionByZeroFindingCheckerInstance {
+            function_name: self.current_fn.clone(),
+            file: self.file.clone(),
+            divisor: span_text(self.source, span),
+            operator: operator.to_string(),
+            unwrapped,
+            line: start.line,
+            column: start.column + 1,
+            end_line: end.line,
+            end_column: end.column + 1,
+        });
+    }
+
+    /// Runs `f` in a nested scope: guards it adds are dropped afterwards, guards it reassigns stay dropped
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        let saved = self.guarded.clone();
+        let mark = self.assigned.len();
+        f(self);
+        let assigned = &self.assigned[mark..];
+        self.guarded = saved.into_iter().filter(|g| !assigned.iter().any(|a| invalidates(a, g))).collect();
+    }
+
+    /// `d = 0` or `d -= 1`, an earlier guard on `d` no longer holds
+    fn reassign(&mut self, target: &Expr) {
+        let key = divisor_key(target);
+        self.guarded.retain(|g| !invalidates(&key, g));
+        self.assigned.push(key);
+    }
 
+    /// Guards from a `require!`-like macro, its arguments are visited for divisions first
+    fn macro_guards(&mut self, mac: &syn::Macro) {
+        let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) else { return };
+        for arg in &args {
+            self.visit_expr(arg);
+        }
+        let name = mac.path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default();
+        let args: Vec<&Expr> = args.iter().collect();
+        if ASSERT_MACROS.contains(&name.as_str())
+            && let Some(cond) = args.first() {
+            self.guarded.extend(non_zero_when(cond));
+        } else if COMPARE_MACROS.contains(&name.as_str())
+            && let [a, b, ..] = args[..] {
+            // require_gt!(d, 0): d > 0, require_gte!(d, 1): d >= 1, require_neq!(d, 0) either way round
+            let bound = if name == "require_gte" { "1" } else { "0" };
+            if is_int(b, bound) {
+                self.guarded.push(divisor_key(a));
+            } else if (name == "require_neq" || name == "assert_ne") && is_int(a, "0") {
+                self.guarded.push(divisor_key(b));
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for PossibleDivisionByZeroChecker<'_> {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.enter(&node.sig);
         syn::visit::visit_item_fn(self, node);
+        self.current_fn.clear();
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.enter(&node.sig);
+        syn::visit::visit_impl_item_fn(self, node);
+        self.current_fn.clear();
     }
 
     fn visit_expr_binary(&mut self, expr: &'ast ExprBinary) {
-        // Check if this is a division: left / right
-        if matches!(expr.op, syn::BinOp::Div(_)) {
-            // expr.right is Box<Expr> -> deref before matching
-            let divisor_expr: &Expr = &*expr.right;
-
-            // We only care about the simple case: "some_var" (Expr::Path with single ident)
-            if let Expr::Path(ExprPath { path, .. }) = divisor_expr {
-                if let Some(ident) = path.get_ident() {
-                    // don't attempt to get line/col yet; just include span debug for context
-                    let line_number = expr.span().start().line;
-                    self.instance.push(PossibleDivisionByZeroFindingCheckerInstance {
-                        function_name: self.current_fn.clone(),
-                        divisor: ident.to_string(),
-                        line: line_number
-                    });
-                }
-            }
+        // left / right, left % right and the compound forms
+        let operator = match expr.op {
+            BinOp::Div(_) | BinOp::DivAssign(_) => Some("/"),
+            BinOp::Rem(_) | BinOp::RemAssign(_) => Some("%"),
+            _ => None,
+        };
+        if let Some(operator) = operator {
+            self.push(&expr.right, operator, false);
+        }
+
+        // `d != 0 && a / d > x` only divides when the left side holds
+        if matches!(expr.op, BinOp::And(_)) {
+            self.visit_expr(&expr.left);
+            self.scoped(|c| {
+                c.guarded.extend(non_zero_when(&expr.left));
+                c.visit_expr(&expr.right);
+            });
+            return;
         }
 
         // continue walking inside this binary expression
         visit::visit_expr_binary(self, expr);
+        if is_compound_assign(&expr.op) {
+            self.reassign(&expr.left);
+        }
+    }
+
+    fn visit_expr_assign(&mut self, expr: &'ast syn::ExprAssign) {
+        visit::visit_expr_assign(self, expr);
+        self.reassign(&expr.left);
+    }
+
+    fn visit_block(&mut self, block: &'ast syn::Block) {
+        // a guard in a loop body, branch or closure only holds inside it
+        self.scoped(|c| visit::visit_block(c, block));
+    }
+
+    fn visit_expr_block(&mut self, expr: &'ast syn::ExprBlock) {
+        // a plain `{ .. }` always runs to its end, its guards hold after it too
+        if expr.label.is_none() {
+            for stmt in &expr.block.stmts {
+                self.visit_stmt(stmt);
+            }
+            return;
+        }
+        visit::visit_expr_block(self, expr);
+    }
+
+    fn visit_arm(&mut self, arm: &'ast syn::Arm) {
+        self.scoped(|c| visit::visit_arm(c, arm));
+    }
+
+    fn visit_expr_closure(&mut self, expr: &'ast syn::ExprClosure) {
+        // the closure may never be called
+        self.scoped(|c| visit::visit_expr_closure(c, expr));
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        // a.checked_div(b).unwrap() panics on zero just like `/`
+        if UNWRAPS.contains(&expr.method.to_string().as_str())
+            && let Expr::MethodCall(inner) = strip(&expr.receiver)
+            && CHECKED_DIVISIONS.contains(&inner.method.to_string().as_str())
+            && let Some(divisor) = inner.args.first() {
+            self.push(divisor, &inner.method.to_string(), true);
+        }
+        visit::visit_expr_method_call(self, expr);
+    }
+
+    fn visit_expr_if(&mut self, expr: &'ast ExprIf) {
+        self.visit_expr(&expr.cond);
+
+        // if d > 0 { a / d }
+        self.scoped(|c| {
+            c.guarded.extend(non_zero_when(&expr.cond));
+            c.visit_block(&expr.then_branch);
+        });
+
+        // if d == 0 { .. } else { a / d }
+        let zero = zero_when(&expr.cond);
+        if let Some((_, else_branch)) = &expr.else_branch {
+            self.scoped(|c| {
+                c.guarded.extend(zero.iter().cloned());
+                c.visit_expr(else_branch);
+            });
+        }
+
+        // if d == 0 { return err!(..) } guards the rest of the function
+        if diverges(&expr.then_branch.stmts) {
+            self.guarded.extend(zero);
+        }
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        visit::visit_local(self, local);
+        let (pat, ty) = match &local.pat {
+            Pat::Type(t) => (&*t.pat, Some(t.ty.to_token_stream().to_string())),
+            other => (other, None),
+        };
+        let init = local.init.as_ref().map(|i| i.expr.to_token_stream().to_string());
+        if let Pat::Ident(ident) = pat {
+            let name = ident.ident.to_string();
+            // a shadowed binding is a new value, an earlier guard doesn't hold for it
+            self.guarded.retain(|g| *g != name);
+            if ty.iter().chain(&init).any(|t| t.contains("NonZero")) {
+                self.non_zero.push(name);
+            }
+        }
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        self.macro_guards(mac);
+        visit::visit_macro(self, mac);
     }
 }
 
-pub fn rules_division_by_variable(ast: &syn::File, r: &mut Report) {
-    let mut checker = PossibleDivisionByZeroChecker { instance: Vec::new(), current_fn: String::new(), };
-    checker.visit_file(ast);
+/// Parens, references, derefs and casts don't change whether a value is zero
+fn strip(e: &Expr) -> &Expr {
+    match e {
+        Expr::Paren(p) => strip(&p.expr),
+        Expr::Group(g) => strip(&g.expr),
+        Expr::Reference(r) => strip(&r.expr),
+        Expr::Cast(c) => strip(&c.expr),
+        Expr::Unary(u) if matches!(u.op, syn::UnOp::Deref(_)) => strip(&u.expr),
+        other => other,
+    }
+}
+
+/// Compares a divisor with a guard, `self.config.rate as u128` and `self . config . rate` are the same key
+fn divisor_key(e: &Expr) -> String {
+    strip(e).to_token_stream().to_string().replace(' ', "")
+}
+
+/// Assigning `self.config` invalidates guards on `self.config` and `self.config.rate`
+fn invalidates(assigned: &str, guard: &str) -> bool {
+    guard.strip_prefix(assigned).is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+fn is_compound_assign(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::AddAssign(_)
+            | BinOp::SubAssign(_)
+            | BinOp::MulAssign(_)
+            | BinOp::DivAssign(_)
+            | BinOp::RemAssign(_)
+            | BinOp::BitXorAssign(_)
+            | BinOp::BitAndAssign(_)
+            | BinOp::BitOrAssign(_)
+            | BinOp::ShlAssign(_)
+            | BinOp::ShrAssign(_)
+    )
+}
+
+fn is_int(e: &Expr, value: &str) -> bool {
+    matches!(strip(e), Expr::Lit(l) if matches!(&l.lit, Lit::Int(i) if i.base10_digits() == value))
+}
+
+/// `MAX_FEE`, `u64::MAX`
+fn is_const_name(name: &str) -> bool {
+    name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Values that are non-zero whenever `cond` is true, `d > 0 && e != 0` -> [d, e]
+fn non_zero_when(cond: &Expr) -> Vec<String> {
+    let Expr::Binary(b) = strip(cond) else { return Vec::new() };
+    match b.op {
+        BinOp::And(_) => non_zero_when(&b.left).into_iter().chain(non_zero_when(&b.right)).collect(),
+        BinOp::Ne(_) if is_int(&b.right, "0") => vec![divisor_key(&b.left)],
+        BinOp::Ne(_) if is_int(&b.left, "0") => vec![divisor_key(&b.right)],
+        BinOp::Gt(_) if is_int(&b.right, "0") => vec![divisor_key(&b.left)],
+        BinOp::Lt(_) if is_int(&b.left, "0") => vec![divisor_key(&b.right)],
+        BinOp::Ge(_) if is_int(&b.right, "1") => vec![divisor_key(&b.left)],
+        BinOp::Le(_) if is_int(&b.left, "1") => vec![divisor_key(&b.right)],
+        _ => Vec::new(),
+    }
+}
+
+/// Values that may be zero unless `cond` is true, `d == 0 || e == 0` -> [d, e]
+fn zero_when(cond: &Expr) -> Vec<String> {
+    match strip(cond) {
+        Expr::Binary(b) => match b.op {
+            BinOp::Or(_) => zero_when(&b.left).into_iter().chain(zero_when(&b.right)).collect(),
+            BinOp::Eq(_) if is_int(&b.right, "0") => vec![divisor_key(&b.left)],
+            BinOp::Eq(_) if is_int(&b.left, "0") => vec![divisor_key(&b.right)],
+            BinOp::Le(_) if is_int(&b.right, "0") => vec![divisor_key(&b.left)],
+            BinOp::Lt(_) if is_int(&b.right, "1") => vec![divisor_key(&b.left)],
+            _ => Vec::new(),
+        },
+        // if !(d > 0) { return }
+        Expr::Unary(u) if matches!(u.op, syn::UnOp::Not(_)) => non_zero_when(&u.expr),
+        _ => Vec::new(),
+    }
+}
+
+/// A block that always leaves the function: `return ..`, `panic!`, `return err!(..)`
+fn diverges(stmts: &[Stmt]) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        Stmt::Expr(Expr::Return(_), _) => true,
+        Stmt::Expr(Expr::Macro(m), _) => m.mac.path.segments.last().is_some_and(|s| DIVERGING_MACROS.contains(&s.ident.to_string().as_str())),
+        Stmt::Macro(m) => m.mac.path.segments.last().is_some_and(|s| DIVERGING_MACROS.contains(&s.ident.to_string().as_str())),
+        _ => false,
+    })
+}
+
+pub fn rules_division_by_variable(file: &SourceFile, r: &mut Report) {
+    let mut checker = PossibleDivisionByZeroChecker::new(file);
+    checker.visit_file(&file.ast);
     for p in checker.instance {
-        // println!("Possible division by 0 : {:?}", p);
-        r.add(Finding::PossibleDivisionByZero(PossibleDivisionByZeroFinding {
-            rule: &RULE_POSSIBLE_DIVISION_BY_ZERO,
-            fn_name : p.function_name,
-            line: p.line,
-            divisor: p.divisor       
-        }))
-    }
-}
\ No newline at end of file
+        let message = if p.unwrapped {
+            format!(
+                "`{}` by `{}` in `{}` is unwrapped, a zero divisor panics just like `/`",
+                p.operator, p.divisor, p.function_name
+            )
+        } else {
+            let op = if p.operator == "%" { "Remainder" } else { "Division" };
+            format!("{} by `{}` in `{}` may be zero, nothing checks it before this point", op, p.divisor, p.function_name)
+        };
+        r.add(Finding::new(
+            &RULE_POSSIBLE_DIVISION_BY_ZERO,
+            Location { file: p.file, line: p.line, column: p.column, end_line: p.end_line, end_column: p.end_column },
+            &p.function_name,
+            &p.divisor,
+            message,
+        )
+        .detail("Function", &p.function_name)
+        .detail("Divisor", &p.divisor)
+        .detail("Operator", &p.operator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Divisors flagged in `body`, the body of a fn taking `x`, `y` and `v`
+    fn flagged(body: &str) -> Vec<String> {
+        let source = format!("fn handler(x: u64, mut y: u64, v: Vec<u64>) {{ {} }}", body);
+        let file = SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(&source).unwrap(), source };
+        let mut checker = PossibleDivisionByZeroChecker::new(&file);
+        checker.visit_file(&file.ast);
+        checker.instance.into_iter().map(|i| i.divisor).collect()
+    }
+
+    #[test]
+    fn guards_cover_the_rest_of_their_block() {
+        assert!(flagged("require!(y != 0, E); let z = x / y;").is_empty());
+        assert!(flagged("if y == 0 { return err!(E); } let z = x / y;").is_empty());
+        assert!(flagged("{ require_gt!(y, 0); } let z = x / y;").is_empty());
+        assert!(flagged("if y > 0 { let z = x / y; }").is_empty());
+        assert!(flagged("let ok = y != 0 && x / y > 1;").is_empty());
+    }
+
+    #[test]
+    fn guards_inside_loops_arms_and_closures_stay_there() {
+        assert_eq!(flagged("for _ in v.iter() { require!(y != 0, E); } let z = x / y;"), vec!["y"]);
+        assert_eq!(flagged("while x > 0 { require!(y != 0, E); } let z = x / y;"), vec!["y"]);
+        assert_eq!(flagged("match x { 0 => require!(y != 0, E), _ => {} } let z = x / y;"), vec!["y"]);
+        assert_eq!(flagged("let check = || require!(y != 0, E); let z = x / y;"), vec!["y"]);
+        assert_eq!(flagged("if y > 0 { } let z = x / y;"), vec!["y"]);
+    }
+
+    #[test]
+    fn reassignment_drops_the_guard() {
+        assert_eq!(flagged("require!(y != 0, E); y = 0; let z = x / y;"), vec!["y"]);
+        assert_eq!(flagged("require!(y != 0, E); y -= 1; let z = x / y;"), vec!["y"]);
+        assert_eq!(flagged("require!(y != 0, E); for _ in v.iter() { y = 0; } let z = x / y;"), vec!["y"]);
+        assert_eq!(flagged("require!(self.a.b != 0, E); self.a = A::default(); let z = x / self.a.b;"), vec!["self.a.b"]);
+        assert!(flagged("require!(y != 0, E); let z = x / y; y = 0;").is_empty());
+        assert!(flagged("y = 5; require!(y != 0, E); let z = x / y;").is_empty());
+    }
+}
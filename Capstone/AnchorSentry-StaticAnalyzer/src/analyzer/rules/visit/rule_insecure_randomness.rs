@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::punctuated::Punctuated;
+use syn::{BinOp, Expr, ExprBinary, ExprMatch, Member, Pat, Token};
+use crate::analyzer::constraints::{expr_to_string, span_text};
+use crate::analyzer::rules::registry::{AnalysisContext, Rule};
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::state::SourceFile;
+
+/* ────────────────────────────────────────────────────────────────
+   H-003 — Insecure Randomness
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_INSECURE_RANDOMNESS: RuleMetadata = RuleMetadata {
+    code: "H-003",
+    title: "Insecure On-Chain Randomness",
+    description:
+        "The outcome of a game, lottery or reward is derived from the Clock (slot, unix_timestamp), SlotHashes \
+         or recent blockhashes. None of these are secret: they are known or guessable before the transaction \
+         executes, and hashing them doesn't change that. The leader that includes the transaction influences \
+         the slot and timestamp, and any user can simulate the instruction and only send it when it wins.",
+    severity: Severity::High,
+    recommendation:
+        "Use a verifiable randomness source such as an oracle VRF (Switchboard, ORAO), or a commit-reveal \
+         scheme where the outcome depends on a value committed before it could be known, i.e an Ed25519 \
+         signature by the house over the bet checked through the instructions sysvar.",
+    additional_links: Some(
+        "https://docs.switchboard.xyz/docs/switchboard/switchboard-randomness\n\
+         https://solana.stackexchange.com/questions/3147/how-can-i-generate-a-random-number-on-solana\n\
+         https://blog.solana.com/solana-randomness"
+    ),
+};
+
+/// Clock fields that look like entropy but are known ahead of time
+const CLOCK_FIELDS: &[&str] = &["slot", "unix_timestamp", "epoch", "epoch_start_timestamp", "leader_schedule_epoch"];
+/// Sysvars made of recent hashes, public before the transaction runs
+const HASH_SYSVARS: &[&str] = &["SlotHashes", "slot_hashes", "RecentBlockhashes", "recent_blockhashes", "recent_blockhash"];
+/// Hashing doesn't add entropy, `hash(&slot.to_le_bytes())` is as predictable as `slot`
+const HASH_FNS: &[&str] = &["hash", "hashv", "keccak", "sha256", "blake3", "extend_and_hash"];
+/// Turning a value into bytes, usually to feed a hash or pick a byte out of it
+const BYTE_METHODS: &[&str] = &["to_le_bytes", "to_be_bytes", "to_bytes", "as_ref", "to_vec"];
+
+pub struct InsecureRandomnessRule;
+
+impl Rule for InsecureRandomnessRule {
+    fn id(&self) -> &'static str {
+        "insecure_randomness"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_INSECURE_RANDOMNESS
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for file in ctx.files {
+            rules_insecure_randomness(file, r);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Entropy {
+    // a raw slot or timestamp, fine for timeouts
+    Time,
+    // a slot or timestamp `%` n, a roll when compared or matched on, a period otherwise
+    Reduced,
+    // hashed, mixed or sliced into bytes, i.e used as a random number
+    Derived,
+}
+
+/// Where the fake entropy of a value comes from
+#[derive(Debug, Clone)]
+pub struct EntropySource {
+    pub level: Entropy,
+    // `Clock::slot`, `SlotHashes`
+    pub origin: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RandomnessUse {
+    pub function_name: String,
+    pub origin: String,
+    // `%`, a comparison, a `match`
+    pub sink: String,
+    pub expression: String,
+    pub span: Span,
+}
+
+/// Follows Clock and recent hash reads through locals into `%`, comparisons and `match`
+pub struct RandomnessChecker<'a> {
+    pub uses: Vec<RandomnessUse>,
+    pub current_fn: String,
+    pub source: &'a str,
+    locals: HashMap<String, EntropySource>,
+    // `let clock = Clock::get()?;`
+    clocks: Vec<String>,
+}
+
+impl<'a> RandomnessChecker<'a> {
+    pub fn new(source: &'a str) -> Self {
+        RandomnessChecker { uses: Vec::new(), current_fn: String::new(), source, locals: HashMap::new(), clocks: Vec::new() }
+    }
+
+    fn enter(&mut self, name: String) {
+        self.current_fn = name;
+        self.locals.clear();
+        self.clocks.clear();
+    }
+
+    fn is_clock(&self, e: &Expr) -> bool {
+        let text = expr_to_string(e).replace(' ', "");
+        text.contains("Clock") || text.ends_with("clock") || self.clocks.contains(&text)
+    }
+
+    fn entropy(&self, e: &Expr) -> Option<EntropySource> {
+        let derived = |s: EntropySource| EntropySource { level: Entropy::Derived, ..s };
+        let reduced = |s: EntropySource| match s.level {
+            Entropy::Time => EntropySource { level: Entropy::Reduced, ..s },
+            _ => derived(s),
+        };
+        match e {
+            Expr::Paren(p) => self.entropy(&p.expr),
+            Expr::Group(g) => self.entropy(&g.expr),
+            Expr::Reference(r) => self.entropy(&r.expr),
+            Expr::Unary(u) => self.entropy(&u.expr),
+            Expr::Try(t) => self.entropy(&t.expr),
+            Expr::Cast(c) => self.entropy(&c.expr),
+            Expr::Index(i) => self.entropy(&i.expr).map(derived),
+            Expr::Field(f) => {
+                if let Member::Named(m) = &f.member
+                    && CLOCK_FIELDS.contains(&m.to_string().as_str())
+                    && self.is_clock(&f.base) {
+                    return Some(EntropySource { level: Entropy::Time, origin: format!("Clock::{}", m) });
+                }
+                self.entropy(&f.base)
+            }
+            Expr::Path(p) => {
+                let ident = p.path.get_ident().map(|i| i.to_string());
+                if let Some(local) = ident.and_then(|i| self.locals.get(&i)) {
+                    return Some(local.clone());
+                }
+                hash_sysvar(e)
+            }
+            Expr::Binary(b) => {
+                let operand = self.max_entropy([&*b.left, &*b.right]);
+                let mixes = matches!(b.op, BinOp::BitXor(_) | BinOp::BitAnd(_) | BinOp::BitOr(_) | BinOp::Shl(_) | BinOp::Shr(_));
+                if matches!(b.op, BinOp::Rem(_)) {
+                    operand.map(reduced)
+                } else if mixes {
+                    operand.map(derived)
+                } else if is_comparison(&b.op) {
+                    None
+                } else {
+                    operand
+                }
+            }
+            Expr::MethodCall(m) => {
+                if let Some(sysvar) = hash_sysvar(e) {
+                    return Some(sysvar);
+                }
+                let inner = self.max_entropy(std::iter::once(&*m.receiver).chain(m.args.iter()));
+                let method = m.method.to_string();
+                if BYTE_METHODS.contains(&method.as_str()) || HASH_FNS.iter().any(|h| method.contains(h)) {
+                    inner.map(derived)
+                } else {
+                    inner
+                }
+            }
+            Expr::Call(c) => {
+                if let Some(sysvar) = hash_sysvar(&c.func) {
+                    return Some(sysvar);
+                }
+                let inner = self.max_entropy(c.args.iter());
+                let is_hash = matches!(&*c.func, Expr::Path(p) if p.path.segments.iter().any(|s| HASH_FNS.iter().any(|h| s.ident.to_string().contains(h))));
+                if is_hash { inner.map(derived) } else { inner }
+            }
+            Expr::Array(a) => self.max_entropy(a.elems.iter()),
+            Expr::Macro(m) => {
+                let args = m.mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated).ok()?;
+                self.max_entropy(args.iter())
+            }
+            _ => None,
+        }
+    }
+
+    fn max_entropy<'e>(&self, exprs: impl IntoIterator<Item = &'e Expr>) -> Option<EntropySource> {
+        exprs.into_iter().filter_map(|e| self.entropy(e)).max_by_key(|s| s.level)
+    }
+
+    fn push(&mut self, source: EntropySource, sink: &str, span: Span) {
+        // `slot % 6 == 0` is one use
+        let line = span.start().line;
+        if self.uses.iter().any(|u| u.function_name == self.current_fn && u.span.start().line == line) {
+            return;
+        }
+        self.uses.push(RandomnessUse {
+            function_name: self.current_fn.clone(),
+            origin: source.origin,
+            sink: sink.to_string(),
+            expression: span_text(self.source, span),
+            span,
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for RandomnessChecker<'_> {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.enter(node.sig.ident.to_string());
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.enter(node.sig.ident.to_string());
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        visit::visit_local(self, local);
+        let pat = match &local.pat {
+            Pat::Type(t) => &*t.pat,
+            other => other,
+        };
+        let (Pat::Ident(ident), Some(init)) = (pat, &local.init) else { return };
+        let name = ident.ident.to_string();
+        if let Some(source) = self.entropy(&init.expr) {
+            self.locals.insert(name, source);
+        } else if expr_to_string(&init.expr).replace(' ', "").starts_with("Clock::") {
+            self.clocks.push(name);
+        } else {
+            self.locals.remove(&name);
+        }
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast ExprBinary) {
+        if matches!(expr.op, BinOp::Rem(_))
+            && let Some(source) = self.entropy(&expr.left)
+            && source.level == Entropy::Derived {
+            // hash[0] % 6, the remainder is the outcome
+            self.push(source, "`%`", expr.span());
+        } else if is_comparison(&expr.op)
+            && let Some(source) = self.max_entropy([&*expr.left, &*expr.right])
+            && source.level >= Entropy::Reduced {
+            // roll < 50 or slot % 2 == 0, deciding a payout
+            self.push(source, "a comparison", expr.span());
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_expr_match(&mut self, expr: &'ast ExprMatch) {
+        if let Some(source) = self.entropy(&expr.expr)
+            && source.level >= Entropy::Reduced {
+            self.push(source, "a `match`", expr.expr.span());
+        }
+        visit::visit_expr_match(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        // require!(roll < 50, ..)
+        if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+fn is_comparison(op: &BinOp) -> bool {
+    matches!(op, BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_) | BinOp::Eq(_) | BinOp::Ne(_))
+}
+
+/// `SlotHashes::from_account_info(..)`, `self.slot_hashes.data`, `recent_blockhashes`
+fn hash_sysvar(e: &Expr) -> Option<EntropySource> {
+    let text = expr_to_string(e);
+    HASH_SYSVARS
+        .iter()
+        .find(|s| text.contains(*s))
+        .map(|s| EntropySource { level: Entropy::Derived, origin: s.to_string() })
+}
+
+/// Why a source is unsafe as randomness, in the words of the finding
+fn predictability(origin: &str) -> &'static str {
+    if origin.ends_with("unix_timestamp") || origin.ends_with("epoch_start_timestamp") {
+        "the timestamp is set by the leader within a tolerance and can be nudged to pick the outcome"
+    } else if origin.starts_with("Clock::") {
+        "the slot is known before the transaction lands and the leader decides which slot includes it"
+    } else {
+        "recent hashes are public before the transaction executes, anyone can simulate it and only submit winning transactions"
+    }
+}
+
+pub fn rules_insecure_randomness(file: &SourceFile, r: &mut Report) {
+    let mut checker = RandomnessChecker::new(&file.source);
+    checker.visit_file(&file.ast);
+    for u in checker.uses {
+        r.add(Finding::new(
+            &RULE_INSECURE_RANDOMNESS,
+            Location::from_span(&file.path, u.span),
+            &u.function_name,
+            &u.origin,
+            format!(
+                "`{}` turns `{}` into an outcome with {} in `{}`: {}",
+                u.function_name, u.origin, u.sink, u.expression, predictability(&u.origin)
+            ),
+        )
+        .detail("Function", &u.function_name)
+        .detail("Source", &u.origin)
+        .detail("Used In", &u.sink));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sinks flagged in `body`, the body of a handler with a `clock` local
+    fn flagged(body: &str) -> Vec<String> {
+        let source = format!("pub fn play(ctx: Context<Play>) -> Result<()> {{ let clock = Clock::get()?; {} Ok(()) }}", body);
+        let file = SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(&source).unwrap(), source };
+        let mut checker = RandomnessChecker::new(&file.source);
+        checker.visit_file(&file.ast);
+        checker.uses.into_iter().map(|u| u.sink).collect()
+    }
+
+    #[test]
+    fn dice_rolls_are_flagged() {
+        assert_eq!(flagged("let roll = (clock.slot % 100) as u8; if roll < 50 { pay()?; }"), vec!["a comparison"]);
+        assert_eq!(flagged("require!(clock.unix_timestamp % 2 == 0, E::Lost);"), vec!["a comparison"]);
+        assert_eq!(flagged("match clock.slot % 6 { 0 => pay()?, _ => {} }"), vec!["a `match`"]);
+        assert_eq!(flagged("let h = hash(&clock.slot.to_le_bytes()).to_bytes(); let roll = h[0] % 6;"), vec!["`%`"]);
+    }
+
+    #[test]
+    fn timeouts_and_periods_are_not() {
+        assert!(flagged("let since_midnight = clock.unix_timestamp % 86_400; state.day_start = clock.unix_timestamp - since_midnight;").is_empty());
+        assert!(flagged("let epoch_offset = clock.slot % 432_000;").is_empty());
+        assert!(flagged("require!(clock.slot - bet.slot > 1000, E::TimeoutNotReached);").is_empty());
+        assert!(flagged("require!(clock.unix_timestamp >= state.unlock_at, E::Locked);").is_empty());
+    }
+}
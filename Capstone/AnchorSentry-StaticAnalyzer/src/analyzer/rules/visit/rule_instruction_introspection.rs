@@ -0,0 +1,487 @@
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::punctuated::Punctuated;
+use syn::{BinOp, Expr, ExprBinary, ExprCall, ExprMethodCall, ExprPath, FnArg, Item, Signature, Token};
+use crate::analyzer::constraints::expr_to_string;
+use crate::analyzer::rules::registry::{AnalysisContext, Rule};
+use crate::analyzer::rules::visit::rule_arbitrary_cpi::context_target;
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::state::{NormalizedAccountStruct, NormalizedProgram, SourceFile};
+
+/* ────────────────────────────────────────────────────────────────
+   H-004 — Unverified Instruction Introspection
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_INSTRUCTION_INTROSPECTION: RuleMetadata = RuleMetadata {
+    code: "H-004",
+    title: "Unverified Signature Instruction Introspection",
+    description:
+        "The program checks an Ed25519 or Secp256k1 signature by reading a sibling instruction from the \
+         instructions sysvar. The runtime only verifies signatures inside the native signature programs, so the \
+         loaded instruction proves nothing unless its program id is the signature program, its public key is the \
+         expected signer and its message is the on-chain state being settled. Reading a fixed index, or using the \
+         unchecked `load_instruction_at`, lets the caller point the check at an instruction of their choosing.",
+    severity: Severity::High,
+    recommendation:
+        "Load the instruction with `load_instruction_at_checked` at an index derived from \
+         `load_current_index_checked`, require `ix.program_id == ed25519_program::ID`, and compare the parsed \
+         public key and message with the expected signer and the account data, i.e `bet.to_slice()`.",
+    additional_links: Some(
+        "https://docs.rs/solana-program/latest/solana_program/sysvar/instructions/index.html\n\
+         https://docs.solanalabs.com/runtime/programs#ed25519-program\n\
+         https://blog.neodyme.io/posts/solana_common_pitfalls/"
+    ),
+};
+
+/// Instructions sysvar readers, the `_checked` ones verify the sysvar address themselves
+const LOAD_FNS: &[&str] = &["load_instruction_at_checked", "load_instruction_at", "get_instruction_relative"];
+/// Parsers of a loaded signature verification instruction
+const SIGNATURE_PARSERS: &[&str] = &["Ed25519InstructionSignatures", "Secp256k1InstructionSignatures"];
+/// Native signature verification programs a loaded instruction must come from
+const VERIFY_PROGRAMS: &[&str] = &["ed25519_program", "secp256k1_program"];
+/// Calls that locate the current instruction, a load relative to it isn't at a fixed index
+const CURRENT_INDEX_FNS: &[&str] = &["load_current_index_checked", "get_instruction_relative"];
+/// Account state a signed part can be bound to: `self.bet`, `ctx.accounts.signer`, `bet.to_slice()`, `x.key()`
+const STATE_IDENTS: &[&str] = &["self", "accounts"];
+const STATE_METHODS: &[&str] = &["key", "to_slice"];
+
+pub struct InstructionIntrospectionRule;
+
+impl Rule for InstructionIntrospectionRule {
+    fn id(&self) -> &'static str {
+        "instruction_introspection"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_INSTRUCTION_INTROSPECTION
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for file in ctx.files {
+            rules_instruction_introspection(file, r);
+        }
+        for s in &ctx.program.account_structs {
+            rules_unused_instructions_sysvar(s, ctx.program, ctx.files, r);
+        }
+    }
+}
+
+/// A read of the instructions sysvar or a parse of a signature instruction
+#[derive(Debug, Clone)]
+pub struct IntrospectionSite {
+    pub function_name: String,
+    pub call: String,
+    pub span: Span,
+    // `load_instruction_at_checked(0, ..)` assumes the position of the verify instruction
+    pub literal_index: Option<String>,
+}
+
+/// Identifiers and method names of one side of a comparison
+#[derive(Debug, Default)]
+struct Names {
+    idents: Vec<String>,
+    methods: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for Names {
+    fn visit_ident(&mut self, ident: &'ast proc_macro2::Ident) {
+        self.idents.push(ident.to_string());
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        self.methods.push(expr.method.to_string());
+        visit::visit_expr_method_call(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        for arg in macro_args(mac) {
+            self.visit_expr(&arg);
+        }
+    }
+}
+
+impl Names {
+    fn of(e: &Expr) -> Self {
+        let mut names = Names::default();
+        names.visit_expr(e);
+        names
+    }
+
+    /// `msg`, `signed_message` and `ix_msg` all name the message
+    fn mention(&self, parts: &[&str]) -> bool {
+        self.idents.iter().any(|ident| parts.iter().any(|p| has_words(ident, p)))
+    }
+
+    fn is_state(&self) -> bool {
+        self.idents.iter().any(|i| STATE_IDENTS.contains(&i.as_str())) || self.methods.iter().any(|m| STATE_METHODS.contains(&m.as_str()))
+    }
+}
+
+/// Both sides of `a == b`, `a != b`, `a.eq(&b)` or `require_keys_eq!(a, b)`
+struct Comparison {
+    left: Names,
+    right: Names,
+}
+
+impl Comparison {
+    fn new(left: &Expr, right: &Expr) -> Self {
+        Comparison { left: Names::of(left), right: Names::of(right) }
+    }
+
+    /// One side names a part of the signed instruction, the other one matches `other`
+    fn binds(&self, parts: &[&str], other: impl Fn(&Names) -> bool) -> bool {
+        (self.left.mention(parts) && other(&self.right)) || (self.right.mention(parts) && other(&self.left))
+    }
+}
+
+#[derive(Default)]
+struct SiteCollector {
+    sites: Vec<IntrospectionSite>,
+    current_fn: String,
+    comparisons: Vec<Comparison>,
+    // Accounts structs of the `Context<S>` params
+    contexts: Vec<String>,
+    // calls `load_current_index_checked` or `get_instruction_relative`
+    relative: bool,
+}
+
+impl<'ast> Visit<'ast> for SiteCollector {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.current_fn = node.sig.ident.to_string();
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.current_fn = node.sig.ident.to_string();
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_signature(&mut self, sig: &'ast Signature) {
+        self.contexts.extend(sig.inputs.iter().filter_map(|input| match input {
+            FnArg::Typed(t) => context_target(&t.ty),
+            FnArg::Receiver(_) => None,
+        }));
+        visit::visit_signature(self, sig);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast ExprBinary) {
+        if matches!(expr.op, BinOp::Eq(_) | BinOp::Ne(_)) {
+            self.comparisons.push(Comparison::new(&expr.left, &expr.right));
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        if (expr.method == "eq" || expr.method == "ne")
+            && let Some(arg) = expr.args.first() {
+            self.comparisons.push(Comparison::new(&expr.receiver, arg));
+        }
+        visit::visit_expr_method_call(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        // require_keys_eq!(a, b) / assert_ne!(a, b), other macros like require!(a == b) are walked for their args
+        let name = mac.path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default();
+        let args = macro_args(mac);
+        if (name.ends_with("_eq") || name.ends_with("_ne") || name.ends_with("_neq"))
+            && let [a, b, ..] = &args[..] {
+            self.comparisons.push(Comparison::new(a, b));
+        }
+        for arg in &args {
+            self.visit_expr(arg);
+        }
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_expr_call(&mut self, expr: &'ast ExprCall) {
+        if let Expr::Path(p) = &*expr.func
+            && let Some(last) = p.path.segments.last()
+            && CURRENT_INDEX_FNS.contains(&last.ident.to_string().as_str()) {
+            self.relative = true;
+        }
+        if let Expr::Path(p) = &*expr.func
+            && let Some(last) = p.path.segments.last()
+            && LOAD_FNS.contains(&last.ident.to_string().as_str()) {
+            let literal_index = match expr.args.first() {
+                Some(Expr::Lit(l)) => Some(l.to_token_stream().to_string()),
+                _ => None,
+            };
+            self.sites.push(IntrospectionSite {
+                function_name: self.current_fn.clone(),
+                call: last.ident.to_string(),
+                span: expr.span(),
+                literal_index,
+            });
+        }
+        visit::visit_expr_call(self, expr);
+    }
+
+    fn visit_expr_path(&mut self, expr: &'ast ExprPath) {
+        // Ed25519InstructionSignatures::unpack(&ix.data)
+        if let Some(parser) = expr.path.segments.iter().find(|s| SIGNATURE_PARSERS.contains(&s.ident.to_string().as_str())) {
+            self.sites.push(IntrospectionSite {
+                function_name: self.current_fn.clone(),
+                call: parser.ident.to_string(),
+                span: expr.span(),
+                literal_index: None,
+            });
+        }
+        visit::visit_expr_path(self, expr);
+    }
+}
+
+/// Comma separated expressions in a macro body, none when it isn't one
+fn macro_args(mac: &syn::Macro) -> Vec<Expr> {
+    mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+        .map(|args| args.into_iter().collect())
+        .unwrap_or_default()
+}
+
+/// `part` as whole `_` separated words of `ident`: `signed_message` has `message`, `ED25519_PROGRAM_ID` has `ed25519_program`
+fn has_words(ident: &str, part: &str) -> bool {
+    let ident = if ident.chars().any(|c| c.is_ascii_lowercase()) { ident.to_string() } else { ident.to_ascii_lowercase() };
+    let words: Vec<&str> = ident.split('_').collect();
+    let part: Vec<&str> = part.split('_').collect();
+    words.windows(part.len()).any(|w| w == part.as_slice())
+}
+
+/// A free fn, or every method of an impl: a verification split over helper methods is checked as one
+struct Unit {
+    name: String,
+    sites: Vec<IntrospectionSite>,
+    comparisons: Vec<Comparison>,
+    contexts: Vec<String>,
+    relative: bool,
+}
+
+impl Unit {
+    /// Compares a part of the signed instruction (`message`, `pubkey`) with account state
+    fn bound_to_state(&self, parts: &[&str]) -> bool {
+        self.comparisons.iter().any(|c| c.binds(parts, Names::is_state))
+    }
+
+    /// `ix.program_id == ed25519_program::ID`
+    fn checks_program_id(&self) -> bool {
+        self.comparisons.iter().any(|c| c.binds(&["program_id"], |other| other.mention(VERIFY_PROGRAMS)))
+    }
+}
+
+fn units(items: &[Item]) -> Vec<Unit> {
+    let mut out = Vec::new();
+    for item in items {
+        let mut collector = SiteCollector::default();
+        let name = match item {
+            Item::Fn(f) => {
+                collector.visit_item_fn(f);
+                f.sig.ident.to_string()
+            }
+            Item::Impl(i) => {
+                collector.visit_item_impl(i);
+                i.self_ty.to_token_stream().to_string().replace(' ', "")
+            }
+            Item::Mod(m) => {
+                if let Some((_, items)) = &m.content {
+                    out.extend(units(items));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        out.push(Unit {
+            name,
+            sites: collector.sites,
+            comparisons: collector.comparisons,
+            contexts: collector.contexts,
+            relative: collector.relative,
+        });
+    }
+    out
+}
+
+pub fn rules_instruction_introspection(file: &SourceFile, r: &mut Report) {
+    for unit in units(&file.ast.items) {
+        let sites = &unit.sites;
+        let Some(first) = sites.first() else { continue };
+
+        let mut problems: Vec<(&IntrospectionSite, String)> = Vec::new();
+        if !unit.checks_program_id() {
+            problems.push((first, format!(
+                "`{}` reads an instruction with `{}` but never compares its `program_id` with `ed25519_program::ID`, any program can stand in for the signature check",
+                first.function_name, first.call
+            )));
+        }
+        if !unit.bound_to_state(&["message", "msg"]) {
+            problems.push((first, format!(
+                "`{}` never compares the signed message with account data such as `bet.to_slice()`, a valid signature over any other message is accepted",
+                first.function_name
+            )));
+        }
+        if !unit.bound_to_state(&["pubkey", "public_key"]) {
+            problems.push((first, format!(
+                "`{}` never compares the signing public key with an expected account, a signature by any key is accepted",
+                first.function_name
+            )));
+        }
+        for site in sites {
+            if site.call == "load_instruction_at" {
+                problems.push((site, format!(
+                    "`{}` uses `load_instruction_at`, which doesn't check it is reading the instructions sysvar, use `load_instruction_at_checked`",
+                    site.function_name
+                )));
+            }
+            if let Some(index) = &site.literal_index
+                && !unit.relative {
+                problems.push((site, format!(
+                    "`{}` loads the instruction at the fixed index {}, relative to nothing; derive it from `load_current_index_checked`",
+                    site.function_name, index
+                )));
+            }
+        }
+
+        for (site, message) in problems {
+            r.add(Finding::new(
+                &RULE_INSTRUCTION_INTROSPECTION,
+                Location::from_span(&file.path, site.span),
+                &unit.name,
+                &site.function_name,
+                message,
+            )
+            .detail("Function", &site.function_name)
+            .detail("Call", &site.call));
+        }
+    }
+}
+
+/// An instructions sysvar account declared on a context whose code never reads it
+pub fn rules_unused_instructions_sysvar(s: &NormalizedAccountStruct, program: &NormalizedProgram, files: &[SourceFile], r: &mut Report) {
+    let Some(field) = s.fields.iter().find(|f| {
+        f.constraints
+            .address
+            .as_ref()
+            .and_then(|a| a.expr.as_ref())
+            .is_some_and(|e| expr_to_string(e).replace(' ', "").contains("instructions::ID"))
+    }) else {
+        return;
+    };
+
+    let read = program.impls_of(&s.name).flat_map(|i| &i.methods).any(|m| {
+        let mut collector = SiteCollector::default();
+        collector.visit_impl_item_fn(m);
+        !collector.sites.is_empty()
+    });
+    // handlers taking `Context<S>` may verify inline
+    let read_in_handler = files
+        .iter()
+        .flat_map(|f| units(&f.ast.items))
+        .any(|u| u.contexts.contains(&s.name) && !u.sites.is_empty());
+    if read || read_in_handler {
+        return;
+    }
+
+    r.add(Finding::new(
+        &RULE_INSTRUCTION_INTROSPECTION,
+        Location::new(&s.file, field.line, field.column),
+        &s.name,
+        &field.name,
+        format!(
+            "`{}` takes the instructions sysvar as `{}` but no code of `{}` reads it, the signature it is meant to verify is never checked",
+            s.name, field.name, s.name
+        ),
+    )
+    .detail("Account", &field.name)
+    .detail("Context", &s.name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+
+    fn analyze(source: &str) -> Vec<String> {
+        let file = SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(source).unwrap(), source: source.to_string() };
+        let files = [file];
+        let program = normalize_program(&files);
+        let mut r = Report::default();
+        rules_instruction_introspection(&files[0], &mut r);
+        for s in &program.account_structs {
+            rules_unused_instructions_sysvar(s, &program, &files, &mut r);
+        }
+        r.findings.into_iter().map(|f| f.message).collect()
+    }
+
+    const VERIFIED: &str = r#"
+        pub fn resolve(ctx: Context<Resolve>, sig: Vec<u8>) -> Result<()> {
+            let index = load_current_index_checked(&ctx.accounts.ixs)?;
+            let ix = load_instruction_at_checked(index as usize - 1, &ctx.accounts.ixs)?;
+            require_keys_eq!(ix.program_id, ed25519_program::ID, E::Program);
+            let signatures = Ed25519InstructionSignatures::unpack(&ix.data)?.0;
+            let signature = &signatures[0];
+            require!(signature.public_key.unwrap() == ctx.accounts.house.key().to_bytes(), E::Key);
+            require!(signature.message.as_ref().unwrap().eq(&ctx.accounts.bet.to_slice()), E::Message);
+            Ok(())
+        }
+    "#;
+
+    #[test]
+    fn fully_verified_signature() {
+        assert!(analyze(VERIFIED).is_empty());
+    }
+
+    #[test]
+    fn unrelated_comparisons_in_one_statement_do_not_bind() {
+        let source = r#"
+            pub fn resolve(ctx: Context<Resolve>) -> Result<()> {
+                let index = load_current_index_checked(&ctx.accounts.ixs)?;
+                let ix = load_instruction_at_checked(index as usize - 1, &ctx.accounts.ixs)?;
+                require!(ix.program_id == ed25519_program::ID, E::Program);
+                let signature = &Ed25519InstructionSignatures::unpack(&ix.data)?.0[0];
+                let msg = signature.message.as_ref().unwrap();
+                let pubkey = signature.public_key.unwrap();
+                require!(msg.len() == 32 && ctx.accounts.bet.key() != Pubkey::default(), E::Message);
+                require!(pubkey.len() == 32, E::Key);
+                Ok(())
+            }
+        "#;
+        let messages = analyze(source);
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages[0].contains("signed message"));
+        assert!(messages[1].contains("signing public key"));
+    }
+
+    #[test]
+    fn program_id_and_fixed_index() {
+        let source = r#"
+            pub fn resolve(ctx: Context<Resolve>) -> Result<()> {
+                let ix = load_instruction_at_checked(0, &ctx.accounts.ixs)?;
+                require!(ix.data.len() > 0 && ix.program_id != Pubkey::default(), E::Program);
+                let signature = &Ed25519InstructionSignatures::unpack(&ix.data)?.0[0];
+                require_keys_eq!(signature.public_key.unwrap(), ctx.accounts.house.key());
+                require!(signature.message.unwrap() == self.bet.to_slice(), E::Message);
+                Ok(())
+            }
+        "#;
+        let messages = analyze(source);
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages[0].contains("program_id"));
+        assert!(messages[1].contains("fixed index 0"));
+    }
+
+    #[test]
+    fn sysvar_read_by_a_handler_with_lifetimes() {
+        let accounts = r#"
+            #[derive(Accounts)]
+            pub struct Resolve<'info> {
+                #[account(address = solana_program::sysvar::instructions::ID)]
+                pub ixs: UncheckedAccount<'info>,
+            }
+        "#;
+        let handler = VERIFIED.replace("Context<Resolve>", "Context<'_, '_, '_, 'info, Resolve<'info>>");
+        assert!(analyze(&format!("{}{}", accounts, handler)).is_empty());
+
+        let messages = analyze(accounts);
+        assert_eq!(messages.len(), 1, "{:?}", messages);
+        assert!(messages[0].contains("no code of `Resolve` reads it"));
+    }
+}
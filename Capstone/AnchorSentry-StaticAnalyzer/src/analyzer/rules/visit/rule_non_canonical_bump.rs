@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprAssign, ExprCall, ExprStruct, FnArg, Local, Member, Pat, Signature};
+use crate::analyzer::constraints::{expr_to_string, Bump};
+use crate::analyzer::rules::registry::{AnalysisContext, Rule};
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::state::{AccountInstructions, NormalizedAccountStruct, SourceFile};
+
+/* ────────────────────────────────────────────────────────────────
+   M-005 — Non-Canonical or User-Supplied Bump
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_NON_CANONICAL_BUMP: RuleMetadata = RuleMetadata {
+    code: "M-005",
+    title: "Non-Canonical or User-Supplied Bump",
+    description:
+        "The same seeds yield up to 255 valid program addresses, one per bump. Only the canonical bump (the \
+         one `find_program_address` returns) is unique. When the bump comes from instruction data, is hardcoded, \
+         or is fed to `create_program_address` directly, a caller can pick another bump and get a different, \
+         equally valid PDA for the same seeds, creating duplicate vaults, config or user accounts that the \
+         program treats as distinct.",
+    severity: Severity::Medium,
+    recommendation:
+        "Use a bare `bump` constraint when the account is created and store `ctx.bumps.<account>` in the \
+         account. Later instructions should use `bump = <account>.bump`. Never accept the bump as an \
+         instruction argument, and prefer `find_program_address` over `create_program_address` unless the bump \
+         was stored canonically.",
+    additional_links: Some(
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/7-bump-seed-canonicalization\n\
+         https://solana.com/developers/courses/program-security/bump-seed-canonicalization\n\
+         https://www.anchor-lang.com/docs/references/account-constraints#accountseeds-bump"
+    ),
+};
+
+pub struct NonCanonicalBumpRule;
+
+impl Rule for NonCanonicalBumpRule {
+    fn id(&self) -> &'static str {
+        "non_canonical_bump"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_NON_CANONICAL_BUMP
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for s in &ctx.program.account_structs {
+            rules_user_supplied_bump(s, &ctx.program.accounts_with_instructions, r);
+        }
+        for file in ctx.files {
+            rules_non_canonical_bump_in_fns(file, r);
+        }
+    }
+}
+
+/// `bump` or `<account>_bump`, not `bump_fee`
+fn is_bump_name(name: &str) -> bool {
+    name == "bump" || name.ends_with("_bump")
+}
+
+/// `bump = <arg>`, `seeds = [.., &[<arg>]]` or a bump-like `#[instruction]` arg on an Accounts struct
+pub fn rules_user_supplied_bump(s: &NormalizedAccountStruct, instructions: &[AccountInstructions], r: &mut Report) {
+    let Some(ix) = instructions.iter().find(|i| i.ctx_name == s.name) else { return };
+    let mut reported_args: Vec<&str> = Vec::new();
+
+    for field in &s.fields {
+        let c = &field.constraints;
+        let bump_expr = match &c.bump {
+            Some(Bump::Provided(e)) => Some(&**e),
+            _ => None,
+        };
+        let seed_exprs = c.seeds.iter().flatten();
+
+        for (expr, place) in bump_expr.into_iter().map(|e| (e, "bump")).chain(seed_exprs.map(|e| (e, "seeds"))) {
+            let mut idents = PathIdents::default();
+            idents.visit_expr(expr);
+            for arg in &ix.args {
+                // a seed like `seed.to_le_bytes()` is fine, only bump-like args in seeds are a bump
+                if !idents.0.contains(&arg.name) || (place == "seeds" && !is_bump_name(&arg.name)) {
+                    continue;
+                }
+                reported_args.push(&arg.name);
+                r.add(Finding::new(
+                    &RULE_NON_CANONICAL_BUMP,
+                    Location::from_span(&s.file, expr.span()),
+                    &s.name,
+                    &field.name,
+                    format!(
+                        "The bump of `{}` in `{}` comes from the instruction argument `{}`, a caller can pick a non-canonical bump",
+                        field.name, s.name, arg.name
+                    ),
+                )
+                .detail("Account", &field.name)
+                .detail("Context", &s.name)
+                .detail("Argument", format!("{}: {}", arg.name, arg.ty))
+                .label(
+                    Location::new(&ix.file, ix.line, ix.column),
+                    format!("`{}` is declared as an instruction argument here", arg.name),
+                ));
+            }
+        }
+    }
+
+    // accepted but not used in a constraint, the handler probably uses it
+    for arg in ix.args.iter().filter(|a| is_bump_name(&a.name) && !reported_args.contains(&a.name.as_str())) {
+        r.add(Finding::new(
+            &RULE_NON_CANONICAL_BUMP,
+            Location::new(&ix.file, ix.line, ix.column),
+            &s.name,
+            &arg.name,
+            format!(
+                "`{}` takes the bump `{}: {}` as an instruction argument, use the canonical `ctx.bumps` instead",
+                s.name, arg.name, arg.ty
+            ),
+        )
+        .detail("Context", &s.name)
+        .detail("Argument", format!("{}: {}", arg.name, arg.ty)));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BumpUse {
+    pub function_name: String,
+    // the stored bump field or `create_program_address`
+    pub subject: String,
+    pub span: Span,
+    pub message: String,
+}
+
+/// Looks at handler and impl method bodies for `create_program_address` and stored bumps
+#[derive(Default)]
+pub struct BumpChecker {
+    pub uses: Vec<BumpUse>,
+    pub current_fn: String,
+    // parameters of the current fn that come from the caller, `ctx`/`bumps` excluded
+    pub params: Vec<String>,
+    // locals of the current fn and whether their value traces to a canonical bump
+    pub locals: HashMap<String, bool>,
+}
+
+impl BumpChecker {
+    fn enter(&mut self, sig: &Signature) {
+        self.current_fn = sig.ident.to_string();
+        self.locals.clear();
+        self.params = sig
+            .inputs
+            .iter()
+            .filter_map(|input| match input {
+                FnArg::Typed(t) => {
+                    let ty = t.ty.to_token_stream().to_string();
+                    if ty.contains("Context") || ty.contains("Bumps") {
+                        return None;
+                    }
+                    match &*t.pat {
+                        Pat::Ident(p) => Some(p.ident.to_string()),
+                        _ => None,
+                    }
+                }
+                FnArg::Receiver(_) => None,
+            })
+            .collect();
+    }
+
+    /// A caller parameter used as a plain variable in `exprs`, `self.state.bump` is not the `bump` param
+    fn caller_param<'e>(&self, exprs: impl IntoIterator<Item = &'e Expr>, bump_only: bool) -> Option<String> {
+        let mut idents = PathIdents::default();
+        for e in exprs {
+            idents.visit_expr(e);
+        }
+        self.params
+            .iter()
+            .find(|p| idents.0.contains(p) && (!bump_only || is_bump_name(p)))
+            .cloned()
+    }
+
+    fn push(&mut self, span: Span, subject: &str, message: String) {
+        self.uses.push(BumpUse { function_name: self.current_fn.clone(), subject: subject.to_string(), span, message });
+    }
+
+    /// Whether `expr` reads `ctx.bumps.*`, a `*.bump` stored on an account, `find_program_address`
+    /// or a local holding one of those
+    fn canonical(&self, expr: &Expr) -> bool {
+        let mut trace = CanonicalBump { locals: &self.locals, found: false };
+        trace.visit_expr(expr);
+        trace.found
+    }
+
+    /// A value written into a bump field must come from `ctx.bumps` / `find_program_address`
+    fn check_stored_bump(&mut self, target: &str, value: &Expr) {
+        let text = expr_to_string(value);
+        if let Some(param) = self.caller_param([value], false) {
+            self.push(
+                value.span(),
+                target,
+                format!("`{}` stores the caller supplied `{}` as `{}` instead of `ctx.bumps`", self.current_fn, param, target),
+            );
+        } else if matches!(value, Expr::Lit(_)) {
+            self.push(
+                value.span(),
+                target,
+                format!("`{}` stores a hardcoded bump `{}` as `{}` instead of `ctx.bumps`", self.current_fn, text, target),
+            );
+        } else if !self.canonical(value) {
+            self.push(
+                value.span(),
+                target,
+                format!(
+                    "`{}` stores `{}` as `{}`, which does not come from `ctx.bumps`, a stored bump or `find_program_address`",
+                    self.current_fn, text, target
+                ),
+            );
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for BumpChecker {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.enter(&node.sig);
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.enter(&node.sig);
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_expr_call(&mut self, expr: &'ast ExprCall) {
+        if let Expr::Path(p) = &*expr.func
+            && p.path.segments.last().is_some_and(|s| s.ident == "create_program_address") {
+            // caller supplied seeds are normal, a caller supplied bump is not
+            if let Some(param) = self.caller_param(&expr.args, true) {
+                self.push(
+                    expr.span(),
+                    "create_program_address",
+                    format!("`{}` derives a PDA with `create_program_address` from the caller supplied `{}`", self.current_fn, param),
+                );
+            } else if !expr.args.iter().any(|a| self.canonical(a)) {
+                self.push(
+                    expr.span(),
+                    "create_program_address",
+                    format!("`{}` calls `create_program_address` without a stored canonical bump, use `find_program_address`", self.current_fn),
+                );
+            }
+        }
+        visit::visit_expr_call(self, expr);
+    }
+
+    fn visit_local(&mut self, local: &'ast Local) {
+        // let bump = ctx.bumps.vault; let (pda, bump) = Pubkey::find_program_address(..);
+        if let Some(init) = &local.init {
+            let canonical = self.canonical(&init.expr);
+            let mut names = PatIdents::default();
+            names.visit_pat(&local.pat);
+            for name in names.0 {
+                self.locals.insert(name, canonical);
+            }
+        }
+        visit::visit_local(self, local);
+    }
+
+    fn visit_expr_assign(&mut self, expr: &'ast ExprAssign) {
+        // self.state.bump = ...
+        if let Expr::Field(f) = &*expr.left
+            && let Member::Named(name) = &f.member
+            && is_bump_name(&name.to_string()) {
+            self.check_stored_bump(&name.to_string(), &expr.right);
+        }
+        visit::visit_expr_assign(self, expr);
+    }
+
+    fn visit_expr_struct(&mut self, expr: &'ast ExprStruct) {
+        // State { bump: ..., .. }
+        for field in &expr.fields {
+            if let Member::Named(name) = &field.member
+                && is_bump_name(&name.to_string()) {
+                self.check_stored_bump(&name.to_string(), &field.expr);
+            }
+        }
+        visit::visit_expr_struct(self, expr);
+    }
+}
+
+/// Single identifier paths, i.e the variables an expression reads
+#[derive(Default)]
+struct PathIdents(Vec<String>);
+
+impl<'ast> Visit<'ast> for PathIdents {
+    fn visit_expr_path(&mut self, expr: &'ast syn::ExprPath) {
+        if let Some(ident) = expr.path.get_ident() {
+            self.0.push(ident.to_string());
+        }
+    }
+}
+
+/// Idents bound by a pattern, `(pda, bump)` -> `pda`, `bump`
+#[derive(Default)]
+struct PatIdents(Vec<String>);
+
+impl<'ast> Visit<'ast> for PatIdents {
+    fn visit_pat_ident(&mut self, pat: &'ast syn::PatIdent) {
+        self.0.push(pat.ident.to_string());
+        visit::visit_pat_ident(self, pat);
+    }
+}
+
+/// Finds a canonical bump source in an expression
+struct CanonicalBump<'a> {
+    locals: &'a HashMap<String, bool>,
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for CanonicalBump<'_> {
+    fn visit_expr_field(&mut self, expr: &'ast syn::ExprField) {
+        let on_bumps = match &*expr.base {
+            Expr::Path(p) => p.path.is_ident("bumps"),
+            Expr::Field(f) => matches!(&f.member, Member::Named(m) if m == "bumps"),
+            _ => false,
+        };
+        // ctx.bumps.vault, or self.vault_state.bump / ctx.accounts.config.vault_bump stored at init
+        if on_bumps || matches!(&expr.member, Member::Named(m) if is_bump_name(&m.to_string())) {
+            self.found = true;
+            return;
+        }
+        visit::visit_expr_field(self, expr);
+    }
+
+    fn visit_expr_path(&mut self, expr: &'ast syn::ExprPath) {
+        if let Some(ident) = expr.path.get_ident()
+            && self.locals.get(&ident.to_string()).copied().unwrap_or(false) {
+            self.found = true;
+        }
+    }
+
+    fn visit_expr_call(&mut self, expr: &'ast ExprCall) {
+        if let Expr::Path(p) = &*expr.func
+            && p.path.segments.last().is_some_and(|s| s.ident == "find_program_address") {
+            self.found = true;
+            return;
+        }
+        visit::visit_expr_call(self, expr);
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast syn::ExprMethodCall) {
+        // ctx.bumps.get("vault") on older Anchor versions
+        if let Expr::Field(f) = &*expr.receiver
+            && matches!(&f.member, Member::Named(m) if m == "bumps") {
+            self.found = true;
+            return;
+        }
+        visit::visit_expr_method_call(self, expr);
+    }
+}
+
+pub fn rules_non_canonical_bump_in_fns(file: &SourceFile, r: &mut Report) {
+    let mut checker = BumpChecker::default();
+    checker.visit_file(&file.ast);
+    for u in checker.uses {
+        r.add(Finding::new(
+            &RULE_NON_CANONICAL_BUMP,
+            Location::from_span(&file.path, u.span),
+            &u.function_name,
+            &u.subject,
+            u.message,
+        )
+        .detail("Function", &u.function_name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+
+    fn file(source: &str) -> SourceFile {
+        SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(source).unwrap(), source: source.to_string() }
+    }
+
+    /// Messages for the handler `body` of `fn handler(ctx: Context<Init>, data: Vec<u8>, bump_fee: u64)`
+    fn flagged(body: &str) -> Vec<String> {
+        let f = file(&format!("pub fn handler(ctx: Context<Init>, data: Vec<u8>, bump_fee: u64) -> Result<()> {{ {} Ok(()) }}", body));
+        let mut r = Report::default();
+        rules_non_canonical_bump_in_fns(&f, &mut r);
+        r.findings.into_iter().map(|f| f.message).collect()
+    }
+
+    #[test]
+    fn canonical_sources_are_not_flagged() {
+        assert!(flagged("ctx.accounts.vault_state.bump = ctx.bumps.vault_state;").is_empty());
+        assert!(flagged("let (_, bump) = Pubkey::find_program_address(&[b\"vault\"], &ID); ctx.accounts.state.bump = bump;").is_empty());
+        assert!(flagged("let bump = ctx.accounts.config.vault_bump; ctx.accounts.state.vault_bump = bump;").is_empty());
+        assert!(flagged("let pda = Pubkey::create_program_address(&[b\"vault\", &[ctx.accounts.state.bump]], &ID)?;").is_empty());
+    }
+
+    #[test]
+    fn untraced_bumps_are_flagged() {
+        let stored = flagged("let bump = data[0]; ctx.accounts.state.bump = bump;");
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].contains("does not come from `ctx.bumps`"));
+        assert_eq!(flagged("let bump = data[0]; let pda = Pubkey::create_program_address(&[b\"vault\", &[bump]], &ID)?;").len(), 1);
+        assert_eq!(flagged("ctx.accounts.state.bump = 254;").len(), 1);
+    }
+
+    #[test]
+    fn bump_names_match_whole_words() {
+        // bump_fee is a fee, not a bump
+        assert!(flagged("ctx.accounts.state.bump_fee = bump_fee;").is_empty());
+        assert!(flagged("ctx.accounts.state.bumpy = data[0];").is_empty());
+
+        let files = [file(r#"
+            #[derive(Accounts)]
+            #[instruction(bump_fee: u64, vault_bump: u8)]
+            pub struct Init<'info> {
+                #[account(mut, seeds = [b"vault", bump_fee.to_le_bytes().as_ref()], bump)]
+                pub vault: SystemAccount<'info>,
+            }
+        "#)];
+        let program = normalize_program(&files);
+        let mut r = Report::default();
+        for s in &program.account_structs {
+            rules_user_supplied_bump(s, &program.accounts_with_instructions, &mut r);
+        }
+        let subjects: Vec<String> = r.findings.into_iter().map(|f| f.subject).collect();
+        assert_eq!(subjects, vec!["vault_bump"]);
+    }
+}
@@ -0,0 +1,339 @@
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::punctuated::Punctuated;
+use syn::{BinOp, Expr, ExprBinary, ExprCall, ExprMethodCall, Member, Token};
+use crate::analyzer::constraints::expr_to_string;
+use crate::analyzer::rules::is_unchecked_account;
+use crate::analyzer::rules::registry::{AnalysisContext, Rule};
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::state::{NormalizedAccountStruct, NormalizedProgram};
+
+/* ────────────────────────────────────────────────────────────────
+   M-003 — Raw Account Data Read Without Owner Check
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_UNCHECKED_ACCOUNT_DATA_READ: RuleMetadata = RuleMetadata {
+    code: "M-003",
+    title: "Raw Account Data Read Without Owner Check",
+    description:
+        "The data of an AccountInfo or UncheckedAccount is borrowed or deserialized by hand, but nothing \
+         checks which program owns the account. Anyone can create an account owned by their own program with \
+         bytes laid out exactly like the expected type, so the deserialized values (authorities, amounts, \
+         flags) are attacker controlled. Anchor's typed accounts perform this owner check automatically, \
+         raw accounts do not.",
+    severity: Severity::Medium,
+    recommendation:
+        "Add an `owner = <program id>` constraint to the account, compare `account.owner` against the \
+         expected program before reading its data, or use a typed account such as Account<'info, T> or \
+         InterfaceAccount<'info, T> so Anchor validates the owner and discriminator.",
+    additional_links: Some(
+        "https://www.anchor-lang.com/docs/references/account-constraints#accountowner--expr\n\
+         https://solana.com/developers/courses/program-security/owner-checks\n\
+         https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/2-owner-checks"
+    ),
+};
+
+/// Methods that hand out the raw bytes of an AccountInfo
+const BORROW_METHODS: &[&str] = &["try_borrow_data", "try_borrow_mut_data"];
+/// Associated fns that turn bytes into a type, `T::try_from_slice(&data)`
+const DESERIALIZE_FNS: &[&str] = &["try_from_slice", "try_deserialize", "try_deserialize_unchecked", "deserialize"];
+
+pub struct UncheckedDataReadRule;
+
+impl Rule for UncheckedDataReadRule {
+    fn id(&self) -> &'static str {
+        "unchecked_account_data_read"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_UNCHECKED_ACCOUNT_DATA_READ
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for s in &ctx.program.account_structs {
+            rules_unchecked_data_read(s, ctx.program, r);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DataRead {
+    pub field: String,
+    pub method: String,
+    // the borrow or deserialize call
+    pub span: Span,
+}
+
+/// `self.<field>.owner` compared in `==`/`!=`, `require_keys_eq!` or `require!`
+#[derive(Debug, Clone)]
+pub struct OwnerCheck {
+    pub field: String,
+    pub method: String,
+    pub span: Span,
+}
+
+/// Walks the methods of an Accounts impl, recording data reads and owner checks of `self.<field>`
+#[derive(Default)]
+pub struct DataReadChecker {
+    pub reads: Vec<DataRead>,
+    pub owner_checks: Vec<OwnerCheck>,
+    pub current_fn: String,
+}
+
+impl<'ast> Visit<'ast> for DataReadChecker {
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.current_fn = node.sig.ident.to_string();
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        let method = expr.method.to_string();
+        if BORROW_METHODS.contains(&method.as_str()) {
+            if let Some(field) = account_field(&expr.receiver) {
+                self.push(field, expr.span());
+            }
+        } else if (method == "borrow" || method == "borrow_mut")
+            // self.account.data.borrow()
+            && let Expr::Field(f) = &*expr.receiver
+            && matches!(&f.member, Member::Named(m) if m == "data")
+            && let Some(field) = account_field(&f.base) {
+            self.push(field, expr.span());
+        }
+        visit::visit_expr_method_call(self, expr);
+    }
+
+    fn visit_expr_call(&mut self, expr: &'ast ExprCall) {
+        if let Expr::Path(p) = &*expr.func
+            && let Some(last) = p.path.segments.last()
+            && DESERIALIZE_FNS.contains(&last.ident.to_string().as_str()) {
+            let mut refs = FieldRefs::default();
+            for arg in &expr.args {
+                refs.visit_expr(arg);
+            }
+            for field in refs.fields {
+                self.push(field, expr.span());
+            }
+        }
+        visit::visit_expr_call(self, expr);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast ExprBinary) {
+        // self.account.owner == &ID, *self.account.owner != crate::ID
+        if matches!(expr.op, BinOp::Eq(_) | BinOp::Ne(_)) {
+            for operand in [&*expr.left, &*expr.right] {
+                self.owner_operand(operand);
+            }
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        // require!/require_keys_eq!/msg! bodies are plain tokens to syn, most are comma separated exprs
+        if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            let name = mac.path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default();
+            match name.as_str() {
+                // require_keys_eq!(*self.account.owner, ID)
+                "require_keys_eq" => args.iter().take(2).for_each(|a| self.owner_operand(a)),
+                // require!(self.account.is_owned_by(&ID), E)
+                "require" => {
+                    if let Some(Expr::MethodCall(m)) = args.first()
+                        && m.method == "is_owned_by"
+                        && let Some(field) = account_field(&m.receiver) {
+                        self.owner_check(field, m.span());
+                    }
+                }
+                _ => {}
+            }
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+impl DataReadChecker {
+    fn push(&mut self, field: String, span: Span) {
+        // `T::try_from_slice(&self.a.try_borrow_data()?)` is one read
+        if self.reads.iter().any(|r| r.field == field && r.span.start().line == span.start().line) {
+            return;
+        }
+        self.reads.push(DataRead { field, method: self.current_fn.clone(), span });
+    }
+
+    /// Records `operand` when it is the owner of an account, `self.a.owner` or `*self.a.to_account_info().owner`
+    fn owner_operand(&mut self, operand: &Expr) {
+        let operand = match operand {
+            Expr::Reference(r) => &*r.expr,
+            Expr::Unary(u) => &*u.expr,
+            Expr::Paren(p) => &*p.expr,
+            other => other,
+        };
+        if let Expr::Field(f) = operand
+            && matches!(&f.member, Member::Named(m) if m == "owner")
+            && let Some(field) = account_field(&f.base) {
+            self.owner_check(field, f.span());
+        }
+    }
+
+    fn owner_check(&mut self, field: String, span: Span) {
+        self.owner_checks.push(OwnerCheck { field, method: self.current_fn.clone(), span });
+    }
+
+    /// Whether the owner of the account `read` borrows was compared earlier in the same fn
+    pub fn checked_before(&self, read: &DataRead) -> bool {
+        let at = read.span.start();
+        self.owner_checks.iter().any(|c| {
+            let start = c.span.start();
+            c.field == read.field && c.method == read.method && (start.line, start.column) < (at.line, at.column)
+        })
+    }
+}
+
+/// Every `self.<field>` / `ctx.accounts.<field>` mentioned in an expression
+#[derive(Default)]
+struct FieldRefs {
+    fields: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for FieldRefs {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        if let Some(field) = account_field(expr) {
+            if !self.fields.contains(&field) {
+                self.fields.push(field);
+            }
+            return;
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+/// `self.asset`, `ctx.accounts.asset`, `&self.asset.to_account_info()` -> `asset`
+pub fn account_field(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Reference(r) => account_field(&r.expr),
+        Expr::Paren(p) => account_field(&p.expr),
+        Expr::Unary(u) => account_field(&u.expr),
+        Expr::MethodCall(m) if m.method == "to_account_info" || m.method == "as_ref" => account_field(&m.receiver),
+        Expr::Field(f) => {
+            let Member::Named(name) = &f.member else { return None };
+            let on_accounts = match &*f.base {
+                Expr::Path(p) => p.path.is_ident("self"),
+                Expr::Field(base) => matches!(&base.member, Member::Named(m) if m == "accounts"),
+                _ => false,
+            };
+            on_accounts.then(|| name.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Whether the Accounts struct itself pins the owner of `field`
+fn owner_constrained(s: &NormalizedAccountStruct, field: &str) -> bool {
+    let owner_ref = format!("{}.owner", field);
+    let owned_by = format!("{}.is_owned_by", field);
+    s.fields.iter().any(|f| {
+        let c = &f.constraints;
+        (f.name == field && (c.owner.is_some() || c.address.is_some()))
+            || c.constraint.iter().filter_map(|ce| ce.expr.as_ref()).any(|e| {
+                let text = expr_to_string(e).replace(' ', "");
+                text.contains(&owner_ref) || text.contains(&owned_by)
+            })
+    })
+}
+
+pub fn rules_unchecked_data_read(s: &NormalizedAccountStruct, program: &NormalizedProgram, r: &mut Report) {
+    for imp in program.impls_of(&s.name) {
+        let mut checker = DataReadChecker::default();
+        for method in &imp.methods {
+            checker.visit_impl_item_fn(method);
+        }
+
+        for read in &checker.reads {
+            let Some(field) = s.fields.iter().find(|f| f.name == read.field) else { continue };
+            if !is_unchecked_account(field)
+                || checker.checked_before(read)
+                || owner_constrained(s, &field.name) {
+                continue;
+            }
+
+            r.add(Finding::new(
+                &RULE_UNCHECKED_ACCOUNT_DATA_READ,
+                Location::from_span(&imp.file, read.span),
+                &s.name,
+                &field.name,
+                format!(
+                    "`{}` reads the data of `{}` in `{}` without checking which program owns it",
+                    read.method, field.name, s.name
+                ),
+            )
+            .detail("Account", &field.name)
+            .detail("Context", &s.name)
+            .detail("Method", &read.method)
+            .label(
+                Location::new(&s.file, field.line, field.column),
+                format!("`{}` is a raw `{}` with no owner constraint", field.name, field.base_type),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+    use crate::state::SourceFile;
+
+    /// Accounts struct `Read` with `oracle` declared as `oracle_decl` and `body` as its `read` method
+    fn flagged(oracle_decl: &str, body: &str) -> Vec<String> {
+        let source = format!(
+            r#"
+            #[derive(Accounts)]
+            pub struct Read<'info> {{
+                {}
+                pub oracle: UncheckedAccount<'info>,
+            }}
+            impl<'info> Read<'info> {{
+                pub fn read(&mut self) -> Result<()> {{ {} Ok(()) }}
+            }}
+            "#,
+            oracle_decl, body
+        );
+        let files = [SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(&source).unwrap(), source }];
+        let program = normalize_program(&files);
+        let mut r = Report::default();
+        for s in &program.account_structs {
+            rules_unchecked_data_read(s, &program, &mut r);
+        }
+        r.findings.into_iter().map(|f| f.subject).collect()
+    }
+
+    const READ: &str = "let price = Price::try_from_slice(&self.oracle.try_borrow_data()?)?;";
+
+    #[test]
+    fn raw_read_without_owner_check() {
+        assert_eq!(flagged("", READ), vec!["oracle"]);
+        assert_eq!(flagged("", "let data = self.oracle.data.borrow();"), vec!["oracle"]);
+        // mentioning the owner is not a check
+        assert_eq!(flagged("", &format!("msg!(\"{{}}\", self.oracle.owner); {}", READ)), vec!["oracle"]);
+    }
+
+    #[test]
+    fn owner_constraint_suppresses() {
+        assert!(flagged("#[account(owner = oracle_program::ID)]", READ).is_empty());
+        assert!(flagged("#[account(constraint = oracle.owner == &oracle_program::ID)]", READ).is_empty());
+    }
+
+    #[test]
+    fn owner_compared_before_the_read() {
+        assert!(flagged("", &format!("require_keys_eq!(*self.oracle.owner, oracle_program::ID, E::Owner); {}", READ)).is_empty());
+        assert!(flagged("", &format!("if self.oracle.owner != &oracle_program::ID {{ return err!(E::Owner); }} {}", READ)).is_empty());
+        assert!(flagged("", &format!("require!(self.oracle.is_owned_by(&oracle_program::ID), E::Owner); {}", READ)).is_empty());
+        // too late, the data was already read
+        assert_eq!(
+            flagged("", &format!("{} require_keys_eq!(*self.oracle.owner, oracle_program::ID, E::Owner);", READ)),
+            vec!["oracle"]
+        );
+    }
+}
@@ -0,0 +1,478 @@
+use std::collections::{HashMap, HashSet};
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Expr, ExprAssign, ExprBinary, ExprCall, ExprMethodCall, ExprStruct, Member};
+use crate::analyzer::constraints::{expr_to_string, mentions_ident};
+use crate::analyzer::rules::{account_field, enforcing_constraint, is_unchecked_account};
+use crate::analyzer::rules::registry::{AnalysisContext, Rule};
+use crate::report::report::*;
+use crate::report::knowledge_base::{Finding, Location, RuleMetadata, Severity};
+use crate::state::{NormalizedAccountField, NormalizedAccountStruct, NormalizedProgram};
+
+/* ────────────────────────────────────────────────────────────────
+   M-007 — Unsafe Account Close
+   ──────────────────────────────────────────────────────────────── */
+pub static RULE_UNSAFE_ACCOUNT_CLOSE: RuleMetadata = RuleMetadata {
+    code: "M-007",
+    title: "Unsafe Account Close",
+    description:
+        "Closing an account moves its lamports somewhere. When `close = <target>` or a manual transfer sends \
+         them to an account the instruction doesn't tie to the closed one, any caller can collect the rent of \
+         someone else's account. Draining a program owned account by hand without wiping its data or handing it \
+         back to the system program leaves it revivable: topping up the lamports within the same transaction \
+         keeps the old data alive. Emptying a PDA vault while the state account it is derived from stays open \
+         lets the same state be used again against an empty vault.",
+    severity: Severity::Medium,
+    recommendation:
+        "Tie the rent destination to the closed account with `has_one = <owner>`, seeds that include it, or an \
+         explicit constraint. Prefer Anchor's `close = <owner>` over moving lamports by hand, it zeroes the data \
+         and reassigns the account. When a vault is emptied, close the state account that derives it in the \
+         same instruction.",
+    additional_links: Some(
+        "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/9-closing-accounts\n\
+         https://solana.com/developers/courses/program-security/closing-accounts\n\
+         https://www.anchor-lang.com/docs/references/account-constraints#accountclose--target"
+    ),
+};
+
+/// Accounts holding program data, draining one without wiping it leaves a revivable account
+const DATA_ACCOUNT_TYPES: &[&str] = &["Account", "AccountLoader", "InterfaceAccount"];
+/// Methods on an account that retire it properly, `AccountsClose::close` or handing it back to the system program
+const RETIRE_METHODS: &[&str] = &["close", "assign", "realloc"];
+// ties through seeds are shallow, vault -> vault_state -> user
+const MAX_DEPTH: usize = 4;
+
+pub struct UnsafeAccountCloseRule;
+
+impl Rule for UnsafeAccountCloseRule {
+    fn id(&self) -> &'static str {
+        "unsafe_account_close"
+    }
+
+    fn metadata(&self) -> &'static RuleMetadata {
+        &RULE_UNSAFE_ACCOUNT_CLOSE
+    }
+
+    fn check(&self, ctx: &AnalysisContext, r: &mut Report) {
+        for s in &ctx.program.account_structs {
+            rules_close_target(s, r);
+            rules_manual_drain(s, ctx.program, r);
+        }
+    }
+}
+
+/// A method moving the whole balance out of `self.<field>`
+#[derive(Debug, Clone)]
+pub struct LamportDrain {
+    pub field: String,
+    pub destination: Option<String>,
+    pub method: String,
+    pub span: Span,
+}
+
+/// Walks the methods of an Accounts impl for full lamport drains and where the lamports go
+#[derive(Default)]
+pub struct DrainChecker {
+    pub drains: Vec<LamportDrain>,
+    // Transfer { from, to } pairs
+    pub transfers: Vec<(String, String)>,
+    // accounts credited with `add_lamports` / `+=` on their lamports
+    pub credited: Vec<String>,
+    // accounts closed, reassigned, resized or wiped in the method bodies
+    pub retired: HashSet<String>,
+    pub current_fn: String,
+    // `let mut data = self.pool.try_borrow_mut_data()?;`
+    data_locals: HashMap<String, String>,
+}
+
+impl DrainChecker {
+    fn push(&mut self, field: String, span: Span) {
+        if self.drains.iter().any(|d| d.field == field && d.method == self.current_fn) {
+            return;
+        }
+        self.drains.push(LamportDrain { field, destination: None, method: self.current_fn.clone(), span });
+    }
+
+    /// The account whose data `e` borrows mutably, `self.x.try_borrow_mut_data()?[..8]` or a local holding it
+    fn borrowed_data(&self, e: &Expr) -> Option<String> {
+        match e {
+            Expr::Unary(u) => self.borrowed_data(&u.expr),
+            Expr::Paren(p) => self.borrowed_data(&p.expr),
+            Expr::Try(t) => self.borrowed_data(&t.expr),
+            Expr::Reference(r) => self.borrowed_data(&r.expr),
+            Expr::Index(i) => self.borrowed_data(&i.expr),
+            Expr::MethodCall(m) if m.method == "try_borrow_mut_data" => account_field(&m.receiver),
+            Expr::MethodCall(m) if m.method == "borrow_mut" || m.method == "deref_mut" => match &*m.receiver {
+                Expr::Field(f) if matches!(&f.member, Member::Named(n) if n == "data") => account_field(&f.base),
+                other => self.borrowed_data(other),
+            },
+            Expr::Path(p) => self.data_locals.get(&p.path.get_ident()?.to_string()).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Pairs each drain with the account its lamports are moved to
+    fn resolve_destinations(&mut self) {
+        for drain in &mut self.drains {
+            drain.destination = self
+                .transfers
+                .iter()
+                .find(|(from, _)| *from == drain.field)
+                .map(|(_, to)| to.clone())
+                .or_else(|| self.credited.iter().find(|c| **c != drain.field).cloned());
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for DrainChecker {
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.current_fn = node.sig.ident.to_string();
+        self.data_locals.clear();
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        visit::visit_local(self, local);
+        if let syn::Pat::Ident(ident) = &local.pat
+            && let Some(init) = &local.init
+            && let Some(field) = self.borrowed_data(&init.expr) {
+            self.data_locals.insert(ident.ident.to_string(), field);
+        }
+    }
+
+    fn visit_expr_struct(&mut self, expr: &'ast ExprStruct) {
+        // system_program::Transfer { from: self.vault.to_account_info(), to: self.user.to_account_info() }
+        if expr.path.segments.last().is_some_and(|s| s.ident == "Transfer") {
+            let field = |name: &str| {
+                expr.fields
+                    .iter()
+                    .find(|f| matches!(&f.member, Member::Named(m) if m == name))
+                    .and_then(|f| account_field(&f.expr))
+            };
+            if let (Some(from), Some(to)) = (field("from"), field("to")) {
+                self.transfers.push((from, to));
+            }
+        }
+        visit::visit_expr_struct(self, expr);
+    }
+
+    fn visit_expr_call(&mut self, expr: &'ast ExprCall) {
+        // transfer(ctx, self.vault.lamports())
+        if let Expr::Path(p) = &*expr.func
+            && p.path.segments.last().is_some_and(|s| s.ident == "transfer")
+            && let Some(amount) = expr.args.iter().nth(1) {
+            for field in balance_reads(amount) {
+                self.push(field, expr.span());
+            }
+        }
+        visit::visit_expr_call(self, expr);
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast ExprMethodCall) {
+        let method = expr.method.to_string();
+        if let Some(field) = account_field(&expr.receiver) {
+            if method == "sub_lamports" && expr.args.iter().any(|a| balance_reads(a).contains(&field)) {
+                self.push(field, expr.span());
+            } else if method == "add_lamports" {
+                self.credited.push(field);
+            } else if RETIRE_METHODS.contains(&method.as_str()) {
+                self.retired.insert(field);
+            }
+        }
+        // data.fill(0), data[..8].copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR)
+        let wipes = method == "fill"
+            || (method == "copy_from_slice" && expr.args.iter().any(|a| expr_to_string(a).contains("CLOSED_ACCOUNT_DISCRIMINATOR")));
+        if wipes && let Some(field) = self.borrowed_data(&expr.receiver) {
+            self.retired.insert(field);
+        }
+        visit::visit_expr_method_call(self, expr);
+    }
+
+    fn visit_expr_assign(&mut self, expr: &'ast ExprAssign) {
+        // **self.pool.try_borrow_mut_lamports()? = 0
+        if let Some(field) = lamports_target(&expr.left)
+            && expr_to_string(&expr.right) == "0" {
+            self.push(field, expr.span());
+        }
+        visit::visit_expr_assign(self, expr);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast ExprBinary) {
+        if let Some(field) = lamports_target(&expr.left) {
+            match expr.op {
+                // **pool.lamports.borrow_mut() -= pool.lamports()
+                BinOp::SubAssign(_) if balance_reads(&expr.right).contains(&field) => self.push(field, expr.span()),
+                BinOp::AddAssign(_) => self.credited.push(field),
+                _ => {}
+            }
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+}
+
+/// `self.x.lamports()` / `self.x.get_lamports()` in an amount, the whole balance of `x`
+fn balance_reads(e: &Expr) -> Vec<String> {
+    #[derive(Default)]
+    struct Reads(Vec<String>);
+    impl<'ast> Visit<'ast> for Reads {
+        fn visit_expr_method_call(&mut self, m: &'ast ExprMethodCall) {
+            if (m.method == "lamports" || m.method == "get_lamports")
+                && let Some(field) = account_field(&m.receiver) {
+                self.0.push(field);
+            }
+            visit::visit_expr_method_call(self, m);
+        }
+    }
+    let mut reads = Reads::default();
+    reads.visit_expr(e);
+    reads.0
+}
+
+/// The account behind `**self.x.try_borrow_mut_lamports()?` or `**self.x.lamports.borrow_mut()`
+fn lamports_target(e: &Expr) -> Option<String> {
+    match e {
+        Expr::Unary(u) => lamports_target(&u.expr),
+        Expr::Paren(p) => lamports_target(&p.expr),
+        Expr::Try(t) => lamports_target(&t.expr),
+        Expr::MethodCall(m) if m.method == "try_borrow_mut_lamports" => account_field(&m.receiver),
+        Expr::MethodCall(m) if m.method == "borrow_mut" => match &*m.receiver {
+            Expr::Field(f) if matches!(&f.member, Member::Named(n) if n == "lamports") => account_field(&f.base),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Fields of `s` an expression mentions, i.e `vault_state` in `vault_state.key().as_ref()`
+fn mentioned_fields<'a>(s: &'a NormalizedAccountStruct, e: &Expr) -> Vec<&'a NormalizedAccountField> {
+    let text = expr_to_string(e);
+    s.fields.iter().filter(|f| mentions_ident(&text, &f.name)).collect()
+}
+
+/// Whether the struct ties `destination` to `account`: `has_one`, its seeds, a constraint naming both,
+/// or the same through an account its seeds are derived from
+pub fn tied_to(s: &NormalizedAccountStruct, account: &NormalizedAccountField, destination: &str, depth: usize) -> bool {
+    if depth > MAX_DEPTH {
+        return false;
+    }
+    let c = &account.constraints;
+    if c.has_one_targets().iter().any(|t| t == destination) {
+        return true;
+    }
+    let seeds = c.seeds.iter().flatten();
+    if seeds.clone().any(|e| mentions_ident(&expr_to_string(e), destination)) {
+        return true;
+    }
+    let names_both = s
+        .fields
+        .iter()
+        .flat_map(|f| f.constraints.constraint.iter().chain(f.constraints.address.iter()))
+        .filter_map(|ce| ce.expr.as_ref())
+        .map(expr_to_string)
+        .any(|text| mentions_ident(&text, &account.name) && mentions_ident(&text, destination));
+    if names_both {
+        return true;
+    }
+    seeds
+        .flat_map(|e| mentioned_fields(s, e))
+        .filter(|f| f.name != account.name)
+        .any(|f| tied_to(s, f, destination, depth + 1))
+}
+
+/// `close = <target>` must send the rent to an account tied to the closed one
+pub fn rules_close_target(s: &NormalizedAccountStruct, r: &mut Report) {
+    for field in &s.fields {
+        let Some(close) = &field.constraints.close else { continue };
+        let target_name = expr_to_string(close);
+        let Some(target) = s.fields.iter().find(|f| f.name == target_name) else { continue };
+
+        let message = if is_unchecked_account(target) && enforcing_constraint(s, target).is_none() {
+            format!(
+                "`{}` in `{}` is closed to `{}`, an unconstrained `{}`, any account can collect its rent",
+                field.name, s.name, target.name, target.base_type
+            )
+        } else if !tied_to(s, field, &target.name, 0) {
+            format!(
+                "`{}` in `{}` is closed to `{}`, but neither `has_one`, seeds nor a constraint tie `{}` to `{}`",
+                field.name, s.name, target.name, target.name, field.name
+            )
+        } else {
+            continue;
+        };
+
+        r.add(Finding::new(
+            &RULE_UNSAFE_ACCOUNT_CLOSE,
+            Location::from_span(&s.file, close.span()),
+            &s.name,
+            &field.name,
+            message,
+        )
+        .detail("Account", &field.name)
+        .detail("Context", &s.name)
+        .detail("Destination", &target.name)
+        .label(
+            Location::new(&s.file, target.line, target.column),
+            format!("`{}` is declared here", target.name),
+        ));
+    }
+}
+
+/// Lamports drained by hand: revivable data accounts, state left open, and untied destinations
+pub fn rules_manual_drain(s: &NormalizedAccountStruct, program: &NormalizedProgram, r: &mut Report) {
+    for imp in program.impls_of(&s.name) {
+        let mut checker = DrainChecker::default();
+        for method in &imp.methods {
+            checker.visit_impl_item_fn(method);
+        }
+        checker.resolve_destinations();
+        let drained: Vec<&str> = checker.drains.iter().map(|d| d.field.as_str()).collect();
+
+        for drain in &checker.drains {
+            let Some(field) = s.fields.iter().find(|f| f.name == drain.field) else { continue };
+            let closed = |f: &NormalizedAccountField| {
+                f.constraints.close.is_some() || checker.retired.contains(&f.name) || drained.contains(&f.name.as_str())
+            };
+
+            // each problem with the field to point a label at
+            let mut problems: Vec<(String, Option<(&NormalizedAccountField, String)>)> = Vec::new();
+            if DATA_ACCOUNT_TYPES.contains(&field.base_type.as_str()) && field.constraints.close.is_none() && !checker.retired.contains(&field.name) {
+                problems.push((
+                    format!(
+                        "`{}` drains the lamports of `{}` without closing it or wiping its data, the data stays behind and the account can be revived in the same transaction",
+                        drain.method, field.name
+                    ),
+                    None,
+                ));
+            }
+            // vault PDA derived from a state account that outlives it
+            let state = field
+                .constraints
+                .seeds
+                .iter()
+                .flatten()
+                .flat_map(|e| mentioned_fields(s, e))
+                .find(|f| f.name != field.name && DATA_ACCOUNT_TYPES.contains(&f.base_type.as_str()) && !closed(f));
+            if let Some(state) = state {
+                problems.push((
+                    format!(
+                        "`{}` empties `{}` but `{}`, which its seeds are derived from, is not closed",
+                        drain.method, field.name, state.name
+                    ),
+                    Some((state, format!("`{}` stays open", state.name))),
+                ));
+            }
+            if let Some(destination) = &drain.destination
+                && let Some(dest) = s.fields.iter().find(|f| f.name == *destination)
+                && !tied_to(s, field, destination, 0) {
+                problems.push((
+                    format!(
+                        "`{}` sends the lamports of `{}` to `{}`, which nothing ties to `{}`",
+                        drain.method, field.name, destination, field.name
+                    ),
+                    Some((dest, format!("`{}` is declared here", dest.name))),
+                ));
+            }
+
+            for (problem, label) in problems {
+                let mut finding = Finding::new(
+                    &RULE_UNSAFE_ACCOUNT_CLOSE,
+                    Location::from_span(&imp.file, drain.span),
+                    &s.name,
+                    &field.name,
+                    problem,
+                )
+                .detail("Account", &field.name)
+                .detail("Context", &s.name)
+                .detail("Method", &drain.method);
+                if let Some(destination) = &drain.destination {
+                    finding = finding.detail("Destination", destination);
+                }
+                if let Some((f, message)) = label {
+                    finding = finding.label(Location::new(&s.file, f.line, f.column), message);
+                }
+                r.add(finding);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::normalize::normalize_program;
+    use crate::state::SourceFile;
+
+    const ACCOUNTS: &str = r#"
+        #[account]
+        pub struct Pool { pub owner: Pubkey }
+
+        #[derive(Accounts)]
+        pub struct ClosePool<'info> {
+            #[account(mut)]
+            pub owner: Signer<'info>,
+            /// CHECK: anyone
+            #[account(mut)]
+            pub receiver: UncheckedAccount<'info>,
+            #[account(mut, CLOSE)]
+            pub pool: Account<'info, Pool>,
+        }
+    "#;
+
+    /// Messages for `ClosePool` with `close` in the pool constraints and `body` as its `close_pool` method
+    fn flagged(close: &str, body: &str) -> Vec<String> {
+        let source = format!(
+            "{}\nimpl<'info> ClosePool<'info> {{ pub fn close_pool(&mut self) -> Result<()> {{ {} Ok(()) }} }}",
+            ACCOUNTS.replace("CLOSE", close),
+            body
+        );
+        let files = [SourceFile { path: "lib.rs".to_string(), ast: syn::parse_file(&source).unwrap(), source }];
+        let program = normalize_program(&files);
+        let mut r = Report::default();
+        for s in &program.account_structs {
+            rules_close_target(s, &mut r);
+            rules_manual_drain(s, &program, &mut r);
+        }
+        r.findings.into_iter().map(|f| f.message).collect()
+    }
+
+    #[test]
+    fn close_targets() {
+        let unconstrained = flagged("close = receiver", "");
+        assert_eq!(unconstrained.len(), 1);
+        assert!(unconstrained[0].contains("an unconstrained `UncheckedAccount`, any account can collect its rent"));
+
+        let untied = flagged("close = owner", "");
+        assert_eq!(untied.len(), 1);
+        assert!(untied[0].contains("neither `has_one`, seeds nor a constraint tie `owner` to `pool`"));
+
+        assert!(flagged("has_one = owner, close = owner", "").is_empty());
+    }
+
+    const DRAIN: &str = "self.owner.add_lamports(self.pool.get_lamports())?; self.pool.sub_lamports(self.pool.get_lamports())?;";
+
+    #[test]
+    fn manual_drain_without_wiping() {
+        let drained = flagged("has_one = owner", DRAIN);
+        assert_eq!(drained.len(), 1);
+        assert!(drained[0].starts_with("`close_pool` drains the lamports of `pool` without closing it or wiping its data"));
+
+        let moved = flagged("has_one = owner", "**self.owner.try_borrow_mut_lamports()? += self.pool.lamports(); **self.pool.try_borrow_mut_lamports()? = 0;");
+        assert_eq!(moved.len(), 1);
+    }
+
+    #[test]
+    fn manual_drain_with_wiping() {
+        assert!(flagged("has_one = owner", &format!("{} self.pool.to_account_info().assign(&system_program::ID);", DRAIN)).is_empty());
+        assert!(flagged("has_one = owner", &format!("{} let mut data = self.pool.try_borrow_mut_data()?; data.fill(0);", DRAIN)).is_empty());
+        assert!(flagged(
+            "has_one = owner",
+            &format!("{} self.pool.try_borrow_mut_data()?[..8].copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR);", DRAIN)
+        )
+        .is_empty());
+        // the lamports go to an account nothing ties to the pool
+        let untied = flagged("has_one = owner", "self.receiver.add_lamports(self.pool.get_lamports())?; self.pool.sub_lamports(self.pool.get_lamports())?; self.pool.close(self.receiver.to_account_info())?;");
+        assert_eq!(untied.len(), 1);
+        assert!(untied[0].contains("to `receiver`, which nothing ties to `pool`"));
+    }
+}
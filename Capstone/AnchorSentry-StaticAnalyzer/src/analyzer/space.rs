@@ -0,0 +1,249 @@
+use syn::punctuated::Punctuated;
+use syn::{Attribute, BinOp, Expr, Fields, GenericArgument, ItemEnum, ItemStruct, PathArguments, Token, Type};
+use crate::analyzer::constraints::AccountConstraints;
+use crate::state::NormalizedProgram;
+
+/// `#[account]` structs are prefixed with an 8 byte discriminator unless a custom one is given
+pub const DEFAULT_DISCRIMINATOR_LEN: usize = 8;
+
+// guards against recursive types like `Box<Self>`
+const MAX_DEPTH: usize = 32;
+
+/// Computes borsh sizes the way `#[derive(InitSpace)]` does, resolving
+/// user defined structs, enums and consts from the program.
+pub struct SpaceCalculator<'a> {
+    program: &'a NormalizedProgram,
+}
+
+impl<'a> SpaceCalculator<'a> {
+    pub fn new(program: &'a NormalizedProgram) -> Self {
+        SpaceCalculator { program }
+    }
+
+    pub fn storage_struct(&self, name: &str) -> Option<&'a ItemStruct> {
+        self.program.storage_structs.iter().find(|s| s.ident == name)
+    }
+
+    /// `X::DISCRIMINATOR.len()`, honoring `#[account(discriminator = [..])]`
+    pub fn discriminator_len(&self, name: &str) -> Option<usize> {
+        let s = self.storage_struct(name)?;
+        let custom = AccountConstraints::from_attrs(&s.attrs)
+            .ok()?
+            .other
+            .into_iter()
+            .find(|o| o.namespace.is_empty() && o.key == "discriminator");
+
+        match custom.and_then(|o| o.value) {
+            Some(Expr::Array(arr)) => Some(arr.elems.len()),
+            Some(Expr::Lit(syn::ExprLit { lit: syn::Lit::ByteStr(b), .. })) => Some(b.value().len()),
+            Some(_) => None,
+            None => Some(DEFAULT_DISCRIMINATOR_LEN),
+        }
+    }
+
+    /// `X::INIT_SPACE`, the size of `X` without its discriminator
+    pub fn init_space(&self, name: &str) -> Option<usize> {
+        self.named_type_size(name, 0)
+    }
+
+    /// Bytes an `Account<'info, X>` needs: discriminator plus data
+    pub fn account_size(&self, name: &str) -> Option<usize> {
+        self.discriminator_len(name)?.checked_add(self.init_space(name)?)
+    }
+
+    /// Evaluates a space expression like `8 + 32 * 2` or `X::DISCRIMINATOR.len() + X::INIT_SPACE`
+    pub fn eval(&self, expr: &Expr) -> Option<usize> {
+        self.eval_expr(expr, 0)
+    }
+
+    fn eval_expr(&self, expr: &Expr, depth: usize) -> Option<usize> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        match expr {
+            Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(i), .. }) => i.base10_parse().ok(),
+            Expr::Paren(p) => self.eval_expr(&p.expr, depth + 1),
+            Expr::Group(g) => self.eval_expr(&g.expr, depth + 1),
+            Expr::Cast(c) => self.eval_expr(&c.expr, depth + 1),
+            Expr::Binary(b) => {
+                let l = self.eval_expr(&b.left, depth + 1)?;
+                let r = self.eval_expr(&b.right, depth + 1)?;
+                match b.op {
+                    BinOp::Add(_) => l.checked_add(r),
+                    BinOp::Sub(_) => l.checked_sub(r),
+                    BinOp::Mul(_) => l.checked_mul(r),
+                    BinOp::Div(_) => l.checked_div(r),
+                    _ => None,
+                }
+            }
+            Expr::Path(p) => {
+                let segments: Vec<String> = p.path.segments.iter().map(|s| s.ident.to_string()).collect();
+                match segments.as_slice() {
+                    [name] => self.const_value(name, depth),
+                    [.., ty, assoc] if assoc == "INIT_SPACE" => self.init_space(ty),
+                    _ => None,
+                }
+            }
+            // X::DISCRIMINATOR.len()
+            Expr::MethodCall(m) if m.method == "len" && m.args.is_empty() => match &*m.receiver {
+                Expr::Path(p) => {
+                    let mut segments = p.path.segments.iter().rev();
+                    let assoc = segments.next()?;
+                    let ty = segments.next()?;
+                    if assoc.ident == "DISCRIMINATOR" {
+                        self.discriminator_len(&ty.ident.to_string())
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn const_value(&self, name: &str, depth: usize) -> Option<usize> {
+        let c = self.program.consts.iter().find(|c| c.ident == name)?;
+        self.eval_expr(&c.expr, depth + 1)
+    }
+
+    fn named_type_size(&self, name: &str, depth: usize) -> Option<usize> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let program = self.program;
+        if let Some(s) = program.storage_structs.iter().chain(&program.data_structs).find(|s| s.ident == name) {
+            return self.fields_size(&s.fields, depth + 1);
+        }
+        if let Some(e) = program.enums.iter().find(|e| e.ident == name) {
+            return self.enum_size(e, depth + 1);
+        }
+        None
+    }
+
+    /// One byte of variant index plus the largest variant
+    fn enum_size(&self, e: &ItemEnum, depth: usize) -> Option<usize> {
+        let mut largest = 0;
+        for variant in &e.variants {
+            largest = largest.max(self.fields_size(&variant.fields, depth)?);
+        }
+        largest.checked_add(1)
+    }
+
+    fn fields_size(&self, fields: &Fields, depth: usize) -> Option<usize> {
+        let mut sum: usize = 0;
+        for field in fields {
+            let max_len = self.max_len(&field.attrs)?;
+            sum = sum.checked_add(self.type_size(&field.ty, &max_len, depth)?)?;
+        }
+        Some(sum)
+    }
+
+    /// `#[max_len(10, 32)]`, one entry per nesting level of `Vec`/`String`
+    fn max_len(&self, attrs: &[Attribute]) -> Option<Vec<usize>> {
+        let Some(attr) = attrs.iter().find(|a| a.path().is_ident("max_len")) else {
+            return Some(Vec::new());
+        };
+        let args = attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated).ok()?;
+        args.iter().map(|e| self.eval(e)).collect()
+    }
+
+    /// Borsh size of `ty`, `max_len` holds the remaining `#[max_len]` values for nested collections
+    pub fn type_size(&self, ty: &Type, max_len: &[usize], depth: usize) -> Option<usize> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        match ty {
+            Type::Paren(p) => self.type_size(&p.elem, max_len, depth + 1),
+            Type::Group(g) => self.type_size(&g.elem, max_len, depth + 1),
+            Type::Array(arr) => {
+                let len = self.eval_expr(&arr.len, depth + 1)?;
+                len.checked_mul(self.type_size(&arr.elem, max_len, depth + 1)?)
+            }
+            Type::Tuple(t) => t
+                .elems
+                .iter()
+                .try_fold(0usize, |sum, elem| sum.checked_add(self.type_size(elem, max_len, depth + 1)?)),
+            Type::Path(p) => {
+                let seg = p.path.segments.last()?;
+                let name = seg.ident.to_string();
+                if let Some(n) = primitive_size(&name) {
+                    return Some(n);
+                }
+
+                let inner = match &seg.arguments {
+                    PathArguments::AngleBracketed(args) => args.args.iter().find_map(|a| match a {
+                        GenericArgument::Type(t) => Some(t),
+                        _ => None,
+                    }),
+                    _ => None,
+                };
+                match (name.as_str(), inner) {
+                    // u32 length prefix plus the bytes
+                    ("String", _) => max_len.first()?.checked_add(4),
+                    ("Vec", Some(t)) => {
+                        let (len, rest) = max_len.split_first()?;
+                        len.checked_mul(self.type_size(t, rest, depth + 1)?)?.checked_add(4)
+                    }
+                    ("Option", Some(t)) => self.type_size(t, max_len, depth + 1)?.checked_add(1),
+                    ("Box", Some(t)) => self.type_size(t, max_len, depth + 1),
+                    _ => self.named_type_size(&name, depth + 1),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+pub fn primitive_size(ty: &str) -> Option<usize> {
+    match ty {
+        "u8" | "i8" | "bool" => Some(1),
+        "u16" | "i16" => Some(2),
+        "u32" | "i32" | "f32" => Some(4),
+        "u64" | "i64" | "f64" => Some(8),
+        "u128" | "i128" => Some(16),
+        "Pubkey" => Some(32),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(src: &str) -> NormalizedProgram {
+        let file = syn::parse_file(src).unwrap();
+        let storage_structs = file
+            .items
+            .into_iter()
+            .filter_map(|item| match item {
+                syn::Item::Struct(s) => Some(s),
+                _ => None,
+            })
+            .collect();
+        NormalizedProgram { storage_structs, ..Default::default() }
+    }
+
+    #[test]
+    fn sizes_collections_and_options() {
+        let program = program(
+            "pub struct Vault { pub owner: Pubkey, #[max_len(10)] pub amounts: Vec<u64>, #[max_len(32)] pub name: String, pub bump: Option<u8>, pub pair: (u16, [u8; 3]) }",
+        );
+        let calc = SpaceCalculator::new(&program);
+        assert_eq!(calc.init_space("Vault"), Some(32 + (4 + 10 * 8) + (4 + 32) + 2 + (2 + 3)));
+        assert_eq!(calc.account_size("Vault"), Some(8 + 32 + 84 + 36 + 2 + 5));
+    }
+
+    #[test]
+    fn overflowing_sizes_are_unknown() {
+        let program = program(
+            "pub struct Huge { #[max_len(4000000000000000000)] pub v: Vec<u64> }
+             pub struct Long { #[max_len(18446744073709551615)] pub s: String }
+             pub struct Wide { pub a: [u8; 18446744073709551615], pub b: u8 }",
+        );
+        let calc = SpaceCalculator::new(&program);
+        assert_eq!(calc.init_space("Huge"), None);
+        assert_eq!(calc.init_space("Long"), None);
+        assert_eq!(calc.init_space("Wide"), None);
+    }
+}
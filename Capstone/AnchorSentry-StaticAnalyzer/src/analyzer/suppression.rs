@@ -0,0 +1,321 @@
+use std::path::Path;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::visit::{self, Visit};
+
+use crate::config::LoadedConfig;
+use crate::report::knowledge_base::{Suppression, SuppressionKind};
+use crate::report::report::Report;
+use crate::state::SourceFile;
+
+pub const INLINE_MARKER: &str = "sentry-ignore:";
+
+/// `// sentry-ignore: L-002, M-001 reason` found on `line`
+#[derive(Debug, Clone)]
+pub struct InlineSuppression {
+    pub line: usize,
+    // whether code precedes the comment on the same line
+    pub trailing: bool,
+    pub codes: Vec<String>,
+    pub reason: String,
+}
+
+/// Lines covered by an inline suppression, inclusive
+#[derive(Debug, Clone, Copy)]
+struct LineRange {
+    start: usize,
+    end: usize,
+}
+
+pub fn parse_inline_suppressions(source: &str) -> Vec<InlineSuppression> {
+    let mut out = Vec::new();
+    for (idx, text) in source.lines().enumerate() {
+        let Some(comment_at) = comment_start(text) else { continue };
+        // the marker may follow an earlier comment on the same line (`// note // sentry-ignore: ...`),
+        // but only right after a `//`, not as a mention somewhere inside a longer comment
+        let marker = text[comment_at..].match_indices("//").find_map(|(at, _)| {
+            let comment = &text[comment_at + at + 2..];
+            comment.trim_start_matches('/').trim_start().strip_prefix(INLINE_MARKER)
+        });
+        let Some(rest) = marker else { continue };
+
+        let mut codes = Vec::new();
+        let mut reason_words = Vec::new();
+        for word in rest.split_whitespace() {
+            let code = word.trim_end_matches(',');
+            if reason_words.is_empty() && is_rule_code(code) {
+                codes.extend(code.split(',').filter(|c| !c.is_empty()).map(str::to_string));
+            } else {
+                reason_words.push(word);
+            }
+        }
+        if codes.is_empty() {
+            continue;
+        }
+
+        out.push(InlineSuppression {
+            line: idx + 1,
+            trailing: !text[..comment_at].trim().is_empty(),
+            codes,
+            reason: reason_words.join(" "),
+        });
+    }
+    out
+}
+
+/// Byte offset of the `//` opening a line comment, skipping any inside string or char literals
+pub(crate) fn comment_start(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut in_string = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if in_string => i += 1,
+            b'"' => in_string = !in_string,
+            // '"' and '\'' are chars, not a string quote; a lifetime like 'info has no closing quote
+            b'\'' if !in_string => {
+                if bytes.get(i + 1) == Some(&b'\\') {
+                    // the escaped char sits at i + 2, the closing quote after it
+                    let rest = bytes.get(i + 3..).unwrap_or_default();
+                    i += rest.iter().position(|&b| b == b'\'').map_or(0, |p| p + 3);
+                } else if bytes.get(i + 2) == Some(&b'\'') {
+                    i += 2;
+                }
+            }
+            b'/' if !in_string && bytes.get(i + 1) == Some(&b'/') => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Codes look like `L-002`, a comma separated list like `L-002,M-001` is accepted too
+fn is_rule_code(word: &str) -> bool {
+    !word.is_empty()
+        && word.split(',').filter(|c| !c.is_empty()).all(|c| {
+            let mut parts = c.splitn(2, '-');
+            matches!(
+                (parts.next(), parts.next()),
+                (Some(sev), Some(num)) if sev.len() == 1
+                    && sev.chars().all(|ch| ch.is_ascii_uppercase())
+                    && !num.is_empty()
+                    && num.chars().all(|ch| ch.is_ascii_digit())
+            )
+        })
+}
+
+/// Collects the line span (attributes and doc comments included) of every field and function
+#[derive(Default)]
+struct ScopeCollector {
+    scopes: Vec<LineRange>,
+}
+
+impl ScopeCollector {
+    fn push(&mut self, tokens: TokenStream) {
+        if let Some(range) = line_range(tokens) {
+            self.scopes.push(range);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ScopeCollector {
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        self.push(node.to_token_stream());
+        for field in &node.fields {
+            self.push(field.to_token_stream());
+        }
+        visit::visit_item_struct(self, node);
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.push(node.to_token_stream());
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.push(node.to_token_stream());
+        visit::visit_impl_item_fn(self, node);
+    }
+}
+
+fn line_range(tokens: TokenStream) -> Option<LineRange> {
+    let mut range: Option<LineRange> = None;
+    for tt in tokens {
+        let (start, end) = match &tt {
+            TokenTree::Group(g) => (g.span_open().start().line, g.span_close().end().line),
+            other => (other.span().start().line, other.span().end().line),
+        };
+        range = Some(match range {
+            Some(r) => LineRange { start: r.start.min(start), end: r.end.max(end) },
+            None => LineRange { start, end },
+        });
+    }
+    range
+}
+
+/// The lines a suppression comment applies to: the field or function that follows it
+/// (or the one it trails), otherwise just the next line of code.
+fn covered_ranges(s: &InlineSuppression, scopes: &[LineRange], lines: &[&str]) -> Vec<LineRange> {
+    let target = if s.trailing {
+        s.line
+    } else {
+        // skip blank lines and comments (doc comments included) to the next line of code
+        let mut line = s.line + 1;
+        while line <= lines.len() {
+            let t = lines[line - 1].trim();
+            if !(t.is_empty() || t.starts_with("//")) {
+                break;
+            }
+            line += 1;
+        }
+        line
+    };
+
+    let first = if s.trailing { s.line } else { s.line + 1 };
+    let matched: Vec<LineRange> = scopes
+        .iter()
+        .filter(|r| r.start >= first && r.start <= target)
+        .copied()
+        .collect();
+
+    if matched.is_empty() {
+        vec![LineRange { start: target, end: target }]
+    } else {
+        matched
+    }
+}
+
+impl Report {
+    /// Mark findings in `files` covered by a `// sentry-ignore: CODE reason` comment
+    pub fn apply_inline_suppressions(&mut self, files: &[SourceFile]) {
+        for file in files {
+            let suppressions = parse_inline_suppressions(&file.source);
+            if suppressions.is_empty() {
+                continue;
+            }
+
+            let mut collector = ScopeCollector::default();
+            collector.visit_file(&file.ast);
+            let lines: Vec<&str> = file.source.lines().collect();
+
+            for s in &suppressions {
+                let ranges = covered_ranges(s, &collector.scopes, &lines);
+                for f in self.findings.iter_mut().filter(|f| !f.is_suppressed() && f.location.file == file.path) {
+                    let line = f.location.line;
+                    if s.codes.iter().any(|c| c == f.rule.code) && ranges.iter().any(|r| r.start <= line && line <= r.end) {
+                        f.suppression = Some(Suppression {
+                            kind: SuppressionKind::Inline,
+                            reason: s.reason.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Mark findings matched by a `[[suppress]]` entry of anchor-sentry.toml
+    pub fn apply_config_suppressions(&mut self, cfg: &LoadedConfig) {
+        let base = cfg.dir.canonicalize().unwrap_or_else(|_| cfg.dir.clone());
+        for f in self.findings.iter_mut().filter(|f| !f.is_suppressed()) {
+            let rel = relative_path(&base, &f.location.file);
+            let entry = cfg.config.suppress.iter().find(|e| {
+                (e.rules.is_empty() || e.rules.iter().any(|r| r == f.rule.code))
+                    && (e.paths.is_empty() || e.paths.iter().any(|p| path_matches(p, &rel)))
+            });
+            if let Some(e) = entry {
+                f.suppression = Some(Suppression {
+                    kind: SuppressionKind::Config,
+                    reason: e.reason.clone().unwrap_or_default(),
+                });
+            }
+        }
+    }
+}
+
+/// Path of `file` relative to the config directory with `/` separators, or as given if outside it
+pub(crate) fn relative_path(base: &Path, file: &str) -> String {
+    let canonical = Path::new(file).canonicalize().unwrap_or_else(|_| Path::new(file).to_path_buf());
+    canonical
+        .strip_prefix(base)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| file.to_string())
+        .replace('\\', "/")
+}
+
+/// `*` matches any run of characters, a pattern without `*` matches the file itself or a directory prefix
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./").replace('\\', "/");
+    if pattern.contains('*') {
+        return wildcard_match(pattern.as_bytes(), path.as_bytes());
+    }
+    let dir = pattern.trim_end_matches('/');
+    path == dir || path.starts_with(&format!("{}/", dir))
+}
+
+fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|i| wildcard_match(rest, &text[i..])),
+        Some((c, rest)) => text.first() == Some(c) && wildcard_match(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_and_reason() {
+        let s = parse_inline_suppressions("// sentry-ignore: L-002, M-001 maker only receives lamports\npub maker: UncheckedAccount<'info>,");
+        assert_eq!(s.len(), 1);
+        assert_eq!(s[0].line, 1);
+        assert!(!s[0].trailing);
+        assert_eq!(s[0].codes, vec!["L-002", "M-001"]);
+        assert_eq!(s[0].reason, "maker only receives lamports");
+    }
+
+    #[test]
+    fn trailing_comment_and_comma_list() {
+        let s = parse_inline_suppressions("let x = a / b;\nlet y = a / c; // sentry-ignore: L-001,L-004 checked above");
+        assert_eq!(s.len(), 1);
+        assert_eq!(s[0].line, 2);
+        assert!(s[0].trailing);
+        assert_eq!(s[0].codes, vec!["L-001", "L-004"]);
+        assert_eq!(s[0].reason, "checked above");
+    }
+
+    #[test]
+    fn ignores_mentions_and_missing_codes() {
+        let s = parse_inline_suppressions("// see sentry-ignore: L-002 in the docs\n// sentry-ignore: no code here\n/// sentry-ignore: H-001");
+        assert_eq!(s.len(), 1);
+        assert_eq!(s[0].line, 3);
+        assert_eq!(s[0].codes, vec!["H-001"]);
+        assert!(s[0].reason.is_empty());
+    }
+
+    #[test]
+    fn skips_slashes_inside_string_literals() {
+        let s = parse_inline_suppressions(
+            "let uri = \"https://example.com\"; // sentry-ignore: M-003 metadata uri\nlet q = ['\"', '\\'']; // sentry-ignore: L-001 quoted",
+        );
+        assert_eq!(s.len(), 2);
+        assert!(s[0].trailing);
+        assert_eq!(s[0].codes, vec!["M-003"]);
+        assert_eq!(s[0].reason, "metadata uri");
+        assert_eq!(s[1].line, 2);
+        assert_eq!(s[1].codes, vec!["L-001"]);
+
+        // a `// sentry-ignore:` inside the string is not a comment
+        assert!(parse_inline_suppressions("msg!(\"// sentry-ignore: L-001\");").is_empty());
+    }
+
+    #[test]
+    fn marker_after_an_existing_trailing_comment() {
+        let s = parse_inline_suppressions("let fee = amount / rate; // rate set at init // sentry-ignore: L-001 non-zero");
+        assert_eq!(s.len(), 1);
+        assert!(s[0].trailing);
+        assert_eq!(s[0].codes, vec!["L-001"]);
+        assert_eq!(s[0].reason, "non-zero");
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+pub const CONFIG_FILE_NAME: &str = "anchor-sentry.toml";
+
+/// Contents of `anchor-sentry.toml`
+///
+/// ```toml
+/// [[suppress]]
+/// rules = ["L-002"]
+/// paths = ["programs/escrow/src/instructions/take.rs"]
+/// reason = "maker is tied to escrow through has_one"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SentryConfig {
+    #[serde(default)]
+    pub suppress: Vec<ConfigSuppression>,
+}
+
+/// One `[[suppress]]` entry, an empty `rules` list means every rule and an empty `paths` list every file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigSuppression {
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub paths: Vec<String>,
+    pub reason: Option<String>,
+}
+
+/// A loaded config along with the directory its relative paths are resolved against
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: SentryConfig,
+    pub dir: PathBuf,
+}
+
+/// Canonical directory of a config file, a bare `anchor-sentry.toml` has an empty parent meaning the working directory
+fn config_dir(path: &Path) -> PathBuf {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+impl LoadedConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let config: SentryConfig = toml::from_str(&raw)
+            .with_context(|| format!("Failed to parse config {}", path.display()))?;
+        Ok(LoadedConfig { config, dir: config_dir(path) })
+    }
+
+    /// Look for `anchor-sentry.toml` in `start` (or its directory when it is a file) and every parent
+    pub fn discover(start: &Path) -> Result<Option<Self>> {
+        let start = start.canonicalize().unwrap_or_else(|_| start.to_path_buf());
+        let mut dir = if start.is_file() { start.parent() } else { Some(start.as_path()) };
+
+        while let Some(d) = dir {
+            let candidate = d.join(CONFIG_FILE_NAME);
+            if candidate.is_file() {
+                return Self::load(&candidate).map(Some);
+            }
+            dir = d.parent();
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_file_name_resolves_to_the_working_directory() {
+        let cwd = std::env::current_dir().unwrap().canonicalize().unwrap();
+        assert_eq!(config_dir(Path::new(CONFIG_FILE_NAME)), cwd);
+        assert_eq!(config_dir(&Path::new(".").join(CONFIG_FILE_NAME)), cwd);
+    }
+
+    #[test]
+    fn nested_file_resolves_to_its_directory() {
+        let cwd = std::env::current_dir().unwrap().canonicalize().unwrap();
+        assert_eq!(config_dir(&Path::new("src").join(CONFIG_FILE_NAME)), cwd.join("src"));
+    }
+}
@@ -1,45 +1,203 @@
+#![allow(non_snake_case)]
+
 pub mod state;
 pub mod analyzer;
 pub mod report;
+pub mod config;
+pub mod lsp;
 
-use std::path::{Path};
+use std::path::{Path, PathBuf};
 use std::fs;
-use anyhow::{Context, Ok, Result};
-use analyzer::rules::*;
-use analyzer::rule_division_by_zero::rules_division_by_variable;
+use anyhow::{Context, Result};
+use analyzer::rules::{AnalysisContext, RuleRegistry};
+use analyzer::parser::load_workspace;
 use report::report::*;
+use state::SourceFile;
+use config::LoadedConfig;
 
 
 use crate::analyzer::normalize_program;
+use crate::analyzer::lifecycle::{program_lifecycle, ProgramLifecycle};
+use crate::analyzer::authorization::{program_authorization, ProgramAuthorization};
+use crate::analyzer::idl::LoadedIdl;
+use crate::report::baseline::{LoadedBaseline, DEFAULT_BASELINE};
+use crate::report::lifecycle::emit_lifecycle;
+use crate::report::authorization::emit_authorization;
+
+/// Options shared by the `analyze` modes
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzeOptions {
+    pub format: OutputFormat,
+    // explicit anchor-sentry.toml, otherwise it is looked up from the analyzed path upwards
+    pub config: Option<PathBuf>,
+    // IDL to cross-check against the source, or to analyze on its own
+    pub idl: Option<PathBuf>,
+    // findings already known, only new ones are reported
+    pub baseline: Option<PathBuf>,
+    // record the current findings into the baseline before applying it
+    pub write_baseline: bool,
+}
+
+impl AnalyzeOptions {
+    fn load_config(&self, analyzed: &Path) -> Result<Option<LoadedConfig>> {
+        match &self.config {
+            Some(path) => LoadedConfig::load(path).map(Some),
+            None => LoadedConfig::discover(analyzed),
+        }
+    }
+
+    fn load_idl(&self) -> Result<Option<LoadedIdl>> {
+        self.idl.as_deref().map(LoadedIdl::load).transpose()
+    }
+
+    /// Config suppressions first, then the baseline for whatever is still active
+    fn apply_suppressions(&self, r: &mut Report, analyzed: &Path) -> Result<()> {
+        if let Some(cfg) = self.load_config(analyzed)? {
+            r.apply_config_suppressions(&cfg);
+        }
+
+        let path = match (&self.baseline, self.write_baseline) {
+            (Some(path), _) => path.clone(),
+            (None, true) => PathBuf::from(DEFAULT_BASELINE),
+            (None, false) => return Ok(()),
+        };
+        if self.write_baseline {
+            r.write_baseline(&path)?;
+            eprintln!("Wrote {} finding(s) to baseline {}", r.active_findings().count(), path.display());
+        }
+        r.apply_baseline(&LoadedBaseline::load(&path)?);
+        Ok(())
+    }
+}
 
-pub fn run_analysis(path: &str) -> Result<()> {
+/// Rules that only need the Accounts structs and storage types, all an IDL describes
+const IDL_ONLY_RULES: &[&str] = &["missing_has_one", "duplicate_mutable_accounts", "missing_account_verification"];
+
+pub fn run_analysis(path: &str, opts: &AnalyzeOptions) -> Result<Report> {
 
     let path = Path::new(path);
 
     // let txt_path = Path::new("escrow-ast-raw.txt");
 
-    let ast = parse_rust_file(path)?;
+    let files = vec![read_source_file(path)?];
+    let idl = opts.load_idl()?;
 
-    let mut r = Report::default();
+    let mut r = Report { root: path.parent().map(Path::to_path_buf), ..Default::default() };
     r.load_file_info(path);
+    if let Some(idl) = &idl {
+        r.load_file_info(&idl.path);
+    }
+
+    analyze_program(&files, idl.as_ref(), &mut r);
+
+    opts.apply_suppressions(&mut r, path)?;
+
+    r.emit(opts.format)?;
+    Ok(r)
+}
+
+/// Analyze every program of an Anchor/cargo workspace, following `mod` declarations from each `lib.rs`.
+pub fn run_workspace_analysis(path: &str, opts: &AnalyzeOptions) -> Result<Report> {
+    let path = Path::new(path);
+    let programs = load_workspace(path)?;
+    let idl = opts.load_idl()?;
+
+    let mut r = Report { root: Some(path.to_path_buf()), ..Default::default() };
+    if let Some(idl) = &idl {
+        r.load_file_info(&idl.path);
+    }
+    for program in &programs {
+        for file in &program.files {
+            r.load_file_info(&file.path);
+        }
+        // the IDL belongs to the program of the same name, or to the only one
+        let idl = idl.as_ref().filter(|idl| {
+            programs.len() == 1 || idl.idl.program_name().is_some_and(|n| n.replace('-', "_") == program.name.replace('-', "_"))
+        });
+        analyze_program(&program.files, idl, &mut r);
+    }
+
+    opts.apply_suppressions(&mut r, path)?;
+
+    r.emit(opts.format)?;
+    Ok(r)
+}
+
+/// Analyze a program from its IDL alone, running the rules that don't need handler code
+pub fn run_idl_analysis(opts: &AnalyzeOptions) -> Result<Report> {
+    let Some(idl) = opts.load_idl()? else { anyhow::bail!("an IDL is required") };
+    let program = idl.to_program();
+
+    let mut r = Report { root: Path::new(&idl.path).parent().map(Path::to_path_buf), ..Default::default() };
+    r.load_file_info(&idl.path);
 
+    let mut registry = RuleRegistry::default();
+    registry.retain(IDL_ONLY_RULES);
+    registry.run(&AnalysisContext::new(&[], &program, None), &mut r);
 
-    let (account_structs, 
-        storage_structs, 
-        accounts_with_instructions, functions_args) = normalize_program(&ast);
+    opts.apply_suppressions(&mut r, Path::new(&idl.path))?;
 
-    for a in account_structs {
-        // println!("{:?}", a);
-        rules_missing_init_if_needed(&a, &mut r);
-        rules_wrong_space_assignment(&a, &storage_structs, &mut r);
-        rules_missing_signer_check(&a, &mut r);
-        
+    r.emit(opts.format)?;
+    Ok(r)
+}
+
+/// Source files of each program to summarize, a single file counts as its own program
+pub fn load_programs(file: Option<&str>, path: Option<&str>) -> Result<Vec<(String, Vec<SourceFile>)>> {
+    match (file, path) {
+        (Some(file), _) => {
+            let file = Path::new(file);
+            let name = file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            Ok(vec![(name, vec![read_source_file(file)?])])
+        }
+        (None, Some(path)) => Ok(load_workspace(Path::new(path))?.into_iter().map(|p| (p.name, p.files)).collect()),
+        (None, None) => anyhow::bail!("either a file or a workspace path is required"),
     }
-    rules_fn_missing_instruction_args(&accounts_with_instructions, &functions_args, &mut r);
-    rules_division_by_variable(&ast, &mut r);
+}
 
-    r.print();
-    Ok(())
+/// Print which instructions create, mutate and close every storage type
+pub fn run_lifecycle(file: Option<&str>, path: Option<&str>, format: SummaryFormat) -> Result<Vec<ProgramLifecycle>> {
+    let lifecycles: Vec<ProgramLifecycle> = load_programs(file, path)?
+        .into_iter()
+        .map(|(name, files)| {
+            let program = normalize_program(&files);
+            ProgramLifecycle { program: name, ..program_lifecycle(&program, &files) }
+        })
+        .collect();
+
+    emit_lifecycle(&lifecycles, format)?;
+    Ok(lifecycles)
+}
+
+/// Print the signers, writable accounts, PDAs and constraints of every handler
+pub fn run_authorization(file: Option<&str>, path: Option<&str>, format: SummaryFormat) -> Result<Vec<ProgramAuthorization>> {
+    let summaries: Vec<ProgramAuthorization> = load_programs(file, path)?
+        .into_iter()
+        .map(|(name, files)| {
+            let program = normalize_program(&files);
+            ProgramAuthorization { program: name, ..program_authorization(&program, &files) }
+        })
+        .collect();
+
+    emit_authorization(&summaries, format)?;
+    Ok(summaries)
+}
+
+/// Run every registered rule over one program, `files` being all the source files that make it up.
+pub fn analyze_program(files: &[SourceFile], idl: Option<&LoadedIdl>, r: &mut Report) {
+    let program = normalize_program(files);
+    let ctx = AnalysisContext::new(files, &program, idl);
+
+    RuleRegistry::default().run(&ctx, r);
+    r.apply_inline_suppressions(files);
+}
+
+/// Read and parse a file, keeping its text around for comment based features like suppressions
+pub fn read_source_file(path: &Path) -> Result<SourceFile> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file {}", path.display()))?;
+    let ast = parse_rust_code(&source).with_context(|| format!("Failed to parse file {}", path.display()))?;
+
+    Ok(SourceFile { path: path.to_string_lossy().to_string(), source, ast })
 }
 
 pub fn parse_rust_file(path: &Path) -> Result<syn::File> {
@@ -53,4 +211,4 @@ pub fn parse_rust_file(path: &Path) -> Result<syn::File> {
 pub fn parse_rust_code(content: &str) -> Result<syn::File> {
     syn::parse_str::<syn::File>(content)
         .map_err(|e| anyhow::anyhow!("Failed to parse Rust code: {}", e))
-}
\ No newline at end of file
+}
@@ -0,0 +1,137 @@
+use proc_macro2::{TokenTree, LineColumn};
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+use super::{Position, Range};
+use crate::analyzer::suppression::{comment_start, INLINE_MARKER};
+use crate::analyzer::rules::{RULE_MISSING_CHECK_DOC, RULE_MISSING_INIT_IF_NEEDED};
+use crate::report::knowledge_base::Finding;
+
+/// A text replacement, insertions have an empty range
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+/// A code action for one finding
+#[derive(Debug, Clone)]
+pub struct Fix {
+    pub title: String,
+    pub edits: Vec<TextEdit>,
+    // offered first by editors, only for fixes that resolve the finding as is
+    pub preferred: bool,
+}
+
+/// The Accounts struct field a finding points at, found by its name and line
+struct FieldFinder<'a> {
+    name: &'a str,
+    line: usize,
+    found: Option<syn::Field>,
+}
+
+impl<'ast> Visit<'ast> for FieldFinder<'_> {
+    fn visit_field(&mut self, field: &'ast syn::Field) {
+        if let Some(ident) = &field.ident
+            && ident == self.name
+            && ident.span().start().line == self.line {
+            self.found = Some(field.clone());
+        }
+        visit::visit_field(self, field);
+    }
+}
+
+fn find_field(text: &str, finding: &Finding) -> Option<syn::Field> {
+    let file = syn::parse_file(text).ok()?;
+    let mut finder = FieldFinder { name: &finding.subject, line: finding.location.line, found: None };
+    finder.visit_file(&file);
+    finder.found
+}
+
+/// proc_macro2 columns count chars, LSP counts UTF-16 units
+fn position(text: &str, at: LineColumn) -> Position {
+    let line = text.lines().nth(at.line.saturating_sub(1)).unwrap_or_default();
+    Position {
+        line: at.line.saturating_sub(1) as u32,
+        character: line.chars().take(at.column).map(|c| c.len_utf16() as u32).sum(),
+    }
+}
+
+fn indentation(text: &str, line: usize) -> String {
+    let line = text.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+    line.chars().take_while(|c| c.is_whitespace()).collect()
+}
+
+/// A new line inserted above `line` with the same indentation
+fn insert_above(text: &str, line: usize, content: &str) -> TextEdit {
+    let at = Position { line: line.saturating_sub(1) as u32, character: 0 };
+    TextEdit { range: Range { start: at, end: at }, new_text: format!("{}{}\n", indentation(text, line), content) }
+}
+
+/// First line of the field including its attributes and doc comments
+fn field_start_line(field: &syn::Field) -> usize {
+    field.attrs.first().map_or_else(|| field.span().start().line, |a| a.span().start().line)
+}
+
+/// H-001: the `init` of the field's `#[account(..)]` becomes `init_if_needed`
+fn init_if_needed_fix(finding: &Finding, text: &str) -> Option<Fix> {
+    let field = find_field(text, finding)?;
+    let attr = field.attrs.iter().find(|a| a.path().is_ident("account"))?;
+    let syn::Meta::List(list) = &attr.meta else { return None };
+    let init = list.tokens.clone().into_iter().find_map(|t| match t {
+        TokenTree::Ident(i) if i == "init" => Some(i),
+        _ => None,
+    })?;
+    let span = init.span();
+    Some(Fix {
+        title: "Use `init_if_needed`".to_string(),
+        edits: vec![TextEdit {
+            range: Range { start: position(text, span.start()), end: position(text, span.end()) },
+            new_text: "init_if_needed".to_string(),
+        }],
+        preferred: true,
+    })
+}
+
+/// L-003: a `/// CHECK:` doc above the field for the author to complete
+fn check_doc_fix(finding: &Finding, text: &str) -> Option<Fix> {
+    let field = find_field(text, finding)?;
+    Some(Fix {
+        title: "Add a `/// CHECK:` doc comment".to_string(),
+        edits: vec![insert_above(text, field_start_line(&field), "/// CHECK: explain why this account needs no further checks")],
+        preferred: false,
+    })
+}
+
+/// `// sentry-ignore: CODE` at the end of the flagged line
+fn suppress_fix(finding: &Finding, text: &str) -> Fix {
+    let line = finding.location.line;
+    let current = text.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+    let marker = format!("// {} {} <reason>", INLINE_MARKER, finding.rule.code);
+    // appended after an existing comment the marker would be part of it, put it on its own line above instead
+    let (at, new_text) = if comment_start(current).is_some() {
+        let indent: String = current.chars().take_while(|c| c.is_whitespace()).collect();
+        (Position { line: line.saturating_sub(1) as u32, character: 0 }, format!("{}{}\n", indent, marker))
+    } else {
+        let end = current.chars().map(|c| c.len_utf16() as u32).sum();
+        (Position { line: line.saturating_sub(1) as u32, character: end }, format!(" {}", marker))
+    };
+    Fix {
+        title: format!("Suppress {} on this line", finding.rule.code),
+        edits: vec![TextEdit { range: Range { start: at, end: at }, new_text }],
+        preferred: false,
+    }
+}
+
+/// Code actions for a finding: its mechanical fix when the rule has one, then an inline suppression
+pub fn fixes(finding: &Finding, text: &str) -> Vec<Fix> {
+    let code = finding.rule.code;
+    let fix = if code == RULE_MISSING_INIT_IF_NEEDED.code {
+        init_if_needed_fix(finding, text)
+    } else if code == RULE_MISSING_CHECK_DOC.code {
+        check_doc_fix(finding, text)
+    } else {
+        None
+    };
+    fix.into_iter().chain(std::iter::once(suppress_fix(finding, text))).collect()
+}
@@ -0,0 +1,461 @@
+pub mod fixes;
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::analyzer::parser::load_workspace;
+use crate::config::LoadedConfig;
+use crate::report::knowledge_base::{Finding, Location, Severity};
+use crate::report::report::Report;
+use crate::state::SourceFile;
+use crate::{analyze_program, parse_rust_code};
+
+/// JSON-RPC error code for requests the server doesn't implement
+const METHOD_NOT_FOUND: i64 = -32601;
+/// Sent back for requests arriving after `shutdown`
+const INVALID_REQUEST: i64 = -32600;
+
+/// Zero based line and UTF-16 offset, as LSP counts them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    pub fn contains(&self, p: Position) -> bool {
+        self.start <= p && p <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Range) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+/// An open editor buffer and the findings of its last analysis
+struct Document {
+    path: String,
+    canonical: PathBuf,
+    // crate the file belongs to, see `program_crate`
+    root: Option<PathBuf>,
+    text: String,
+    // `None` while the buffer doesn't parse
+    ast: Option<syn::File>,
+    findings: Vec<(Finding, Range)>,
+}
+
+impl Document {
+    fn source_file(&self, path: &str) -> Option<SourceFile> {
+        Some(SourceFile { path: path.to_string(), source: self.text.clone(), ast: self.ast.clone()? })
+    }
+
+    fn is_file(&self, file: &str) -> bool {
+        file == self.path || Path::new(file) == self.canonical
+    }
+}
+
+/// Offset of `column` (1 based, in chars) on `line` in UTF-16 code units
+fn utf16_column(line: &str, column: usize) -> u32 {
+    line.chars().take(column.saturating_sub(1)).map(|c| c.len_utf16() as u32).sum()
+}
+
+/// LSP range of a finding location, point locations extend to the end of their line
+pub fn lsp_range(text: &str, loc: &Location) -> Range {
+    let lines: Vec<&str> = text.lines().collect();
+    let line_text = |n: usize| lines.get(n.saturating_sub(1)).copied().unwrap_or_default();
+    let start = Position { line: loc.line.saturating_sub(1) as u32, character: utf16_column(line_text(loc.line), loc.column) };
+    let end = if loc.is_point() {
+        let text = line_text(loc.line);
+        Position { line: start.line, character: utf16_column(text, text.chars().count() + 1) }
+    } else {
+        Position { line: loc.end_line.saturating_sub(1) as u32, character: utf16_column(line_text(loc.end_line), loc.end_column) }
+    };
+    Range { start, end }
+}
+
+/// `file:///home/me/my%20program/lib.rs` -> `/home/me/my program/lib.rs`
+pub fn uri_to_path(uri: &str) -> String {
+    let raw = uri.strip_prefix("file://").unwrap_or(uri);
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(b) = raw.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            out.push(b);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+pub fn path_to_uri(path: &str) -> String {
+    let mut out = String::from("file://");
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            other => out.push_str(&format!("%{:02X}", other)),
+        }
+    }
+    out
+}
+
+fn lsp_severity(sev: Severity) -> u8 {
+    match sev {
+        Severity::High => 1,
+        Severity::Medium => 2,
+        Severity::Low => 3,
+    }
+}
+
+/// Reads one `Content-Length` framed message, `None` once the client closes stdin
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length") {
+            length = Some(value.trim().parse::<usize>().context("Invalid Content-Length")?);
+        }
+    }
+    let length = length.context("Message without Content-Length")?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body).context("Invalid JSON-RPC message")?))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The crate a file belongs to, the nearest directory up with a `Cargo.toml` and a `src/lib.rs`
+fn program_crate(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|d| d.join("Cargo.toml").is_file() && d.join("src").join("lib.rs").is_file())
+        .map(Path::to_path_buf)
+}
+
+pub struct Server {
+    documents: HashMap<String, Document>,
+    // program files as loaded from disk, by crate root; dropped when one of its files is saved
+    crates: HashMap<PathBuf, Vec<SourceFile>>,
+    shutdown: bool,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server::new()
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server { documents: HashMap::new(), crates: HashMap::new(), shutdown: false }
+    }
+
+    /// Loads the files of the crate at `root` from disk unless they are cached, or when `file`
+    /// is missing from them, i.e a module added since. False when `file` isn't part of it.
+    fn load_crate(&mut self, root: &Path, file: &Path) -> bool {
+        let has = |files: &[SourceFile]| files.iter().any(|f| Path::new(&f.path) == file);
+        if self.crates.get(root).is_some_and(|files| has(files)) {
+            return true;
+        }
+        let Some(program) = load_workspace(root).ok().and_then(|programs| programs.into_iter().find(|p| has(&p.files))) else {
+            return false;
+        };
+        self.crates.insert(root.to_path_buf(), program.files);
+        true
+    }
+
+    /// Source files of the program the document belongs to, with open buffers in place of the
+    /// files on disk, and the open documents among them. Just the document when it isn't part of
+    /// a crate that loads, nothing when it doesn't parse.
+    fn program_files(&mut self, uri: &str) -> (Vec<SourceFile>, Vec<String>) {
+        let Some(doc) = self.documents.get(uri) else { return (Vec::new(), Vec::new()) };
+        if doc.ast.is_none() {
+            return (Vec::new(), vec![uri.to_string()]);
+        }
+        let (root, canonical) = (doc.root.clone(), doc.canonical.clone());
+        let loaded = root.as_ref().is_some_and(|root| self.load_crate(root, &canonical));
+
+        let doc = &self.documents[uri];
+        let single = || (doc.source_file(&doc.path).into_iter().collect(), vec![uri.to_string()]);
+        let Some(on_disk) = root.filter(|_| loaded).and_then(|root| self.crates.get(&root)) else { return single() };
+        let mut files = Vec::new();
+        let mut open = Vec::new();
+        for file in on_disk {
+            let Some((open_uri, doc)) = self.documents.iter().find(|(_, d)| Path::new(&file.path) == d.canonical) else {
+                files.push(file.clone());
+                continue;
+            };
+            match doc.source_file(&file.path) {
+                Some(source) => {
+                    files.push(source);
+                    open.push(open_uri.clone());
+                }
+                // another buffer that doesn't parse drops the whole program, the document alone still gets checked
+                None => return single(),
+            }
+        }
+        (files, open)
+    }
+
+    /// Re-analyzes the program of a document and returns a `publishDiagnostics` notification for
+    /// every open document of it, a change in one file can add or resolve findings in another
+    fn refresh(&mut self, uri: &str) -> Vec<Value> {
+        let (files, open) = self.program_files(uri);
+        let mut r = Report::default();
+        if !files.is_empty() {
+            analyze_program(&files, None, &mut r);
+            match LoadedConfig::discover(&self.documents[uri].canonical) {
+                Ok(Some(cfg)) => r.apply_config_suppressions(&cfg),
+                Ok(None) => {}
+                Err(e) => eprintln!("anchor-sentry: {:#}", e),
+            }
+        }
+
+        let mut notifications = Vec::new();
+        for open_uri in open {
+            let Some(doc) = self.documents.get(&open_uri) else { continue };
+            let findings: Vec<(Finding, Range)> = r
+                .findings
+                .iter()
+                .filter(|f| !f.is_suppressed() && doc.is_file(&f.location.file))
+                .map(|f| (f.clone(), lsp_range(&doc.text, &f.location)))
+                .collect();
+            let diagnostics: Vec<Value> = findings.iter().map(|(f, range)| self.diagnostic(f, *range)).collect();
+            notifications.push(json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/publishDiagnostics",
+                "params": { "uri": open_uri, "diagnostics": diagnostics },
+            }));
+            if let Some(doc) = self.documents.get_mut(&open_uri) {
+                doc.findings = findings;
+            }
+        }
+        notifications
+    }
+
+    /// Stores the new text of a document and republishes its program
+    fn update(&mut self, uri: &str, text: String) -> Vec<Value> {
+        let ast = parse_rust_code(&text).ok();
+        match self.documents.get_mut(uri) {
+            Some(doc) => {
+                doc.text = text;
+                doc.ast = ast;
+            }
+            None => {
+                let path = uri_to_path(uri);
+                let canonical = Path::new(&path).canonicalize().unwrap_or_else(|_| PathBuf::from(&path));
+                let root = program_crate(&canonical);
+                self.documents.insert(uri.to_string(), Document { path, canonical, root, text, ast, findings: Vec::new() });
+            }
+        }
+        self.refresh(uri)
+    }
+
+    fn diagnostic(&self, finding: &Finding, range: Range) -> Value {
+        let related: Vec<Value> = finding
+            .labels
+            .iter()
+            .map(|l| {
+                let text = self
+                    .documents
+                    .values()
+                    .find(|d| d.path == l.location.file)
+                    .map(|d| d.text.clone())
+                    .or_else(|| std::fs::read_to_string(&l.location.file).ok())
+                    .unwrap_or_default();
+                json!({
+                    "location": { "uri": path_to_uri(&l.location.file), "range": lsp_range(&text, &l.location) },
+                    "message": l.message,
+                })
+            })
+            .collect();
+        json!({
+            "range": range,
+            "severity": lsp_severity(finding.severity()),
+            "code": finding.rule.code,
+            "source": "anchor-sentry",
+            "message": format!("{}: {}", finding.rule.title, finding.message),
+            "relatedInformation": related,
+        })
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((doc, position)) = self.document_position(params) else { return Value::Null };
+        let hits: Vec<&(Finding, Range)> = doc.findings.iter().filter(|(_, range)| range.contains(position)).collect();
+        let Some((_, range)) = hits.first() else { return Value::Null };
+
+        let sections: Vec<String> = hits
+            .iter()
+            .map(|(f, _)| {
+                format!(
+                    "**{} — {}** ({:?})\n\n{}\n\n{}\n\n**Recommendation:** {}",
+                    f.rule.code, f.rule.title, f.severity(), f.message, f.rule.description, f.rule.recommendation
+                )
+            })
+            .collect();
+        json!({
+            "contents": { "kind": "markdown", "value": sections.join("\n\n---\n\n") },
+            "range": range,
+        })
+    }
+
+    fn code_actions(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(doc) = self.documents.get(uri) else { return json!([]) };
+        let Ok(requested) = serde_json::from_value::<Range>(params["range"].clone()) else { return json!([]) };
+
+        let mut actions = Vec::new();
+        for (finding, range) in doc.findings.iter().filter(|(_, range)| range.overlaps(&requested)) {
+            let diagnostic = self.diagnostic(finding, *range);
+            for fix in fixes::fixes(finding, &doc.text) {
+                actions.push(json!({
+                    "title": fix.title,
+                    "kind": "quickfix",
+                    "isPreferred": fix.preferred,
+                    "diagnostics": [diagnostic],
+                    "edit": { "changes": { uri: fix.edits.iter().map(|e| json!({ "range": e.range, "newText": e.new_text })).collect::<Vec<_>>() } },
+                }));
+            }
+        }
+        Value::Array(actions)
+    }
+
+    fn document_position(&self, params: &Value) -> Option<(&Document, Position)> {
+        let doc = self.documents.get(params["textDocument"]["uri"].as_str()?)?;
+        let position = serde_json::from_value(params["position"].clone()).ok()?;
+        Some((doc, position))
+    }
+
+    /// Handles one client message and returns what to send back, responses and notifications alike
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    // full text on every change, the analysis is per file anyway
+                    "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+                    "hoverProvider": true,
+                    "codeActionProvider": { "codeActionKinds": ["quickfix"] },
+                },
+                "serverInfo": { "name": "anchor-sentry", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/codeAction" => self.code_actions(params),
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                return self.update(uri, text.to_string());
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let Some(text) = params["contentChanges"].as_array().and_then(|c| c.last()).and_then(|c| c["text"].as_str()) else {
+                    return Vec::new();
+                };
+                return self.update(uri, text.to_string());
+            }
+            "textDocument/didSave" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                // the files on disk changed, modules may have been added or removed
+                if let Some(root) = self.documents.get(uri).and_then(|d| d.root.as_ref()) {
+                    self.crates.remove(root);
+                }
+                return match params["text"].as_str() {
+                    Some(text) => self.update(uri, text.to_string()),
+                    None if self.documents.contains_key(uri) => self.refresh(uri),
+                    None => Vec::new(),
+                };
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let closed = self.documents.remove(uri);
+                let mut notifications = vec![json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": [] },
+                })];
+                // the rest of the crate sees the file on disk again instead of the buffer
+                let sibling = closed.and_then(|c| c.root).and_then(|root| {
+                    self.documents.iter().find(|(_, d)| d.root.as_ref() == Some(&root)).map(|(u, _)| u.clone())
+                });
+                if let Some(sibling) = sibling {
+                    notifications.extend(self.refresh(&sibling));
+                }
+                return notifications;
+            }
+            // notifications we don't act on, `initialized`, `$/cancelRequest`...
+            _ if id.is_none() => return Vec::new(),
+            _ => {
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": METHOD_NOT_FOUND, "message": format!("Unhandled method {}", method) },
+                })];
+            }
+        };
+
+        match id {
+            Some(id) if self.shutdown && method != "shutdown" => vec![json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": INVALID_REQUEST, "message": "Server is shutting down" },
+            })],
+            Some(id) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Serve LSP over stdin/stdout until the client sends `exit` or closes the stream
+pub fn run_lsp() -> Result<()> {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let mut stdout = io::stdout().lock();
+    let mut server = Server::new();
+
+    while let Some(message) = read_message(&mut reader)? {
+        if message["method"] == "exit" {
+            if !server.shutdown {
+                anyhow::bail!("exit received before shutdown");
+            }
+            break;
+        }
+        for reply in server.handle(&message) {
+            write_message(&mut stdout, &reply)?;
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,15 @@
+use std::process::ExitCode;
 use clap::{Parser, Subcommand};
-use AnchorSentry_StaticAnalyzer::run_analysis;
+use AnchorSentry_StaticAnalyzer::{AnalyzeOptions, run_analysis, run_authorization, run_idl_analysis, run_lifecycle, run_workspace_analysis};
+use AnchorSentry_StaticAnalyzer::report::report::{OutputFormat, SummaryFormat};
+use AnchorSentry_StaticAnalyzer::report::knowledge_base::Severity;
+
+/// No findings at or above the `--fail-on` threshold
+const EXIT_OK: u8 = 0;
+/// Findings at or above the `--fail-on` threshold
+const EXIT_FINDINGS: u8 = 1;
+/// The analysis itself failed, i.e a file could not be read or parsed (clap already exits with 2 on bad usage)
+const EXIT_ERROR: u8 = 3;
 
 #[derive(Parser)]
 #[command(
@@ -14,26 +24,131 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Analyze a Rust file containing an Anchor program
+    /// Analyze a Rust file or a whole Anchor workspace
     Analyze {
         /// Path to the Rust source file
+        #[arg(short, long, required_unless_present_any = ["path", "idl"], conflicts_with = "path")]
+        file: Option<String>,
+
+        /// Path to a workspace containing Anchor.toml or Cargo.toml
         #[arg(short, long)]
-        file: String,
+        path: Option<String>,
+
+        /// Output format of the report
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Exit with code 1 when a finding of this severity or higher is reported
+        #[arg(long, value_enum)]
+        fail_on: Option<Severity>,
+
+        /// Path to anchor-sentry.toml, looked up from the analyzed path upwards when omitted
+        #[arg(long)]
+        config: Option<String>,
+
+        /// Anchor IDL (JSON, or a Rust file with `idlgen!`) to cross-check against the source,
+        /// analyzed on its own when no file or path is given
+        #[arg(long)]
+        idl: Option<String>,
+
+        /// Only report findings not listed in this baseline file
+        #[arg(long)]
+        baseline: Option<String>,
+
+        /// Write the current findings to the baseline (sentry-baseline.json unless --baseline is given)
+        #[arg(long)]
+        write_baseline: bool,
     },
 
+    /// Show which instructions create, mutate and close each account type
+    Lifecycle {
+        /// Path to the Rust source file
+        #[arg(short, long, required_unless_present = "path", conflicts_with = "path")]
+        file: Option<String>,
+
+        /// Path to a workspace containing Anchor.toml or Cargo.toml
+        #[arg(short, long)]
+        path: Option<String>,
+
+        /// Output format of the table
+        #[arg(long, value_enum, default_value_t = SummaryFormat::Text)]
+        format: SummaryFormat,
+    },
+
+    /// List every handler with its signers, writable accounts, PDAs and the constraints gating them
+    Authorization {
+        /// Path to the Rust source file
+        #[arg(short, long, required_unless_present = "path", conflicts_with = "path")]
+        file: Option<String>,
+
+        /// Path to a workspace containing Anchor.toml or Cargo.toml
+        #[arg(short, long)]
+        path: Option<String>,
+
+        /// Output format of the table
+        #[arg(long, value_enum, default_value_t = SummaryFormat::Text)]
+        format: SummaryFormat,
+    },
+
+    /// Serve diagnostics, hovers and quick fixes to an editor over the Language Server Protocol (stdio)
+    Lsp,
+
     /// Print info about the tool
     Info,
 }
 
-fn main() {
+fn main() -> ExitCode {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Analyze { file } => {
-            run_analysis(&file);
+        Commands::Analyze { file, path, format, fail_on, config, idl, baseline, write_baseline } => {
+            let opts = AnalyzeOptions {
+                format,
+                config: config.map(Into::into),
+                idl: idl.map(Into::into),
+                baseline: baseline.map(Into::into),
+                write_baseline,
+            };
+            let result = match (file, path) {
+                (Some(file), _) => run_analysis(&file, &opts),
+                (None, Some(path)) => run_workspace_analysis(&path, &opts),
+                (None, None) => run_idl_analysis(&opts),
+            };
+            match result {
+                Ok(report) => match fail_on {
+                    Some(threshold) if report.has_findings_at_or_above(threshold) => ExitCode::from(EXIT_FINDINGS),
+                    _ => ExitCode::from(EXIT_OK),
+                },
+                Err(e) => {
+                    eprintln!("Error: {:#}", e);
+                    ExitCode::from(EXIT_ERROR)
+                }
+            }
         }
+        Commands::Lifecycle { file, path, format } => match run_lifecycle(file.as_deref(), path.as_deref(), format) {
+            Ok(_) => ExitCode::from(EXIT_OK),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                ExitCode::from(EXIT_ERROR)
+            }
+        },
+        Commands::Authorization { file, path, format } => match run_authorization(file.as_deref(), path.as_deref(), format) {
+            Ok(_) => ExitCode::from(EXIT_OK),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                ExitCode::from(EXIT_ERROR)
+            }
+        },
+        Commands::Lsp => match AnchorSentry_StaticAnalyzer::lsp::run_lsp() {
+            Ok(()) => ExitCode::from(EXIT_OK),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                ExitCode::from(EXIT_ERROR)
+            }
+        },
         Commands::Info => {
             println!("Anchor Sentry — Solana Static Analyzer (V0)");
+            ExitCode::from(EXIT_OK)
         }
     }
 }
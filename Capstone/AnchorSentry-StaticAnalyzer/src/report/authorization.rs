@@ -0,0 +1,88 @@
+use serde::Serialize;
+
+use super::report::{SummaryFormat, BOLD, CYAN, DIM, GREEN, MAGENTA, RESET, YELLOW};
+use super::table::print_table;
+use crate::analyzer::authorization::{AccountAccess, InstructionAuthorization, ProgramAuthorization};
+
+const HEADERS: [&str; 5] = ["Account", "Type", "Access", "PDA Seeds", "Constraints"];
+
+/// Top level JSON document written by `authorization --format json`
+#[derive(Debug, Serialize)]
+pub struct JsonAuthorization<'a> {
+    pub tool: &'static str,
+    pub version: &'static str,
+    pub programs: &'a [ProgramAuthorization],
+}
+
+/// `signer, mut`, `—` for read-only accounts
+fn access(a: &AccountAccess) -> String {
+    let flags: Vec<&str> = [("signer", a.signer), ("mut", a.writable)].iter().filter(|(_, set)| *set).map(|(f, _)| *f).collect();
+    if flags.is_empty() { "—".to_string() } else { flags.join(", ") }
+}
+
+fn row(a: &AccountAccess) -> Vec<String> {
+    let seeds = match (&a.seeds, &a.bump) {
+        (Some(seeds), Some(bump)) => format!("[{}] {}", seeds.join(", "), bump),
+        (Some(seeds), None) => format!("[{}]", seeds.join(", ")),
+        (None, _) => "—".to_string(),
+    };
+    let constraints = if a.constraints.is_empty() { "—".to_string() } else { a.constraints.join(", ") };
+    vec![a.name.clone(), a.ty.clone(), access(a), seeds, constraints]
+}
+
+fn print_instruction(ix: &InstructionAuthorization) {
+    println!("{BOLD}{CYAN}{}{RESET}({})  {DIM}Context<{}>{RESET}", ix.instruction, ix.args.join(", "), ix.context);
+    if !ix.calls.is_empty() {
+        println!("  {DIM}calls {}{RESET}", ix.calls.join(", "));
+    }
+    println!();
+
+    if !ix.context_found {
+        println!("  {YELLOW}Accounts struct `{}` not found, its signers and accounts are unknown{RESET}\n", ix.context);
+        return;
+    }
+
+    let rows: Vec<Vec<String>> = ix.accounts.iter().map(row).collect();
+    print_table(&HEADERS, &rows);
+    println!();
+
+    if ix.signers.is_empty() {
+        println!("  {YELLOW}No signer, anyone can call it{RESET}");
+    }
+    for signer in &ix.signers {
+        if signer.checked_against.is_empty() {
+            println!("  {BOLD}{}{RESET} signs: {YELLOW}not checked against stored state, any signer is accepted{RESET}", signer.account);
+        } else {
+            println!("  {BOLD}{}{RESET} signs: {GREEN}{}{RESET}", signer.account, signer.checked_against.join(", "));
+        }
+    }
+    println!();
+}
+
+pub fn print_authorization(programs: &[ProgramAuthorization]) {
+    println!("\n{MAGENTA}{BOLD}══════════════════════════════════════════════════════════════");
+    println!("        SOLANA STATIC ANALYZER — AUTHORIZATION SUMMARY");
+    println!("══════════════════════════════════════════════════════════════{RESET}\n");
+
+    for program in programs {
+        println!("{CYAN}{BOLD}Program:{RESET} {}\n", program.program);
+        if program.instructions.is_empty() {
+            println!("{DIM}  No #[program] handlers found{RESET}\n");
+            continue;
+        }
+        for ix in &program.instructions {
+            print_instruction(ix);
+        }
+    }
+}
+
+pub fn emit_authorization(programs: &[ProgramAuthorization], format: SummaryFormat) -> anyhow::Result<()> {
+    match format {
+        SummaryFormat::Text => print_authorization(programs),
+        SummaryFormat::Json => {
+            let doc = JsonAuthorization { tool: "anchor-sentry", version: env!("CARGO_PKG_VERSION"), programs };
+            println!("{}", serde_json::to_string_pretty(&doc)?);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::knowledge_base::{Suppression, SuppressionKind};
+use super::report::Report;
+use crate::analyzer::suppression::relative_path;
+
+/// Written by `--write-baseline` when `--baseline` doesn't name a file
+pub const DEFAULT_BASELINE: &str = "sentry-baseline.json";
+/// Bumped whenever the fingerprint inputs change, older baselines then have to be rewritten
+pub const BASELINE_VERSION: u32 = 1;
+
+/// Findings accepted as known, `analyze --baseline` only reports the ones not listed here
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub tool: String,
+    pub findings: Vec<BaselineEntry>,
+}
+
+/// Everything but the fingerprint is there for whoever reviews the baseline file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub rule: String,
+    pub file: String,
+    pub context: String,
+    pub subject: String,
+    pub message: String,
+}
+
+/// A baseline along with the directory finding paths are taken relative to
+#[derive(Debug, Clone)]
+pub struct LoadedBaseline {
+    pub baseline: Baseline,
+    pub path: PathBuf,
+    pub dir: PathBuf,
+}
+
+fn baseline_dir(path: &Path) -> PathBuf {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+impl LoadedBaseline {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        let baseline: Baseline = serde_json::from_str(&text).with_context(|| format!("Failed to parse baseline {}", path.display()))?;
+        if baseline.version != BASELINE_VERSION {
+            anyhow::bail!(
+                "Baseline {} has version {}, expected {}; regenerate it with --write-baseline",
+                path.display(), baseline.version, BASELINE_VERSION
+            );
+        }
+        Ok(LoadedBaseline { baseline, path: path.to_path_buf(), dir: baseline_dir(path) })
+    }
+}
+
+impl Report {
+    /// Baseline of every finding not already suppressed in source or config
+    pub fn to_baseline(&self, path: &Path) -> Baseline {
+        let dir = baseline_dir(path);
+        let findings = self
+            .findings
+            .iter()
+            .zip(self.fingerprints_from(Some(&dir)))
+            .filter(|(f, _)| !f.is_suppressed())
+            .map(|(f, fingerprint)| BaselineEntry {
+                fingerprint,
+                rule: f.rule.code.to_string(),
+                file: relative_path(&dir, &f.location.file),
+                context: f.context.clone(),
+                subject: f.subject.clone(),
+                message: f.message.clone(),
+            })
+            .collect();
+        Baseline { version: BASELINE_VERSION, tool: format!("anchor-sentry {}", env!("CARGO_PKG_VERSION")), findings }
+    }
+
+    pub fn write_baseline(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.to_baseline(path))?;
+        fs::write(path, json + "\n").with_context(|| format!("Failed to write baseline {}", path.display()))
+    }
+
+    /// Mark findings listed in the baseline, only new ones stay active
+    pub fn apply_baseline(&mut self, baseline: &LoadedBaseline) {
+        let fingerprints = self.fingerprints_from(Some(&baseline.dir));
+        let reason = format!("listed in {}", baseline.path.display());
+        for (f, fingerprint) in self.findings.iter_mut().zip(fingerprints) {
+            if !f.is_suppressed() && baseline.baseline.findings.iter().any(|e| e.fingerprint == fingerprint) {
+                f.suppression = Some(Suppression { kind: SuppressionKind::Baseline, reason: reason.clone() });
+            }
+        }
+    }
+}
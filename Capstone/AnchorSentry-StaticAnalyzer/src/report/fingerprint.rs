@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::knowledge_base::{Finding, Location};
+use super::report::Report;
+use crate::analyzer::suppression::relative_path;
+
+/// 64-bit FNV-1a, unlike `DefaultHasher` its output never changes between Rust releases
+pub fn fnv1a64(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in data {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Identity of a finding that survives edits elsewhere in the file: rule, file, context, subject
+/// and the flagged code with whitespace collapsed, no line.
+pub fn finding_key(f: &Finding, file: &str, snippet: &str) -> String {
+    format!(
+        "{}|{}|{}|{}|{}",
+        f.rule.code,
+        file.replace('\\', "/"),
+        f.context,
+        f.subject,
+        snippet
+    )
+}
+
+/// Source covered by `loc` on a single line of whitespace separated tokens, whole lines for point locations
+pub fn normalized_snippet(source: &str, loc: &Location) -> String {
+    let last = loc.end_line.max(loc.line);
+    let text: Vec<String> = source
+        .lines()
+        .enumerate()
+        .skip(loc.line.saturating_sub(1))
+        .take(last + 1 - loc.line.max(1))
+        .map(|(i, line)| {
+            let n = i + 1;
+            if loc.is_point() {
+                return line.to_string();
+            }
+            let start = if n == loc.line { loc.column.saturating_sub(1) } else { 0 };
+            let end = if n == last { loc.end_column.saturating_sub(1) } else { usize::MAX };
+            line.chars().skip(start).take(end.saturating_sub(start)).collect()
+        })
+        .collect();
+    text.join(" ").split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl Report {
+    /// One fingerprint per finding, in the same order as `findings`.
+    /// Findings sharing a key (i.e two divisions by `x` in one fn) get an occurrence suffix.
+    pub fn fingerprints(&self) -> Vec<String> {
+        self.fingerprints_from(None)
+    }
+
+    /// Fingerprints with file paths taken relative to `base`, so a checked in baseline matches
+    /// wherever the repository is cloned
+    pub fn fingerprints_from(&self, base: Option<&Path>) -> Vec<String> {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        self.findings
+            .iter()
+            .map(|f| {
+                let file = match base {
+                    Some(base) => relative_path(base, &f.location.file),
+                    None => f.location.file.clone(),
+                };
+                let snippet = self
+                    .sources
+                    .get(&f.location.file)
+                    .map(|source| normalized_snippet(source, &f.location))
+                    .unwrap_or_default();
+                let key = finding_key(f, &file, &snippet);
+                let n = seen.entry(key.clone()).or_insert(0);
+                *n += 1;
+                format!("{:016x}:{}", fnv1a64(key.as_bytes()), n)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::rules::RULE_POSSIBLE_DIVISION_BY_ZERO;
+
+    fn span(line: usize, column: usize, end_line: usize, end_column: usize) -> Location {
+        Location { file: "lib.rs".to_string(), line, column, end_line, end_column }
+    }
+
+    #[test]
+    fn snippet_of_a_span_on_one_line() {
+        let source = "fn f() {\n    let z = x  /  (y - 1);\n}";
+        assert_eq!(normalized_snippet(source, &span(2, 13, 2, 26)), "x / (y - 1)");
+        assert_eq!(normalized_snippet(source, &span(2, 20, 2, 25)), "y - 1");
+    }
+
+    #[test]
+    fn snippet_of_a_span_over_lines_is_collapsed() {
+        let source = "let z = amount\n        .checked_div(\n            rate,\n        )\n        .unwrap();";
+        assert_eq!(normalized_snippet(source, &span(1, 9, 4, 10)), "amount .checked_div( rate, )");
+    }
+
+    #[test]
+    fn point_location_takes_the_whole_line() {
+        let source = "struct A {\n\tpub  maker:   UncheckedAccount<'info>,  \n}";
+        assert_eq!(normalized_snippet(source, &Location::new("lib.rs", 2, 6)), "pub maker: UncheckedAccount<'info>,");
+    }
+
+    #[test]
+    fn columns_count_chars() {
+        let source = "let é = \"ü\" ; let z = a / b;";
+        assert_eq!(normalized_snippet(source, &span(1, 23, 1, 28)), "a / b");
+    }
+
+    #[test]
+    fn out_of_range_locations_are_empty() {
+        assert_eq!(normalized_snippet("let a = 1;", &span(5, 1, 5, 3)), "");
+        assert_eq!(normalized_snippet("", &Location::new("lib.rs", 0, 0)), "");
+    }
+
+    #[test]
+    fn fingerprints_ignore_lines_and_number_repeats() {
+        let report = |source: &str, lines: &[usize]| {
+            let mut r = Report::default();
+            r.sources.insert("lib.rs".to_string(), source.to_string());
+            for &line in lines {
+                r.add(Finding::new(&RULE_POSSIBLE_DIVISION_BY_ZERO, span(line, 9, line, 14), "f", "b", "message"));
+            }
+            r.fingerprints()
+        };
+        let before = report("let a = a / b;\nlet c = c / b;", &[1]);
+        let after = report("\n\nlet a = a / b;\nlet c = a / b;", &[3, 4]);
+        assert_eq!(before[0], after[0]);
+        assert!(after[0].ends_with(":1"));
+        assert_eq!(after[1], after[0].replace(":1", ":2"));
+    }
+}
@@ -0,0 +1,73 @@
+use serde::Serialize;
+use chrono::Local;
+
+use super::knowledge_base::Finding;
+use super::line_counter::FileInfo;
+use super::report::Report;
+
+/// Bumped whenever a field is renamed or removed, adding fields keeps the version.
+pub const JSON_SCHEMA_VERSION: u32 = 2;
+
+/// Top level JSON document written by `--format json`
+#[derive(Debug, Serialize)]
+pub struct JsonReport<'a> {
+    pub schema_version: u32,
+    pub tool: JsonTool,
+    pub generated_at: String,
+    pub files: Vec<JsonFile<'a>>,
+    pub summary: JsonSummary,
+    pub findings: &'a [Finding],
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonTool {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonFile<'a> {
+    pub path: &'a str,
+    #[serde(flatten)]
+    pub info: &'a FileInfo,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonSummary {
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+    pub total: usize,
+    pub suppressed: usize,
+}
+
+impl Report {
+    pub fn to_json_report(&self) -> JsonReport<'_> {
+        let (high, medium, low) = self.severity_counts();
+        JsonReport {
+            schema_version: JSON_SCHEMA_VERSION,
+            tool: JsonTool {
+                name: "anchor-sentry",
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            generated_at: Local::now().to_rfc3339(),
+            files: self
+                .files
+                .iter()
+                .map(|(path, info)| JsonFile { path, info })
+                .collect(),
+            summary: JsonSummary {
+                high,
+                medium,
+                low,
+                total: high + medium + low,
+                suppressed: self.suppressed_count(),
+            },
+            findings: &self.findings,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.to_json_report())
+    }
+}
@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+use super::report::{SummaryFormat, BOLD, CYAN, DIM, MAGENTA, RESET};
+use super::table::print_table;
+use crate::analyzer::lifecycle::{AccountLifecycle, LifecycleStep, ProgramLifecycle};
+
+const HEADERS: [&str; 4] = ["Account Type", "Created By", "Mutated By", "Closed By"];
+
+/// Top level JSON document written by `lifecycle --format json`
+#[derive(Debug, Serialize)]
+pub struct JsonLifecycle<'a> {
+    pub tool: &'static str,
+    pub version: &'static str,
+    pub programs: &'a [ProgramLifecycle],
+}
+
+/// Instructions of `steps`, once each and in order
+fn instruction_list<'a>(steps: impl Iterator<Item = &'a LifecycleStep>, describe: impl Fn(&LifecycleStep) -> String) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for step in steps {
+        let cell = describe(step);
+        if !out.contains(&cell) {
+            out.push(cell);
+        }
+    }
+    if out.is_empty() { "—".to_string() } else { out.join(", ") }
+}
+
+/// One table row: type, creators, mutators and closers with their rent receiver
+fn row(account: &AccountLifecycle) -> Vec<String> {
+    vec![
+        account.type_name.clone(),
+        instruction_list(account.creators(), |s| format!("{} ({})", s.instruction_label(false), s.created.as_deref().unwrap_or_default())),
+        instruction_list(account.mutators(), |s| s.instruction_label(false)),
+        instruction_list(account.closers(), |s| match &s.close {
+            Some(c) => format!("{} → {}", s.instruction_label(false), c.destination),
+            None => s.instruction_label(false),
+        }),
+    ]
+}
+
+pub fn print_lifecycle(programs: &[ProgramLifecycle]) {
+    println!("\n{MAGENTA}{BOLD}══════════════════════════════════════════════════════════════");
+    println!("        SOLANA STATIC ANALYZER — ACCOUNT LIFECYCLES");
+    println!("══════════════════════════════════════════════════════════════{RESET}\n");
+
+    for program in programs {
+        println!("{CYAN}{BOLD}Program:{RESET} {}\n", program.program);
+        if program.accounts.is_empty() {
+            println!("{DIM}  No #[account] types found{RESET}\n");
+            continue;
+        }
+
+        let rows: Vec<Vec<String>> = program.accounts.iter().map(row).collect();
+        print_table(&HEADERS, &rows);
+        println!();
+    }
+}
+
+pub fn emit_lifecycle(programs: &[ProgramLifecycle], format: SummaryFormat) -> anyhow::Result<()> {
+    match format {
+        SummaryFormat::Text => print_lifecycle(programs),
+        SummaryFormat::Json => {
+            let doc = JsonLifecycle { tool: "anchor-sentry", version: env!("CARGO_PKG_VERSION"), programs };
+            println!("{}", serde_json::to_string_pretty(&doc)?);
+        }
+    }
+    Ok(())
+}
@@ -1,8 +1,9 @@
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use serde::Serialize;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, Serialize)]
 pub struct FileInfo {
     pub comments_count: i32,
     pub blank_spaces_count: i32,
@@ -11,11 +12,7 @@ pub struct FileInfo {
 
 impl FileInfo {
     pub fn new() -> Self {
-        FileInfo {
-            comments_count: 0,
-            blank_spaces_count: 0,
-            lines_of_code: 0,
-        }
+        FileInfo::default()
     }
 }
 
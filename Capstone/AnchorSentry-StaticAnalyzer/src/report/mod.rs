@@ -1,3 +1,12 @@
 pub mod knowledge_base;
+#[allow(clippy::module_inception)]
 pub mod report;
-pub mod line_counter;
\ No newline at end of file
+pub mod line_counter;
+pub mod json;
+pub mod sarif;
+pub mod fingerprint;
+pub mod baseline;
+pub mod snippet;
+pub mod lifecycle;
+pub mod table;
+pub mod authorization;
\ No newline at end of file
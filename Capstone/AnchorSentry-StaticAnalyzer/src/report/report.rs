@@ -1,30 +1,27 @@
-use crate::report::knowledge_base::PossibleMissingAccountVerificationFinding;
-
 use super::knowledge_base::{
     Finding,
-    MissingInitIfNeededFinding,
-    WrongSpaceAssignmentFinding,
-    MissingRequiredInstructionArgumentFinding,
-    PossibleDivisionByZeroFinding,
     Severity
 };
 use super::line_counter::*;
 
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
 use chrono::Local;
 
 /// ANSI COLORS
-const RESET: &str = "\x1b[0m";
-const BOLD: &str = "\x1b[1m";
-const DIM: &str = "\x1b[2m";
-
-const RED: &str = "\x1b[31m";
-const YELLOW: &str = "\x1b[33m";
-const BLUE: &str = "\x1b[34m";
-const CYAN: &str = "\x1b[36m";
-const MAGENTA: &str = "\x1b[35m";
-const GREEN: &str = "\x1b[32m";
-
-fn sev_color(sev: Severity) -> &'static str {
+pub(super) const RESET: &str = "\x1b[0m";
+pub(super) const BOLD: &str = "\x1b[1m";
+pub(super) const DIM: &str = "\x1b[2m";
+
+pub(super) const RED: &str = "\x1b[31m";
+pub(super) const YELLOW: &str = "\x1b[33m";
+pub(super) const BLUE: &str = "\x1b[34m";
+pub(super) const CYAN: &str = "\x1b[36m";
+pub(super) const MAGENTA: &str = "\x1b[35m";
+pub(super) const GREEN: &str = "\x1b[32m";
+
+pub(super) fn sev_color(sev: Severity) -> &'static str {
     match sev {
         Severity::High => RED,
         Severity::Medium => YELLOW,
@@ -40,21 +37,32 @@ fn sev_icon(sev: Severity) -> &'static str {
     }
 }
 
-fn finding_severity(f: &Finding) -> Severity {
-    match f {
-        Finding::MissingInitIfNeeded(x) => x.rule.severity,
-        Finding::WrongSpaceAssignment(x) => x.rule.severity,
-        Finding::MissingRequiredInstructionArgument(x) => x.rule.severity,
-        Finding::PossibleDivisionByZero(x) => x.rule.severity,
-        Finding::PossibleMissingAccountVerification(x) => x.rule.severity
-    }
+/// How the report is written to stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Sarif,
+}
+
+/// How the summary subcommands (`lifecycle`, `authorization`) write their table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SummaryFormat {
+    #[default]
+    Text,
+    Json,
 }
 
 #[derive(Default)]
 pub struct Report {
     pub findings: Vec<Finding>,
-    pub file_info: Option<FileInfo>,
-    pub file_path: Option<String>,
+    //every analyzed file with its line stats
+    pub files: Vec<(String, FileInfo)>,
+    // file text by path, for rendering snippets
+    pub sources: HashMap<String, String>,
+    // the analyzed workspace, or the directory of the analyzed file; SARIF paths are relative to it
+    pub root: Option<PathBuf>,
 }
 
 impl Report {
@@ -62,13 +70,46 @@ impl Report {
         self.findings.push(f);
     }
 
+    /// Findings that were not suppressed
+    pub fn active_findings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|f| !f.is_suppressed())
+    }
+
+    pub fn suppressed_count(&self) -> usize {
+        self.findings.iter().filter(|f| f.is_suppressed()).count()
+    }
+
+    /// Number of (high, medium, low) findings, suppressed ones excluded
+    pub fn severity_counts(&self) -> (usize, usize, usize) {
+        let mut high = 0;
+        let mut medium = 0;
+        let mut low = 0;
+
+        for f in self.active_findings() {
+            match f.severity() {
+                Severity::High => high += 1,
+                Severity::Medium => medium += 1,
+                Severity::Low => low += 1,
+            }
+        }
+        (high, medium, low)
+    }
+
+    /// Whether any finding is at least as severe as `threshold`, i.e `Medium` matches High and Medium
+    pub fn has_findings_at_or_above(&self, threshold: Severity) -> bool {
+        // Severity orders High < Medium < Low
+        self.active_findings().any(|f| f.severity() <= threshold)
+    }
+
     //for implementing line_counter
     pub fn load_file_info<P: AsRef<std::path::Path>>(&mut self, path: P) {
         let path_str = path.as_ref().to_string_lossy().to_string();
         match analyze_file(&path) {
             Ok(info) => {
-                self.file_info = Some(info);
-                self.file_path = Some(path_str);
+                if let Ok(text) = fs::read_to_string(&path) {
+                    self.sources.insert(path_str.clone(), text);
+                }
+                self.files.push((path_str, info));
             }
             Err(e) => {
                 eprintln!("Warning: failed to analyze file stats: {}", e);
@@ -76,6 +117,21 @@ impl Report {
         }
     }
 
+    /// Canonical `root`, the working directory when none was set
+    pub fn source_root(&self) -> PathBuf {
+        let root = self.root.clone().unwrap_or_else(|| PathBuf::from("."));
+        root.canonicalize().unwrap_or(root)
+    }
+
+    pub fn emit(&self, format: OutputFormat) -> anyhow::Result<()> {
+        match format {
+            OutputFormat::Text => self.print(),
+            OutputFormat::Json => println!("{}", self.to_json()?),
+            OutputFormat::Sarif => println!("{}", serde_json::to_string_pretty(&self.to_sarif())?),
+        }
+        Ok(())
+    }
+
     pub fn print(&self) {
         println!("\n{MAGENTA}{BOLD}══════════════════════════════════════════════════════════════");
         println!("        SOLANA STATIC ANALYZER — SECURITY REPORT");
@@ -83,7 +139,10 @@ impl Report {
 
         println!("{DIM}Generated at {}{RESET}\n", Local::now().format("%Y-%m-%d %H:%M:%S"));
 
-        if let (Some(info), Some(path)) = (&self.file_info, &self.file_path) {
+        if self.files.is_empty() {
+            println!("File metadata: <unavailable>\n");
+        }
+        for (path, info) in &self.files {
             println!(" File Analyzed: {}", path);
             println!("   ├─ Lines of Code : {}", info.lines_of_code);
             println!("   ├─ Comments      : {}", info.comments_count);
@@ -91,16 +150,17 @@ impl Report {
             println!("   └─ Total Lines   : {}\n",
                 info.lines_of_code + info.comments_count + info.blank_spaces_count
             );
-        } else {
-            println!("File metadata: <unavailable>\n");
         }
 
         println!("\n{BLUE}{BOLD}══════════════════════════════════════════════════════════════");
         println!("        FINDINGS");
         println!("══════════════════════════════════════════════════════════════{RESET}\n");
 
-        if self.findings.is_empty() {
+        if self.active_findings().next().is_none() {
             println!("{GREEN}{BOLD} No vulnerabilities found!{RESET}\n");
+            if self.suppressed_count() > 0 {
+                println!("{DIM}  {} suppressed finding(s){RESET}\n", self.suppressed_count());
+            }
             return;
         }
 
@@ -113,25 +173,7 @@ impl Report {
     }
 
     fn print_summary(&self) {
-        let mut high = 0;
-        let mut medium = 0;
-        let mut low = 0;
-
-        for f in &self.findings {
-            let sev = match f {
-                Finding::MissingInitIfNeeded(x) => x.rule.severity,
-                Finding::WrongSpaceAssignment(x) => x.rule.severity,
-                Finding::MissingRequiredInstructionArgument(x) => x.rule.severity,
-                Finding::PossibleDivisionByZero(x) => x.rule.severity,
-                Finding::PossibleMissingAccountVerification(x) => x.rule.severity
-            };
-
-            match sev {
-                Severity::High => high += 1,
-                Severity::Medium => medium += 1,
-                Severity::Low => low += 1,
-            }
-        }
+        let (high, medium, low) = self.severity_counts();
 
         println!("{CYAN}{BOLD}Summary:{RESET}");
         println!("{CYAN}──────────────────────────────────────────────{RESET}");
@@ -147,27 +189,21 @@ impl Report {
             "  {}{} Low Severity Issues{RESET}",
             BLUE, low
         );
+        println!(
+            "  {}{} Suppressed{RESET}",
+            DIM, self.suppressed_count()
+        );
         println!("{CYAN}──────────────────────────────────────────────{RESET}\n");
     }
 
     fn print_findings(&self) {
 
-        let mut sorted_findings = self.findings.clone();
+        let mut sorted_findings: Vec<Finding> = self.active_findings().cloned().collect();
 
-        sorted_findings.sort_by(|a, b| {
-            finding_severity(a).cmp(&finding_severity(b))
-        });
+        sorted_findings.sort_by_key(|f| f.severity());
 
         for f in &sorted_findings {
-            match f {
-                Finding::MissingInitIfNeeded(x) => self.print_missing_init_if_needed(x),
-                Finding::WrongSpaceAssignment(x) => self.print_wrong_space_assignment(x),
-                Finding::MissingRequiredInstructionArgument(x) => {
-                    self.print_missing_instruction_arg(x)
-                }
-                Finding::PossibleDivisionByZero(x) => self.print_division_by_zero(x),
-                Finding::PossibleMissingAccountVerification(x) => self.print_missing_account_verification(x)
-            }
+            self.print_finding(f);
         }
     }
 
@@ -184,104 +220,17 @@ impl Report {
         println!("{MAGENTA}────────────────────────────────────────────────────────{RESET}");
     }
 
-    fn print_missing_init_if_needed(&self, x: &MissingInitIfNeededFinding) {
-        Self::header(x.rule.code, x.rule.title, x.rule.severity);
-
-        println!("  {BOLD}Account:{RESET} {}", x.account);
-        println!("  {BOLD}Context:{RESET} {}", x.context);
-        println!("  {BOLD}Line:{RESET} {}", x.line);
-
-        println!("\n  {BOLD}Description:{RESET}");
-        println!("    {}", x.rule.description);
-
-        println!("\n  {BOLD}Recommendation:{RESET}");
-        println!("    {}", x.rule.recommendation);
-
-        if let Some(links) = x.rule.additional_links {
-            println!("\n  {BOLD}Resources:{RESET}");
-            for link in links.split('\n') {
-                println!("     {}", link);
-            }
-        }
-
-        println!();
-    }
-
-    fn print_wrong_space_assignment(&self, x: &WrongSpaceAssignmentFinding) {
+    fn print_finding(&self, x: &Finding) {
         Self::header(x.rule.code, x.rule.title, x.rule.severity);
 
-        println!("  {BOLD}Location Line:{RESET} {}", x.line);
-        println!("  {BOLD}Account:{RESET} {}", x.account);
-        println!("  {BOLD}Expected:{RESET} {}", x.expected);
-        println!("  {BOLD}Actual:{RESET} {}", x.actual);
-
-        println!("\n  {BOLD}Description:{RESET}");
-        println!("    {}", x.rule.description);
-
-        println!("\n  {BOLD}Recommendation:{RESET}");
-        println!("    {}", x.rule.recommendation);
-
-        if let Some(links) = x.rule.additional_links {
-            println!("\n  {BOLD}Resources:{RESET}");
-            for link in links.split('\n') {
-                println!("     {}", link);
-            }
-        }
-        println!();
-    }
-
-    fn print_missing_instruction_arg(&self, x: &MissingRequiredInstructionArgumentFinding) {
-        Self::header(x.rule.code, x.rule.title, x.rule.severity);
-
-        println!("  {BOLD}Function:{RESET} {}", x.fn_name);
-        println!("  {BOLD}Missing Argument:{RESET} {}: {}", x.required_arg_name, x.required_arg_type);
-        println!("  {BOLD}Line:{RESET} {}", x.line);
-
-        println!("\n  {BOLD}Description:{RESET}");
-        println!("    {}", x.rule.description);
-
-        println!("\n  {BOLD}Recommendation:{RESET}");
-        println!("    {}", x.rule.recommendation);
-
-        if let Some(links) = x.rule.additional_links {
-            println!("\n  {BOLD}Resources:{RESET}");
-            for link in links.split('\n') {
-                println!("     {}", link);
-            }
-        }
+        println!("  {BOLD}{}{RESET}", x.message);
+        print!("{}", self.render_snippet(x));
         println!();
-    }
-
-    fn print_division_by_zero(&self, x: &PossibleDivisionByZeroFinding) {
-        Self::header(x.rule.code, x.rule.title, x.rule.severity);
-
-        println!("  {BOLD}Function:{RESET} {}", x.fn_name);
-        println!("  {BOLD}Divisor Variable:{RESET} {}", x.divisor);
-        println!("  {BOLD}Line:{RESET} {}", x.line);
-
-        println!("\n  {BOLD}Description:{RESET}");
-        println!("    {}", x.rule.description);
 
-        println!("\n  {BOLD}Recommendation:{RESET}");
-        println!("    {}", x.rule.recommendation);
-
-        if let Some(links) = x.rule.additional_links {
-            println!("\n  {BOLD}Resources:{RESET}");
-            for link in links.split('\n') {
-                println!("     {}", link);
-            }
+        for (key, value) in &x.details {
+            println!("  {BOLD}{}:{RESET} {}", key, value);
         }
 
-        println!();
-    }
-
-    fn print_missing_account_verification(&self, x: &PossibleMissingAccountVerificationFinding) {
-        Self::header(x.rule.code, x.rule.title, x.rule.severity);
-
-        println!("  {BOLD}Account:{RESET} {}", x.account_name);
-        println!("  {BOLD}Type:{RESET} {}", x.field_type);
-        println!("  {BOLD}Line:{RESET} {}", x.line);
-
         println!("\n  {BOLD}Description:{RESET}");
         println!("    {}", x.rule.description);
 
@@ -0,0 +1,160 @@
+use std::path::Path;
+use serde_json::{Value, json};
+
+use super::knowledge_base::{Location, RuleMetadata, Severity, SuppressionKind};
+use super::report::Report;
+use crate::analyzer::rules::RuleRegistry;
+use crate::analyzer::suppression::relative_path;
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+/// Key of our entry in `partialFingerprints`, bump the suffix if the fingerprint inputs change
+pub const FINGERPRINT_KEY: &str = "anchorSentry/v2";
+
+fn sarif_level(sev: Severity) -> &'static str {
+    match sev {
+        Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low => "note",
+    }
+}
+
+/// GitHub code scanning sorts security results on this 0-10 score
+fn security_severity(sev: Severity) -> &'static str {
+    match sev {
+        Severity::High => "8.0",
+        Severity::Medium => "5.0",
+        Severity::Low => "3.0",
+    }
+}
+
+fn sarif_rule(rule: &RuleMetadata) -> Value {
+    let links: Vec<&str> = rule
+        .additional_links
+        .map(|l| l.split('\n').map(str::trim).filter(|l| !l.is_empty()).collect())
+        .unwrap_or_default();
+
+    let mut markdown = format!("{}\n\n**Recommendation:** {}", rule.description, rule.recommendation);
+    if !links.is_empty() {
+        markdown.push_str("\n\n**Resources:**\n");
+        for link in &links {
+            markdown.push_str(&format!("- {}\n", link));
+        }
+    }
+
+    let mut value = json!({
+        "id": rule.code,
+        "name": rule.title,
+        "shortDescription": { "text": rule.title },
+        "fullDescription": { "text": rule.description },
+        "help": { "text": rule.recommendation, "markdown": markdown },
+        "defaultConfiguration": { "level": sarif_level(rule.severity) },
+        "properties": {
+            "tags": ["security", "solana", "anchor"],
+            "security-severity": security_severity(rule.severity),
+        },
+    });
+    if let Some(first) = links.first() {
+        value["helpUri"] = json!(first);
+    }
+    value
+}
+
+/// Paths relative to `%SRCROOT%` whichever way the analyzed path was given, only files outside it
+/// become absolute `file://` URIs
+fn artifact_location(file: &str, root: &Path) -> Value {
+    let uri = relative_path(root, file).replace('\\', "/");
+    if Path::new(&uri).is_absolute() {
+        json!({ "uri": format!("file://{}", uri) })
+    } else {
+        json!({ "uri": uri.trim_start_matches("./"), "uriBaseId": "%SRCROOT%" })
+    }
+}
+
+fn region(loc: &Location) -> Value {
+    let mut region = json!({ "startLine": loc.line, "startColumn": loc.column });
+    if !loc.is_point() {
+        region["endLine"] = json!(loc.end_line);
+        region["endColumn"] = json!(loc.end_column);
+    }
+    region
+}
+
+fn physical_location(loc: &Location, root: &Path) -> Value {
+    json!({
+        "artifactLocation": artifact_location(&loc.file, root),
+        "region": region(loc),
+    })
+}
+
+impl Report {
+    pub fn to_sarif(&self) -> Value {
+        // the whole built-in catalog, plus any rule that reported without being registered
+        let mut catalog = RuleRegistry::default().metadata();
+        for f in &self.findings {
+            if !catalog.iter().any(|r| r.code == f.rule.code) {
+                catalog.push(f.rule);
+            }
+        }
+        let rules: Vec<Value> = catalog.iter().map(|r| sarif_rule(r)).collect();
+        let root = self.source_root();
+        let fingerprints = self.fingerprints_from(Some(&root));
+
+        let results: Vec<Value> = self
+            .findings
+            .iter()
+            .zip(fingerprints)
+            .map(|(f, fingerprint)| {
+                let rule = f.rule;
+                let rule_index = catalog.iter().position(|r| r.code == rule.code);
+                let mut result = json!({
+                    "ruleId": rule.code,
+                    "ruleIndex": rule_index,
+                    "level": sarif_level(rule.severity),
+                    "message": { "text": f.message },
+                    "locations": [{ "physicalLocation": physical_location(&f.location, &root) }],
+                    "partialFingerprints": { FINGERPRINT_KEY: fingerprint },
+                });
+                if !f.labels.is_empty() {
+                    let related: Vec<Value> = f
+                        .labels
+                        .iter()
+                        .enumerate()
+                        .map(|(id, l)| json!({
+                            "id": id,
+                            "physicalLocation": physical_location(&l.location, &root),
+                            "message": { "text": l.message },
+                        }))
+                        .collect();
+                    result["relatedLocations"] = json!(related);
+                }
+                if let Some(s) = &f.suppression {
+                    let kind = match s.kind {
+                        SuppressionKind::Inline => "inSource",
+                        SuppressionKind::Config | SuppressionKind::Baseline => "external",
+                    };
+                    result["suppressions"] = json!([{ "kind": kind, "justification": s.reason }]);
+                }
+                result
+            })
+            .collect();
+
+        json!({
+            "$schema": SARIF_SCHEMA,
+            "version": SARIF_VERSION,
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "anchor-sentry",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    }
+                },
+                "originalUriBaseIds": {
+                    "%SRCROOT%": { "uri": format!("file://{}/", root.to_string_lossy().replace('\\', "/").trim_end_matches('/')) },
+                },
+                "results": results,
+            }]
+        })
+    }
+}
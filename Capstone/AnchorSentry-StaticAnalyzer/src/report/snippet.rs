@@ -0,0 +1,103 @@
+use super::knowledge_base::{Finding, Location};
+use super::report::{Report, sev_color, BOLD, BLUE, CYAN, RESET};
+
+/// Spans longer than this show their first and last lines around a `...`
+const MAX_SNIPPET_LINES: usize = 4;
+
+impl Report {
+    /// rustc style view of a finding: `--> file:line:col`, the source lines with the
+    /// offending code underlined, then each secondary label with its own snippet
+    pub fn render_snippet(&self, f: &Finding) -> String {
+        let gutter = std::iter::once(&f.location)
+            .chain(f.labels.iter().map(|l| &l.location))
+            .map(|l| l.end_line.max(l.line).to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(gutter);
+
+        let mut out = String::new();
+        out.push_str(&format!("  {pad}{BLUE}{BOLD}-->{RESET} {}\n", location_header(&f.location)));
+        out.push_str(&format!("  {pad} {BLUE}{BOLD}|{RESET}\n"));
+        self.render_block(&mut out, &f.location, '^', sev_color(f.severity()), "", gutter);
+
+        let mut previous_file = &f.location.file;
+        for label in &f.labels {
+            if &label.location.file != previous_file {
+                out.push_str(&format!("  {pad} {BLUE}{BOLD}|{RESET}\n"));
+                out.push_str(&format!("  {pad}{BLUE}{BOLD}:::{RESET} {}\n", location_header(&label.location)));
+                out.push_str(&format!("  {pad} {BLUE}{BOLD}|{RESET}\n"));
+            } else {
+                out.push_str(&format!("  {BLUE}{BOLD}...{RESET}\n"));
+            }
+            self.render_block(&mut out, &label.location, '-', CYAN, &label.message, gutter);
+            previous_file = &label.location.file;
+        }
+        out.push_str(&format!("  {pad} {BLUE}{BOLD}|{RESET}\n"));
+        out
+    }
+
+    fn render_block(&self, out: &mut String, loc: &Location, marker: char, color: &str, label: &str, gutter: usize) {
+        let pad = " ".repeat(gutter);
+        let Some(source) = self.sources.get(&loc.file) else { return };
+        let lines: Vec<&str> = source.lines().collect();
+        let first = loc.line;
+        let last = loc.end_line.max(loc.line);
+        if first == 0 || first > lines.len() {
+            return;
+        }
+        let last = last.min(lines.len());
+
+        let shown: Vec<Option<usize>> = if last - first + 1 > MAX_SNIPPET_LINES {
+            vec![Some(first), Some(first + 1), None, Some(last)]
+        } else {
+            (first..=last).map(Some).collect()
+        };
+
+        for n in shown {
+            let Some(n) = n else {
+                out.push_str(&format!("  {BLUE}{BOLD}...{RESET}\n"));
+                continue;
+            };
+            let text: Vec<char> = lines[n - 1].chars().map(|c| if c == '\t' { ' ' } else { c }).collect();
+            out.push_str(&format!(
+                "  {BLUE}{BOLD}{:>gutter$} |{RESET} {}\n",
+                n,
+                text.iter().collect::<String>()
+            ));
+
+            // only the first and last line of a span get underlined
+            if n != first && n != last {
+                continue;
+            }
+            let (start, end) = underline_range(loc, n, &text);
+            let label = if n == last && !label.is_empty() { format!(" {}", label) } else { String::new() };
+            out.push_str(&format!(
+                "  {pad} {BLUE}{BOLD}|{RESET} {}{color}{BOLD}{}{}{RESET}\n",
+                " ".repeat(start),
+                marker.to_string().repeat(end - start),
+                label
+            ));
+        }
+    }
+}
+
+fn location_header(loc: &Location) -> String {
+    format!("{}:{}:{}", loc.file, loc.line, loc.column)
+}
+
+/// Char range of line `n` covered by `loc`, a point location covers the identifier starting there
+fn underline_range(loc: &Location, n: usize, text: &[char]) -> (usize, usize) {
+    let line_start = text.iter().position(|c| !c.is_whitespace()).unwrap_or(0);
+    let line_end = text.len() - text.iter().rev().take_while(|c| c.is_whitespace()).count();
+
+    let start = if n == loc.line { loc.column.saturating_sub(1) } else { line_start };
+    let start = start.min(text.len());
+    let end = if loc.is_point() {
+        start + text[start..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count()
+    } else if n == loc.end_line {
+        loc.end_column.saturating_sub(1).min(text.len())
+    } else {
+        line_end
+    };
+    (start, end.max(start + 1))
+}
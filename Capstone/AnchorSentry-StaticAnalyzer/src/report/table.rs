@@ -0,0 +1,28 @@
+use super::report::{BOLD, CYAN, RESET};
+
+/// Prints rows under bold headers, columns padded to their widest cell
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for cells in rows {
+        for (w, c) in widths.iter_mut().zip(cells) {
+            *w = (*w).max(c.chars().count());
+        }
+    }
+
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    print_row(&headers, &widths, BOLD);
+    let rule: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
+    println!("{CYAN}  {}{RESET}", rule.join("──┼──"));
+    for cells in rows {
+        print_row(cells, &widths, "");
+    }
+}
+
+fn print_row(cells: &[String], widths: &[usize], style: &str) {
+    let padded: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(c, w)| format!("{}{}", c, " ".repeat(w - c.chars().count())))
+        .collect();
+    println!("  {style}{}{RESET}", padded.join("  │  ").trim_end());
+}
@@ -1,12 +1,15 @@
 // Re-export everything from state.rs so other modules can just do:
 // use crate::state::*;
+#[allow(clippy::module_inception)]
 pub mod state;
 
 pub use state::{
-    AttributeValue,
+    SourceFile,
+    NormalizedProgram,
     NormalizedAccountStruct,
     NormalizedAccountField,
     NormalizedFunctionArgs,
+    NormalizedImpl,
     FnArgs,
     AccountInstructions,
     PossibleDivisionByZeroFindingCheckerInstance,
@@ -1,18 +1,60 @@
 use std::collections::HashMap;
+use syn::{ImplItemFn, ItemConst, ItemEnum, ItemStruct};
+use crate::analyzer::constraints::AccountConstraints;
 
+
+
+/// A parsed source file of a program, kept with its path so findings can point back to it.
 #[derive(Debug, Clone)]
-pub enum AttributeValue {
-    Bool(bool),
-    String(String),
+pub struct SourceFile {
+    pub path: String,
+    // raw text, comments are not part of the AST
+    pub source: String,
+    pub ast: syn::File,
 }
 
+/// Merged view of a program, built from every file reachable from its `lib.rs`.
+#[derive(Debug, Clone, Default)]
+pub struct NormalizedProgram {
+    pub name: String,
+    pub account_structs: Vec<NormalizedAccountStruct>,
+    pub storage_structs: Vec<ItemStruct>,
+    // every other struct and enum, used to size nested fields of storage structs
+    pub data_structs: Vec<ItemStruct>,
+    pub enums: Vec<ItemEnum>,
+    // top level consts, i.e `const MAX_NAME_LEN: usize = 32;`
+    pub consts: Vec<ItemConst>,
+    // file each storage struct, data struct and enum was declared in
+    pub type_files: HashMap<String, String>,
+    pub accounts_with_instructions: Vec<AccountInstructions>,
+    pub functions_args: Vec<NormalizedFunctionArgs>,
+    // inherent `impl<'info> X<'info>` blocks, paired with account structs by name
+    pub impls: Vec<NormalizedImpl>,
+}
 
+impl NormalizedProgram {
+    /// Every inherent impl block of `type_name`
+    pub fn impls_of<'a>(&'a self, type_name: &'a str) -> impl Iterator<Item = &'a NormalizedImpl> {
+        self.impls.iter().filter(move |i| i.type_name == type_name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NormalizedImpl {
+    //i.e `Stake` for `impl<'info> Stake<'info>`
+    pub type_name: String,
+    pub methods: Vec<ImplItemFn>,
+    //file the impl block was found in
+    pub file: String,
+}
 
 #[derive(Debug, Clone)]
 pub struct NormalizedAccountStruct {
     pub name: String,
     pub fields: Vec<NormalizedAccountField>,
-    pub line: usize
+    pub line: usize,
+    //file the struct was found in
+    pub file: String,
 }
 
 //@note: Currently want to use this to represent derive(accounts), hopefully its enough
@@ -29,29 +71,13 @@ pub struct NormalizedAccountField {
     // ["'info", "TokenAccount"] do i need to add 'info, everything uses info
     pub generic_args: Vec<String>,
 
-    // Attributes
-    //hashmap seems to be the closest to python dictionaries
-    // account struct attribites i.e init_if_needed|payer (String), true|signer (AttributeValue)
-    pub attributes: HashMap<String, AttributeValue>, // flattened attributes
-    // Metadata, for line
-    pub line: usize
-}
-
-impl NormalizedAccountField {
-    pub fn contains_attr(&self, key: &str) -> bool {
-        self.attributes.keys().any(|k| k.contains(key))
-    }
-    pub fn has_bool_attribute(&self, key: &str) -> bool {
-        matches!(self.attributes.get(key), Some(AttributeValue::Bool(true)))
-    }
-
-    pub fn has_str_attribute(&self, key: &str) -> Option<&str> {
-        if let Some(AttributeValue::String(s)) = self.attributes.get(key) {
-            Some(s)
-        } else {
-            None
-        }
-    }
+    // Constraints parsed from #[account(...)] i.e init, seeds = [..], has_one = maker
+    pub constraints: AccountConstraints,
+    // `///` doc lines, i.e ["CHECK: only receives lamports"]
+    pub docs: Vec<String>,
+    // Metadata, for line and column (1-based)
+    pub line: usize,
+    pub column: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -59,7 +85,9 @@ pub struct NormalizedFunctionArgs {
     pub name: String,
     pub context: String,
     pub args: Vec<FnArgs>,
-    pub line: usize
+    pub line: usize,
+    pub column: usize,
+    pub file: String,
 }
 
 #[derive(Debug, Clone)]
@@ -68,16 +96,29 @@ pub struct FnArgs {
     pub ty: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AccountInstructions {
     pub ctx_name: String,
-    pub args: Vec<FnArgs>
+    pub args: Vec<FnArgs>,
+    // where the `#[instruction(...)]` attribute is
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct PossibleDivisionByZeroFindingCheckerInstance {
     pub function_name: String,
+    pub file: String,
     pub divisor: String,
-    pub line: usize
+    // `/`, `%`, `checked_div` ...
+    pub operator: String,
+    // the `checked_*` result is unwrapped
+    pub unwrapped: bool,
+    // span of the divisor expression (1-based, end exclusive)
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
 }
 
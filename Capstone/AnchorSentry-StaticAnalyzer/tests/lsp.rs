@@ -0,0 +1,216 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use serde_json::{Value, json};
+use AnchorSentry_StaticAnalyzer::lsp::{Server, path_to_uri, read_message, write_message};
+
+const LIB_RS: &str = r#"use anchor_lang::prelude::*;
+mod state;
+pub use state::*;
+
+#[program]
+pub mod bets {
+    use super::*;
+    pub fn settle(ctx: Context<Settle>) -> Result<()> {
+        let total = ctx.accounts.bet.amount + 1;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, close = user)]
+    pub bet: Account<'info, Bet>,
+}
+"#;
+
+const STATE_RS: &str = r#"use anchor_lang::prelude::*;
+
+#[account]
+pub struct Bet {
+    pub amount: u64,
+}
+
+#[derive(Accounts)]
+pub struct Create<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(init, payer = user, space = 8 + 8)]
+    pub bet: Account<'info, Bet>,
+    pub system_program: Program<'info, System>,
+}
+"#;
+
+/// A program crate with `lib.rs` and `state.rs` in a fresh temp directory
+fn program_crate(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("anchor-sentry-lsp-{}-{}", std::process::id(), name));
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("Cargo.toml"), "[package]\nname = \"bets\"\n").unwrap();
+    fs::write(root.join("src").join("lib.rs"), LIB_RS).unwrap();
+    fs::write(root.join("src").join("state.rs"), STATE_RS).unwrap();
+    root.canonicalize().unwrap()
+}
+
+fn uri(root: &std::path::Path, file: &str) -> String {
+    path_to_uri(&root.join("src").join(file).to_string_lossy())
+}
+
+/// Frames `messages` the way a client does and feeds them through `read_message` to the server
+fn exchange(server: &mut Server, messages: &[Value]) -> Vec<Value> {
+    let mut input = Vec::new();
+    for message in messages {
+        write_message(&mut input, message).unwrap();
+    }
+    let mut reader = Cursor::new(input);
+    let mut replies = Vec::new();
+    while let Some(message) = read_message(&mut reader).unwrap() {
+        replies.extend(server.handle(&message));
+    }
+    replies
+}
+
+/// Codes of the diagnostics last published for `uri`, `None` when nothing was published
+fn published(replies: &[Value], uri: &str) -> Option<Vec<String>> {
+    let params = replies
+        .iter()
+        .filter(|r| r["method"] == "textDocument/publishDiagnostics" && r["params"]["uri"] == uri)
+        .map(|r| &r["params"])
+        .next_back()?;
+    let diagnostics = params["diagnostics"].as_array()?;
+    Some(diagnostics.iter().map(|d| d["code"].as_str().unwrap_or_default().to_string()).collect())
+}
+
+fn did_open(uri: &str, text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": uri, "languageId": "rust", "version": 1, "text": text } },
+    })
+}
+
+fn did_change(uri: &str, text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": { "textDocument": { "uri": uri, "version": 2 }, "contentChanges": [{ "text": text }] },
+    })
+}
+
+#[test]
+fn read_message_parses_headers_and_stops_at_end_of_input() {
+    let body = r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#;
+    let framed = format!("content-length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}", body.len(), body);
+    let mut reader = Cursor::new(framed.into_bytes());
+    let message = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(message["method"], "shutdown");
+    assert!(read_message(&mut reader).unwrap().is_none());
+
+    let mut server = Server::new();
+    let replies = server.handle(&message);
+    assert_eq!(replies, vec![json!({ "jsonrpc": "2.0", "id": 1, "result": null })]);
+}
+
+#[test]
+fn initialize_and_unknown_requests() {
+    let mut server = Server::new();
+    let replies = exchange(&mut server, &[
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "workspace/symbol", "params": {} }),
+    ]);
+    assert_eq!(replies.len(), 2);
+    assert_eq!(replies[0]["result"]["capabilities"]["hoverProvider"], true);
+    assert_eq!(replies[1]["error"]["code"], -32601);
+}
+
+#[test]
+fn a_change_republishes_every_open_file_of_the_crate() {
+    let root = program_crate("republish");
+    let (lib, state) = (uri(&root, "lib.rs"), uri(&root, "state.rs"));
+    let mut server = Server::new();
+
+    let opened = exchange(&mut server, &[did_open(&lib, LIB_RS), did_open(&state, STATE_RS)]);
+    assert_eq!(published(&opened, &lib), Some(vec!["M-007".to_string(), "L-004".to_string()]));
+    assert_eq!(published(&opened, &state), Some(Vec::new()));
+
+    // without `close` in lib.rs the `Bet` created in state.rs is never closed
+    let changed = exchange(&mut server, &[did_change(&lib, &LIB_RS.replace("mut, close = user", "mut"))]);
+    assert_eq!(published(&changed, &lib), Some(vec!["L-004".to_string()]));
+    assert_eq!(published(&changed, &state), Some(vec!["L-005".to_string()]));
+
+    let hover = exchange(&mut server, &[json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "textDocument/hover",
+        "params": { "textDocument": { "uri": state }, "position": { "line": 12, "character": 10 } },
+    })]);
+    assert!(hover[0]["result"]["contents"]["value"].as_str().unwrap().contains("L-005"));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn the_program_is_read_from_disk_once_until_a_save() {
+    let root = program_crate("cache");
+    let lib = uri(&root, "lib.rs");
+    let mut server = Server::new();
+    exchange(&mut server, &[did_open(&lib, LIB_RS)]);
+
+    // state.rs isn't open, edits to it on disk are picked up on the next save, not on every change.
+    // `amount` stops being an integer, so `amount + 1` in lib.rs can't overflow anymore
+    fs::write(root.join("src").join("state.rs"), STATE_RS.replace("amount: u64", "amount: Decimal")).unwrap();
+    let changed = exchange(&mut server, &[did_change(&lib, LIB_RS)]);
+    assert!(published(&changed, &lib).unwrap().contains(&"L-004".to_string()));
+
+    let saved = exchange(&mut server, &[json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didSave",
+        "params": { "textDocument": { "uri": lib } },
+    })]);
+    let codes = published(&saved, &lib).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+    assert!(!codes.contains(&"L-004".to_string()), "state.rs was not reloaded after the save: {:?}", codes);
+}
+
+/// Inserts `text` at an LSP `position`, columns are ASCII in these tests
+fn insert_at(source: &str, position: &Value, text: &str) -> String {
+    let (line, character) = (position["line"].as_u64().unwrap() as usize, position["character"].as_u64().unwrap() as usize);
+    let offset: usize = source.split_inclusive('\n').take(line).map(str::len).sum::<usize>() + character;
+    format!("{}{}{}", &source[..offset], text, &source[offset..])
+}
+
+#[test]
+fn the_suppress_action_silences_a_line_with_a_comment() {
+    let root = program_crate("suppress");
+    let lib = uri(&root, "lib.rs");
+    let source = LIB_RS.replace("amount + 1;", "amount + 1; // one more than the stake");
+    let mut server = Server::new();
+    assert!(published(&exchange(&mut server, &[did_open(&lib, &source)]), &lib).unwrap().contains(&"L-004".to_string()));
+
+    let actions = exchange(&mut server, &[json!({
+        "jsonrpc": "2.0",
+        "id": 4,
+        "method": "textDocument/codeAction",
+        "params": {
+            "textDocument": { "uri": lib },
+            "range": { "start": { "line": 8, "character": 0 }, "end": { "line": 8, "character": 80 } },
+            "context": { "diagnostics": [] },
+        },
+    })]);
+    let action = actions[0]["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|a| a["title"] == "Suppress L-004 on this line")
+        .unwrap();
+    let edit = &action["edit"]["changes"][lib.as_str()][0];
+    let fixed = insert_at(&source, &edit["range"]["start"], edit["newText"].as_str().unwrap());
+    assert!(fixed.contains("        // sentry-ignore: L-004 <reason>\n        let total"));
+
+    let changed = exchange(&mut server, &[did_change(&lib, &fixed)]);
+    let codes = published(&changed, &lib).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+    assert!(!codes.contains(&"L-004".to_string()), "still reported after the fix: {:?}", codes);
+}